@@ -2,25 +2,37 @@
 //!
 //! This module parses the device tree blob from memory and converts it into a convenient Rust object, on which you can call various methods to query the device tree
 
-use crate::node::{cpu, RawNode};
+use crate::edit::Tree;
+use crate::header::{self, Header};
+use crate::memory_map::{Allocation, MemoryMap};
+use crate::memory_reservation::{self, MemoryReservations};
+use crate::node::{cpu, Locate as _, RawNode, Warnings};
 use crate::node_name::NameRefError;
-use crate::transmute_slice_down;
+use crate::rc::Rc;
+use crate::walker::{Event, Walker};
+use crate::writer::Writer;
+use crate::{is_aligned_for, transmute_bytes_up, transmute_slice_down};
 use crate::{map::Map, node::root, node_name::NameRef, parse::U32ByteSlice};
 use alloc::boxed::Box;
+use alloc::string::{String, ToString as _};
 use alloc::vec;
-use alloc::{rc::Rc, vec::Vec};
+use alloc::vec::Vec;
+use core::error;
 use core::ffi::CStr;
+use core::fmt::{self, Display, Write as _};
 use core::iter;
-use core::mem;
+use core::mem::{self, ManuallyDrop};
+use core::ptr::{self, NonNull};
+use core::slice;
 
 /// The structure block is composed of a sequence of pieces, each beginning with a token, that is, a big-endian 32-bit integer.
 /// Some tokens are followed by extra data, the format of which is determined by the token value.
 /// All tokens shall be aligned on a 32-bit boundary,
 /// which may require padding bytes (with a value of `0x0`) to be inserted after the previous token’s data.ß
-enum Token<'token> {
+pub(crate) enum Token<'token> {
     /// The `BeginNode` token marks the beginning of a node’s representation.
     /// It shall be followed by the node’s unit name as extra data.
-    BeginNode(NameRef<'token>),
+    BeginNode(&'token [u8]),
     /// The `EndNode` token marks the end of a node’s representation.
     /// This token has no extra data; so it is followed immediately by the next token, which may be any token except FDT_PROP.
     EndNode,
@@ -62,6 +74,23 @@ pub enum TokenError {
     Size,
 }
 
+impl Display for TokenError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::EoF => f.write_str("structure block ended in the middle of a token"),
+            Self::InvalidToken(token) => write!(f, "invalid token {token:#X}"),
+            Self::NodeNameMalformed => f.write_str("node name is not nul-terminated"),
+            Self::NodeNameInvalid(ref error) => Display::fmt(error, f),
+            Self::PropValue => {
+                f.write_str("property value runs past the end of the structure block")
+            }
+            Self::PropName => f.write_str("property name is not a string in the strings block"),
+            Self::Size => f.write_str("property length or name offset does not fit into a `usize`"),
+        }
+    }
+}
+
 impl<'token> Token<'token> {
     /// The discriminant value for a `BeginNode` token
     const BEGIN_NODE: u32 = 0x1;
@@ -75,7 +104,7 @@ impl<'token> Token<'token> {
     const END: u32 = 0x9;
 
     /// Parses a single token out of the byte stream, or fails with an error
-    fn consume_token(
+    pub(crate) fn consume_token(
         bytes: &mut U32ByteSlice<'token>,
         strings: &'token [u8],
     ) -> Result<Self, TokenError> {
@@ -86,10 +115,7 @@ impl<'token> Token<'token> {
                 bytes
                     .consume_c_str()
                     .ok_or(TokenError::NodeNameMalformed)
-                    .and_then(|name| {
-                        NameRef::try_from(name.to_bytes()).map_err(TokenError::NodeNameInvalid)
-                    })
-                    .map(Self::BeginNode)
+                    .map(|name| Self::BeginNode(name.to_bytes()))
             }
             Self::END_NODE => Ok(Self::EndNode),
             Self::PROP => {
@@ -117,7 +143,7 @@ impl<'token> Token<'token> {
     }
 
     /// Creates an iterator over the provided byte stream that produces tokens one at a time, or fails if it encounters an invalid token
-    fn iterate_bytes(
+    pub(crate) fn iterate_bytes(
         mut bytes: U32ByteSlice<'token>,
         strings: &'token [u8],
     ) -> impl Iterator<Item = Result<Self, TokenError>> {
@@ -152,39 +178,378 @@ pub enum DeviceTreeError<'dtb> {
     Node(root::NodeError<'dtb>),
     /// The boot CPU specified was invalid
     BootCpu(u32),
+    /// The memory reservation block was invalid
+    MemoryReservations(memory_reservation::Error),
+    /// More nodes were ended than begun, or the structure block had more than one `End` token
     TooManyEnds,
+    /// A property was found outside of any node
     InvalidProp,
+    /// Nodes were begun and ended out of order
     MismatchedNodes,
+    /// The structure block did not have exactly one root node, as the names of the top-level nodes
     BadRoots(Box<[NameRef<'dtb>]>),
+    /// The root node had a name
     BadRootName(NameRef<'dtb>),
+    /// The structure block ended while the given number of nodes were still open
     BadDepth(usize),
+    /// The blob is not backwards compatible with the version parsed by this crate, as (version, last compatible version)
     NewerVersion((u32, u32)),
+    /// The strings block does not fit within the blob, as (offset, size)
     StringsIndex((usize, usize)),
+    /// The structure block does not fit within the blob, as (offset, size)
     StructIndex((usize, usize)),
+    /// The header was otherwise invalid
+    Header(header::Error),
+    /// A node has more than one property with the same name
+    DuplicateProperty,
+}
+
+impl Display for DeviceTreeError<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Alignment => f.write_str("misaligned blob or block"),
+            Self::Magic => f.write_str("incorrect magic bytes"),
+            Self::Size => f.write_str("some size or offset does not fit into a `usize`"),
+            Self::Index => f.write_str("invalid index"),
+            Self::Token(ref error) => Display::fmt(error, f),
+            Self::EoF => f.write_str("blob ended before parsing completed"),
+            Self::String => f.write_str("invalid UTF-8"),
+            Self::Property => f.write_str("invalid property"),
+            Self::Parsing => f.write_str("invalid value"),
+            Self::InvalidToken(token) => write!(f, "invalid token {token:#X}"),
+            Self::Node(ref error) => Display::fmt(error, f),
+            Self::BootCpu(id) => write!(f, "boot CPU {id:#X} does not exist"),
+            Self::MemoryReservations(ref error) => Display::fmt(error, f),
+            Self::TooManyEnds => f.write_str("more nodes ended than began"),
+            Self::InvalidProp => f.write_str("property outside of any node"),
+            Self::MismatchedNodes => f.write_str("mismatched `BeginNode` and `EndNode` tokens"),
+            Self::BadRoots(ref roots) => {
+                write!(f, "expected exactly one root node, found {}", roots.len())
+            }
+            Self::BadRootName(name) => write!(f, "root node has name `{name}`"),
+            Self::BadDepth(depth) => {
+                write!(f, "structure block ended with {depth} nodes still open")
+            }
+            Self::NewerVersion(versions) => Display::fmt(&header::Error::NewerVersion(versions), f),
+            Self::StringsIndex(block) => Display::fmt(&header::Error::StringsIndex(block), f),
+            Self::StructIndex(block) => Display::fmt(&header::Error::StructIndex(block), f),
+            Self::Header(ref error) => Display::fmt(error, f),
+            Self::DuplicateProperty => f.write_str("duplicate property"),
+        }
+    }
+}
+
+/// An error from parsing a device tree blob, along with where in the blob it occurred
+#[derive(Debug)]
+pub struct ParseError<'dtb> {
+    /// The error that occurred
+    error: DeviceTreeError<'dtb>,
+    /// The path of the node at which the error occurred, if it occurred within the structure block
+    path: Option<String>,
+    /// The name of the property at which the error occurred, if any single property is responsible
+    property: Option<&'dtb CStr>,
+    /// The byte offset, from the start of the structure block, of the token at which the error occurred
+    offset: Option<usize>,
+}
+
+impl<'dtb> ParseError<'dtb> {
+    /// Creates an error that occurred at the token at the given offset, within the node with the given path, and at the given property if any.
+    /// The path includes the root node, so is empty only if the error occurred before the root node began
+    fn at(
+        error: DeviceTreeError<'dtb>,
+        path: &[NameRef<'_>],
+        property: Option<&'dtb CStr>,
+        offset: usize,
+    ) -> Self {
+        Self {
+            error,
+            path: (!path.is_empty()).then(|| {
+                if path.len() == 1 {
+                    return String::from("/");
+                }
+                path.iter().skip(1).fold(String::new(), |mut path, name| {
+                    write!(path, "/{name}").expect("Writing to a `String` should never fail");
+                    path
+                })
+            }),
+            property,
+            offset: Some(offset),
+        }
+    }
+
+    /// Creates an error that occurred in parsing a node, which is located by walking the blob to the given offset of the responsible node,
+    /// and from there to the given property of that node, if any
+    fn locate(
+        error: DeviceTreeError<'dtb>,
+        dtb: &'dtb [u8],
+        node_offset: usize,
+        property: Option<&'dtb CStr>,
+    ) -> Self {
+        let mut located = Self {
+            error,
+            path: None,
+            property,
+            offset: Some(node_offset),
+        };
+        let Ok(mut walker) = Walker::from_byte_slice(dtb) else {
+            return located;
+        };
+        while walker.offset() < node_offset {
+            if !matches!(walker.next(), Some(Ok(_))) {
+                return located;
+            }
+        }
+        if walker.offset() != node_offset || !matches!(walker.next(), Some(Ok(Event::BeginNode(_))))
+        {
+            return located;
+        }
+        located.path = Some(walker.path().to_string());
+        // Properties precede children, so the property must be found before any other node begins or ends
+        loop {
+            let offset = walker.offset();
+            match walker.next() {
+                Some(Ok(Event::Property(found))) if Some(found.name()) == property => {
+                    located.offset = Some(offset);
+                    return located;
+                }
+                Some(Ok(Event::Property(_))) => {}
+                _ => return located,
+            }
+        }
+    }
+
+    /// Returns the error that occurred
+    #[must_use]
+    #[inline]
+    pub const fn error(&self) -> &DeviceTreeError<'dtb> {
+        &self.error
+    }
+
+    /// Returns the path of the node at which the error occurred, such as `/soc/serial@7e201000`, if it occurred within the structure block
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Returns the name of the property at which the error occurred, if any single property is responsible
+    #[must_use]
+    #[inline]
+    pub const fn property(&self) -> Option<&'dtb CStr> {
+        self.property
+    }
+
+    /// Returns the byte offset, from the start of the structure block, of the token at which the error occurred, if known.
+    /// This is the offset of the responsible property if found, or otherwise of the `BeginNode` of the responsible node
+    #[must_use]
+    #[inline]
+    pub const fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// Discards the location of the error, returning only the error that occurred
+    #[must_use]
+    #[inline]
+    pub fn into_error(self) -> DeviceTreeError<'dtb> {
+        self.error
+    }
+}
+
+impl<'dtb> From<DeviceTreeError<'dtb>> for ParseError<'dtb> {
+    #[inline]
+    fn from(value: DeviceTreeError<'dtb>) -> Self {
+        Self {
+            error: value,
+            path: None,
+            property: None,
+            offset: None,
+        }
+    }
+}
+
+impl Display for ParseError<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref path) = self.path {
+            write!(f, "`{path}`: ")?;
+        }
+        if let Some(property) = self.property {
+            write!(f, "property `{}`: ", property.to_string_lossy())?;
+        }
+        Display::fmt(&self.error, f)?;
+        if let Some(offset) = self.offset {
+            write!(f, " (at structure block offset {offset:#X})")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ParseError<'_> {}
+
+/// Options that control how strictly a device tree blob is checked against the specification while parsing
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// Whether or not nodes that do not conform to the specification are tolerated, rather than failing the parse
+    lenient: bool,
+}
+
+impl ParseOptions {
+    /// Options that reject any node that does not conform to the specification. This is the default
+    #[must_use]
+    #[inline]
+    pub const fn strict() -> Self {
+        Self { lenient: false }
+    }
+
+    /// Options that tolerate nodes that do not conform to the specification, as is common in firmware-provided blobs.
+    ///
+    /// Invalid properties of the root node are ignored, CPU nodes may lack a `device_type`, dangling aliases are skipped,
+    /// and memory, reserved memory, and chosen nodes that fail to parse fall back to generic device nodes.
+    /// Each tolerated error is reported by `DeviceTree::warnings` instead of failing the parse
+    #[must_use]
+    #[inline]
+    pub const fn lenient() -> Self {
+        Self { lenient: true }
+    }
+
+    /// Returns whether or not nodes that do not conform to the specification are tolerated
+    #[must_use]
+    #[inline]
+    pub const fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+}
+
+impl From<header::Error> for DeviceTreeError<'_> {
+    #[inline]
+    fn from(value: header::Error) -> Self {
+        match value {
+            header::Error::EoF => Self::EoF,
+            header::Error::Magic => Self::Magic,
+            header::Error::NewerVersion(versions) => Self::NewerVersion(versions),
+            header::Error::Size => Self::Size,
+            header::Error::Alignment => Self::Alignment,
+            header::Error::StructIndex(block) => Self::StructIndex(block),
+            header::Error::StringsIndex(block) => Self::StringsIndex(block),
+            header::Error::ReservationsIndex(_) | header::Error::Overlap => Self::Header(value),
+        }
+    }
+}
+
+/// The blocks of a device tree blob, as located by the fields of its header
+pub(crate) struct Blocks<'dtb> {
+    /// The header of the blob
+    pub(crate) header: Header,
+    /// The memory reservation block, extending to the start of the next block
+    pub(crate) memory_reservations: &'dtb [u64],
+    /// The structure block
+    pub(crate) structure: U32ByteSlice<'dtb>,
+    /// The strings block
+    pub(crate) strings: &'dtb [u8],
+}
+
+impl<'dtb> Blocks<'dtb> {
+    /// Reads the header of a device tree blob and locates each of its blocks.
+    ///
+    /// The blob must be aligned to a `u64` boundary
+    #[expect(clippy::unwrap_in_result, reason = "Checks should never fail")]
+    pub(crate) fn locate(dtb: &'dtb [u8]) -> Result<Self, DeviceTreeError<'dtb>> {
+        let header = Header::parse(dtb)?;
+        let dt_bytes = dtb.get(..header.total_size()).ok_or(DeviceTreeError::EoF)?;
+
+        // The header has already checked that each block is in bounds and aligned relative to the start of the blob
+        let memory_reservations = dt_bytes
+            .get(header.memory_reservations_offset()..header.memory_reservations_end())
+            .and_then(transmute_bytes_up)
+            .ok_or(DeviceTreeError::Alignment)?;
+
+        let structure = U32ByteSlice::new(
+            dt_bytes
+                .get(
+                    header.structure_offset()
+                        ..header
+                            .structure_offset()
+                            .wrapping_add(header.structure_size()),
+                )
+                .and_then(transmute_bytes_up)
+                .ok_or(DeviceTreeError::Alignment)?,
+            header.structure_size(),
+        )
+        .expect("Length should be correct");
+
+        let strings = dt_bytes
+            .get(
+                header.strings_offset()
+                    ..header.strings_offset().wrapping_add(header.strings_size()),
+            )
+            .ok_or_else(|| {
+                DeviceTreeError::StringsIndex((header.strings_offset(), header.strings_size()))
+            })?;
+
+        Ok(Self {
+            header,
+            memory_reservations,
+            structure,
+            strings,
+        })
+    }
+}
+
+/// Copies a device tree blob of any alignment, such as one read from a file, into memory aligned to a `u64` boundary as required for parsing.
+/// Any bytes past the end of the blob are zeroed
+#[must_use]
+#[inline]
+#[expect(
+    clippy::host_endian_bytes,
+    reason = "The bytes of the blob are copied as-is, not interpreted"
+)]
+pub fn copy_aligned(bytes: &[u8]) -> Box<[u64]> {
+    bytes
+        .chunks(mem::size_of::<u64>())
+        .map(|chunk| {
+            let mut word = [0; mem::size_of::<u64>()];
+            word.iter_mut()
+                .zip(chunk)
+                .for_each(|(byte, &chunk_byte)| *byte = chunk_byte);
+            u64::from_ne_bytes(word)
+        })
+        .collect()
 }
 
 #[derive(Debug)]
 pub struct DeviceTree<'dtb> {
     /// The root node of the device tree itself
     root: root::Node<'dtb>,
-    /// This field shall contain the version of the devicetree data structure.
-    /// The version is 17 if using the structure as parsed in this crate.
-    /// An `DTSpec` boot program may provide the devicetree of a later version,
-    /// in which case this field shall contain the version number defined in whichever later document gives the details of that version.
-    ///
-    /// Note: The version is with respect to the binary structure of the device tree, not its content.
-    version: u32,
-    /// This field shall contain the lowest version of the devicetree data structure with which the version used is backwards compatible.
-    /// So, for the structure as parsed in this crate (version 17),
-    /// this field shall contain 16 because version 17 is backwards compatible with version 16,
-    /// but not earlier versions.
-    /// A `DTSpec` boot program should provide a devicetree in a format which is backwards compatible with version 16,
-    /// and thus this field shall always contain 16.
-    last_compatible_version: u32,
+    /// The header of the blob
+    header: Header,
     /// The system's boot CPU
     boot_cpu: Rc<cpu::Node<'dtb>>,
+    /// The regions of physical memory reserved by the memory reservation block
+    memory_reservations: MemoryReservations,
+    /// The raw structure block, retained for serialization
+    structure: U32ByteSlice<'dtb>,
+    /// The raw strings block, retained for serialization
+    strings: &'dtb [u8],
+    /// The errors tolerated while parsing leniently
+    warnings: Box<[ParseError<'dtb>]>,
 }
 
+// With the `sync` feature, the whole node graph must be shareable between threads
+#[cfg(feature = "sync")]
+const _: () = {
+    /// Fails to compile unless the given type is `Send` and `Sync`
+    const fn assert_send_sync<T>()
+    where
+        T: Send + Sync,
+    {
+    }
+    assert_send_sync::<DeviceTree<'static>>();
+    assert_send_sync::<OwnedDeviceTree>();
+    assert_send_sync::<ParseError<'static>>();
+    assert_send_sync::<OwnedDeviceTreeError>();
+};
+
 impl<'dtb> DeviceTree<'dtb> {
     /// The version of the DTB that we are parsing.
     /// The `last_compatible_version` should be no greater than this.
@@ -193,136 +558,146 @@ impl<'dtb> DeviceTree<'dtb> {
     ///
     /// # Errors
     /// Returns an error if any part of the parsing process fails.
-    /// See `DeviceTreeError` and associated errors for specific error conditions that are caught
-    #[expect(clippy::unwrap_in_result, reason = "Checks should never fail")]
-    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
-    #[expect(clippy::too_many_lines)]
+    /// See `ParseError`, `DeviceTreeError`, and associated errors for specific error conditions that are caught
+    #[inline]
+    pub fn from_bytes(dtb: &'dtb [u64]) -> Result<Self, ParseError<'dtb>> {
+        Self::from_bytes_with(dtb, ParseOptions::strict())
+    }
+
+    /// Parses a device tree blob located at some point in memory, as controlled by the given options.
+    ///
+    /// # Errors
+    /// Returns an error if any part of the parsing process fails.
+    /// See `ParseError`, `DeviceTreeError`, and associated errors for specific error conditions that are caught
+    #[inline]
+    pub fn from_bytes_with(
+        dtb: &'dtb [u64],
+        options: ParseOptions,
+    ) -> Result<Self, ParseError<'dtb>> {
+        // SAFETY: It is safe to transmute a `u64` to `u8`s
+        Self::from_byte_slice_with(unsafe { transmute_slice_down(dtb) }, options)
+    }
+
+    /// Parses a device tree blob located at the given pointer, whose size is read from the `totalsize` field of its header.
+    ///
+    /// This is intended for blobs handed over by firmware, such as in `x0` on `AArch64`
+    ///
+    /// # Errors
+    /// Returns an error if the pointer is not aligned to a `u64` boundary, or if any part of the parsing process fails.
+    /// See `ParseError`, `DeviceTreeError`, and associated errors for specific error conditions that are caught
+    ///
+    /// # Safety
+    /// The pointer must point to a device tree blob, or at least to 8 readable bytes.
+    /// If the blob has a valid magic number, the entirety of the `totalsize` bytes indicated by its header must be readable,
+    /// and must not be mutated for the duration of `'dtb`
     #[inline]
-    pub fn from_bytes(dtb: &'dtb [u64]) -> Result<Self, DeviceTreeError<'dtb>> {
+    pub unsafe fn from_ptr(dtb: *const u8) -> Result<Self, ParseError<'dtb>> {
+        // SAFETY: The caller upholds the same requirements
+        unsafe { Self::from_ptr_with(dtb, ParseOptions::strict()) }
+    }
+
+    /// Parses a device tree blob located at the given pointer, as controlled by the given options.
+    /// The size of the blob is read from the `totalsize` field of its header
+    ///
+    /// # Errors
+    /// Returns an error if the pointer is not aligned to a `u64` boundary, or if any part of the parsing process fails.
+    /// See `ParseError`, `DeviceTreeError`, and associated errors for specific error conditions that are caught
+    ///
+    /// # Safety
+    /// The same requirements as for `from_ptr` apply
+    #[inline]
+    pub unsafe fn from_ptr_with(
+        dtb: *const u8,
+        options: ParseOptions,
+    ) -> Result<Self, ParseError<'dtb>> {
         /// The magic bytes located at the start of the device tree
         const FDT_HEADER_MAGIC: u32 = 0xD00D_FEED;
 
-        let binding = dtb.first().ok_or(DeviceTreeError::EoF)?.to_ne_bytes();
-        let mut magic_and_size = binding.array_chunks::<{ mem::size_of::<u32>() }>();
-
-        // This field shall contain the value 0xd00dfeed (big-endian).
-        let fdt_header_magic = u32::from_be_bytes(
-            *magic_and_size
-                .next()
-                .expect("Should be exactly two elements in the iterator"),
-        );
-        if fdt_header_magic != FDT_HEADER_MAGIC {
-            return Err(DeviceTreeError::Magic);
+        if !is_aligned_for::<u64>(dtb) {
+            return Err(DeviceTreeError::Alignment.into());
         }
-
-        // This field shall contain the total size in bytes of the devicetree data structure. This size shall encompass all sections of the structure: the header, the memory reservation block, structure block and strings block, as well as any free space gaps between the blocks or after the final block.
-        let dt_size = usize::try_from(u32::from_be_bytes(
-            *magic_and_size
-                .next()
-                .expect("Should be exactly two elements in the iterator"),
-        ))
-        .map_err(|_err| DeviceTreeError::Size)?;
-
-        let dt_bytes = dtb
-            .get(0..dt_size.div_ceil(mem::size_of::<u64>()))
-            .ok_or(DeviceTreeError::EoF)?;
-        // SAFETY: It is safe to transmute a `u64` to `u32`s
-        let dt_bytes_u32: &[u32] = unsafe { transmute_slice_down(dt_bytes) };
-
-        let mut dt_header =
-            U32ByteSlice::new(dt_bytes_u32.get(0..10).ok_or(DeviceTreeError::EoF)?, 40)
-                .expect("Length should be correct");
-
-        dt_header.consume_u32(); // Magic, already checked
-        dt_header.consume_u32(); // Size, already read
-
-        // This field shall contain the offset in bytes of the structure block from the beginning of the header.
-        let dt_struct_offset =
-            usize::try_from(dt_header.consume_u32().ok_or(DeviceTreeError::EoF)?)
-                .map_err(|_err| DeviceTreeError::Size)?;
-
-        // This field shall contain the offset in bytes of the strings block from the beginning of the header.
-        let dt_strings_offset =
-            usize::try_from(dt_header.consume_u32().ok_or(DeviceTreeError::EoF)?)
-                .map_err(|_err| DeviceTreeError::Size)?;
-
-        // This field shall contain the offset in bytes of the memory reservation block from the beginning of the header.
-        let mem_rsvmap_offset =
-            usize::try_from(dt_header.consume_u32().ok_or(DeviceTreeError::EoF)?)
-                .map_err(|_err| DeviceTreeError::Size)?;
-
-        let version = dt_header.consume_u32().ok_or(DeviceTreeError::EoF)?;
-
-        let last_compatible_version = dt_header.consume_u32().ok_or(DeviceTreeError::EoF)?;
-
-        if last_compatible_version > Self::VERSION_PARSED {
-            return Err(DeviceTreeError::NewerVersion((
-                version,
-                last_compatible_version,
-            )));
+        // SAFETY: The caller promises that the magic and size fields of the header are readable
+        let [magic_0, magic_1, magic_2, magic_3, size_0, size_1, size_2, size_3] =
+            unsafe { dtb.cast::<[u8; 8]>().read() };
+        if u32::from_be_bytes([magic_0, magic_1, magic_2, magic_3]) != FDT_HEADER_MAGIC {
+            return Err(DeviceTreeError::Magic.into());
         }
-
-        // This field shall contain the physical ID of the system’s boot CPU. It shall be identical to the physical ID given in the `reg` property of that CPU node within the devicetree.
-        let boot_cpuid_phys = dt_header.consume_u32().ok_or(DeviceTreeError::EoF)?;
-
-        // This field shall contain the length in bytes of the strings block section of the devicetree blob.
-        let dt_strings_size = usize::try_from(dt_header.consume_u32().ok_or(DeviceTreeError::EoF)?)
-            .map_err(|_err| DeviceTreeError::Size)?;
-        // This field shall contain the length in bytes of the structure block section of the devicetree blob.
-        let dt_struct_size = usize::try_from(dt_header.consume_u32().ok_or(DeviceTreeError::EoF)?)
+        let size = usize::try_from(u32::from_be_bytes([size_0, size_1, size_2, size_3]))
             .map_err(|_err| DeviceTreeError::Size)?;
 
-        // Enforce alignment of the dt_struct to its proper size
-        if dt_struct_offset % mem::size_of::<u32>() != 0
-            || dt_struct_size % mem::size_of::<u32>() != 0
-        {
-            return Err(DeviceTreeError::Alignment);
-        }
-        let dt_struct_index = dt_struct_offset.div_ceil(4);
-        let dt_struct_elems = dt_struct_size.div_ceil(4);
+        // SAFETY: The caller promises that the whole blob is readable and immutable for `'dtb`
+        Self::from_byte_slice_with(unsafe { slice::from_raw_parts(dtb, size) }, options)
+    }
 
-        let dt_struct = U32ByteSlice::new(
-            dt_bytes_u32
-                .get(
-                    dt_struct_index
-                        ..dt_struct_index
-                            .checked_add(dt_struct_elems)
-                            .ok_or(DeviceTreeError::Size)?,
-                )
-                .ok_or(DeviceTreeError::StructIndex((
-                    dt_struct_offset,
-                    dt_struct_size,
-                )))?,
-            dt_struct_size,
-        )
-        .expect("Length should be correct");
+    /// Parses a device tree blob located in the given bytes.
+    ///
+    /// The bytes must be aligned to a `u64` boundary, as required of device tree blobs;
+    /// `copy_aligned` can be used to copy a blob of unknown alignment, such as one read from a file, into suitably aligned memory
+    ///
+    /// # Errors
+    /// Returns an error if the bytes are not properly aligned, or if any part of the parsing process fails.
+    /// See `ParseError`, `DeviceTreeError`, and associated errors for specific error conditions that are caught
+    #[inline]
+    pub fn from_byte_slice(dtb: &'dtb [u8]) -> Result<Self, ParseError<'dtb>> {
+        Self::from_byte_slice_with(dtb, ParseOptions::strict())
+    }
 
-        // SAFETY: Transmuting a `u64` to multiple `u8`s is valid
-        let dt_strings = unsafe { transmute_slice_down(dt_bytes) }
-            .get(
-                dt_strings_offset
-                    ..dt_strings_offset
-                        .checked_add(dt_strings_size)
-                        .ok_or(DeviceTreeError::Size)?,
-            )
-            .ok_or(DeviceTreeError::StringsIndex((
-                dt_strings_offset,
-                dt_strings_size,
-            )))?;
+    /// Parses a device tree blob located in the given bytes, as controlled by the given options.
+    ///
+    /// The bytes must be aligned to a `u64` boundary, as for `from_byte_slice`
+    ///
+    /// # Errors
+    /// Returns an error if the bytes are not properly aligned, or if any part of the parsing process fails.
+    /// See `ParseError`, `DeviceTreeError`, and associated errors for specific error conditions that are caught
+    #[expect(clippy::unwrap_in_result, reason = "Checks should never fail")]
+    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+    #[expect(clippy::too_many_lines, reason = "Each token is handled in sequence")]
+    #[inline]
+    pub fn from_byte_slice_with(
+        dtb: &'dtb [u8],
+        options: ParseOptions,
+    ) -> Result<Self, ParseError<'dtb>> {
+        if !is_aligned_for::<u64>(dtb.as_ptr()) {
+            return Err(DeviceTreeError::Alignment.into());
+        }
+        let Blocks {
+            header,
+            memory_reservations: mem_rsvmap,
+            structure: dt_struct,
+            strings: dt_strings,
+        } = Blocks::locate(dtb)?;
 
         let mut properties = Vec::new();
         let mut children = vec![Vec::new()];
         let mut names = Vec::new();
-        let mut device_tree = Err(DeviceTreeError::EoF);
-        for token in Token::iterate_bytes(dt_struct, dt_strings) {
-            match token.map_err(DeviceTreeError::Token)? {
+        let mut offsets = Vec::new();
+        let boot_cpuid_phys = header.boot_cpuid_phys();
+        let mut device_tree = Err(DeviceTreeError::EoF.into());
+        let mut remaining = dt_struct;
+        while !remaining.is_empty() {
+            let offset = dt_struct.len_bytes().wrapping_sub(remaining.len_bytes());
+            let token = Token::consume_token(&mut remaining, dt_strings)
+                .map_err(|err| ParseError::at(DeviceTreeError::Token(err), &names, None, offset))?;
+            match token {
                 Token::BeginNode(name) => {
+                    let name = NameRef::try_from(name).map_err(|err| {
+                        ParseError::at(
+                            DeviceTreeError::Token(TokenError::NodeNameInvalid(err)),
+                            &names,
+                            None,
+                            offset,
+                        )
+                    })?;
                     properties.push(Map::new());
                     children.push(Vec::new());
                     names.push(name);
+                    offsets.push(offset);
                 }
                 Token::EndNode => {
-                    let name = names.pop().ok_or(DeviceTreeError::TooManyEnds)?;
+                    let at = |error| ParseError::at(error, &[], None, offset);
+                    let name = names
+                        .pop()
+                        .ok_or_else(|| at(DeviceTreeError::TooManyEnds))?;
                     let node = RawNode::new(
                         children
                             .pop()
@@ -330,32 +705,43 @@ impl<'dtb> DeviceTree<'dtb> {
                         properties
                             .pop()
                             .expect("Properties, children, and names should all be in sync"),
+                        offsets
+                            .pop()
+                            .expect("Properties, children, and names should all be in sync"),
                     );
                     children
                         .last_mut()
-                        .ok_or(DeviceTreeError::TooManyEnds)?
+                        .ok_or_else(|| at(DeviceTreeError::TooManyEnds))?
                         .push((name, node));
                 }
                 Token::Prop(name, value) => {
                     if properties
                         .last_mut()
-                        .ok_or(DeviceTreeError::InvalidProp)?
+                        .ok_or_else(|| {
+                            ParseError::at(DeviceTreeError::InvalidProp, &names, Some(name), offset)
+                        })?
                         .insert(name, value)
                         .is_some()
                     {
                         // Duplicate property is bad
-                        return Err(DeviceTreeError::Parsing);
+                        return Err(ParseError::at(
+                            DeviceTreeError::DuplicateProperty,
+                            &names,
+                            Some(name),
+                            offset,
+                        ));
                     }
                 }
                 Token::Nop => {}
                 Token::End => {
+                    let at = |error| ParseError::at(error, &names, None, offset);
                     if device_tree.is_ok() {
-                        return Err(DeviceTreeError::TooManyEnds);
+                        return Err(at(DeviceTreeError::TooManyEnds));
                     }
 
                     // The depth should be exactly one at the end, just the root node left to parse
                     if children.len() != 1 {
-                        return Err(DeviceTreeError::BadDepth(children.len()));
+                        return Err(at(DeviceTreeError::BadDepth(children.len())));
                     }
 
                     let mut roots = children
@@ -364,9 +750,9 @@ impl<'dtb> DeviceTree<'dtb> {
 
                     // There should be exactly one root
                     if roots.len() != 1 {
-                        return Err(DeviceTreeError::BadRoots(
+                        return Err(at(DeviceTreeError::BadRoots(
                             roots.into_iter().map(|(name, _)| name).collect(),
-                        ));
+                        )));
                     }
 
                     let (name, root) = roots
@@ -375,10 +761,30 @@ impl<'dtb> DeviceTree<'dtb> {
 
                     // The full path to the root node is /
                     if !<&str>::from(name.node_name()).is_empty() || name.unit_address().is_some() {
-                        return Err(DeviceTreeError::BadRootName(name));
+                        return Err(ParseError::at(
+                            DeviceTreeError::BadRootName(name),
+                            &[],
+                            None,
+                            root.offset,
+                        ));
                     }
 
-                    let root: root::Node = root.try_into().map_err(DeviceTreeError::Node)?;
+                    let root_offset = root.offset;
+                    let locate = |err: root::NodeError<'dtb>| {
+                        let node_offset = err.node_offset().unwrap_or(root_offset);
+                        let property = err.property();
+                        ParseError::locate(DeviceTreeError::Node(err), dtb, node_offset, property)
+                    };
+                    let mut warnings = Vec::new();
+                    let root = root::Node::parse(
+                        root,
+                        &mut if options.lenient {
+                            Warnings::lenient(&mut warnings)
+                        } else {
+                            Warnings::strict()
+                        },
+                    )
+                    .map_err(locate)?;
 
                     let boot_cpu = Rc::clone(
                         root.cpus()
@@ -387,9 +793,13 @@ impl<'dtb> DeviceTree<'dtb> {
                     );
                     device_tree = Ok(Self {
                         root,
-                        version,
-                        last_compatible_version,
+                        header,
                         boot_cpu,
+                        memory_reservations: MemoryReservations::try_from(mem_rsvmap)
+                            .map_err(DeviceTreeError::MemoryReservations)?,
+                        structure: dt_struct,
+                        strings: dt_strings,
+                        warnings: warnings.into_iter().map(locate).collect(),
                     });
                 }
             }
@@ -413,13 +823,264 @@ impl<'dtb> DeviceTree<'dtb> {
     #[must_use]
     #[inline]
     pub const fn version(&self) -> u32 {
-        self.version
+        self.header.version()
+    }
+
+    /// Returns the header of the blob that this device tree was parsed from
+    #[must_use]
+    #[inline]
+    pub const fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns the errors that were tolerated while parsing leniently, in the order they were encountered.
+    /// This is always empty when parsing strictly
+    #[must_use]
+    #[inline]
+    pub const fn warnings(&self) -> &[ParseError<'dtb>] {
+        &self.warnings
+    }
+
+    /// Returns the regions of physical memory listed in the memory reservation block, which shall not be used for general memory allocations
+    #[must_use]
+    #[inline]
+    pub const fn memory_reservations(&self) -> &MemoryReservations {
+        &self.memory_reservations
+    }
+
+    /// Computes the memory that is free for general use: every region of the `/memory` nodes,
+    /// less the memory reservation block and the static allocations of the `/reserved-memory` node
+    #[must_use]
+    #[inline]
+    pub fn memory_map(&self) -> MemoryMap<'dtb> {
+        MemoryMap::new(&self.root, &self.memory_reservations)
+    }
+
+    /// Computes the memory that is free for general use, as in `memory_map`, and then places every dynamic allocation of the `/reserved-memory` node within it,
+    /// respecting their `alignment` and `alloc-ranges`.
+    /// Reservations are placed in order of their unit names, rather than the order they appear in the devicetree
+    #[must_use]
+    #[inline]
+    pub fn allocate_reserved_memory(&self) -> Allocation<'dtb> {
+        MemoryMap::new(&self.root, &self.memory_reservations).allocate_dynamic(&self.root)
+    }
+
+    /// Serializes this device tree back into a flattened device tree blob.
+    ///
+    /// The emitted blob contains the same memory reservations, nodes, and properties as the one this tree was parsed from,
+    /// with any `Nop` tokens and free space removed and the strings block deduplicated
+    #[must_use]
+    #[inline]
+    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        for token in Token::iterate_bytes(self.structure, self.strings) {
+            match token.expect("The structure block should have been validated while parsing") {
+                Token::BeginNode(name) => writer.begin_node(name),
+                Token::EndNode => writer.end_node(),
+                Token::Prop(name, value) => writer.property(name, value.into()),
+                Token::Nop | Token::End => Ok(()),
+            }
+            .expect("A parsed tree should always be valid to serialize");
+        }
+        writer
+            .finish(self.memory_reservations.entries(), self.boot_cpu.reg)
+            .expect("A parsed tree should always be valid to serialize")
+    }
+
+    /// Converts this device tree into an editable tree, with the same memory reservations, nodes, and properties
+    #[must_use]
+    #[inline]
+    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+    pub fn to_tree(&self) -> Tree {
+        Tree::from_structure(
+            self.structure,
+            self.strings,
+            self.memory_reservations.entries(),
+            self.boot_cpu.reg,
+        )
+        .expect("The structure block should have been validated while parsing")
     }
 
     /// Returns the last compatible version of the device tree parsed; should be at least `VERSION_PARSED` for the parsing code in this crate
     #[must_use]
     #[inline]
     pub const fn last_compatible_version(&self) -> u32 {
-        self.last_compatible_version
+        self.header.last_compatible_version()
     }
 }
+
+/// A device tree that owns the blob it was parsed from, so that it does not borrow from any external buffer.
+///
+/// This allows a tree to be held in a long-lived struct or a `static`, at the cost of keeping the whole blob alive alongside it
+pub struct OwnedDeviceTree {
+    /// The tree parsed from `storage`, whose borrows actually only live as long as `storage`.
+    /// It is dropped before `storage` is freed
+    tree: ManuallyDrop<DeviceTree<'static>>,
+    /// The blob that the tree was parsed from, which is never mutated or moved while the tree exists
+    storage: NonNull<[u64]>,
+}
+
+/// An error from parsing an `OwnedDeviceTree`, which keeps the blob alive for the error to borrow from
+pub struct OwnedDeviceTreeError {
+    /// The error from parsing `storage`, whose borrows actually only live as long as `storage`.
+    /// It is dropped before `storage` is freed
+    error: ManuallyDrop<Box<ParseError<'static>>>,
+    /// The blob that failed to parse, which is never mutated or moved while the error exists
+    storage: NonNull<[u64]>,
+}
+
+impl OwnedDeviceTree {
+    /// Parses the given blob, taking ownership of it
+    ///
+    /// # Errors
+    /// Returns an error if any part of the parsing process fails, from which the blob can be recovered
+    #[inline]
+    pub fn new(blob: Box<[u64]>) -> Result<Self, OwnedDeviceTreeError> {
+        Self::new_with(blob, ParseOptions::strict())
+    }
+
+    /// Parses the given blob with the given options, taking ownership of it
+    ///
+    /// # Errors
+    /// Returns an error if any part of the parsing process fails and is not tolerated by `options`, from which the blob can be recovered
+    #[inline]
+    pub fn new_with(blob: Box<[u64]>, options: ParseOptions) -> Result<Self, OwnedDeviceTreeError> {
+        let storage = NonNull::from(Box::leak(blob));
+        // SAFETY: The storage is valid and initialized because it comes from a `Box`.
+        // It is only freed after the tree or error borrowing from it has been dropped, and is never mutated until then,
+        // so the borrow is valid for as long as it is used, despite being nominally `'static`
+        match DeviceTree::from_bytes_with(unsafe { storage.as_ref() }, options) {
+            Ok(tree) => Ok(Self {
+                tree: ManuallyDrop::new(tree),
+                storage,
+            }),
+            Err(error) => Err(OwnedDeviceTreeError {
+                error: ManuallyDrop::new(Box::new(error)),
+                storage,
+            }),
+        }
+    }
+
+    /// Copies the given blob, of any alignment, into owned storage and parses it
+    ///
+    /// # Errors
+    /// Returns an error if any part of the parsing process fails
+    #[inline]
+    pub fn copy_from(bytes: &[u8]) -> Result<Self, OwnedDeviceTreeError> {
+        Self::new(copy_aligned(bytes))
+    }
+
+    /// Copies the given blob, of any alignment, into owned storage and parses it with the given options
+    ///
+    /// # Errors
+    /// Returns an error if any part of the parsing process fails and is not tolerated by `options`
+    #[inline]
+    pub fn copy_from_with(
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<Self, OwnedDeviceTreeError> {
+        Self::new_with(copy_aligned(bytes), options)
+    }
+
+    /// Returns the parsed device tree
+    #[must_use]
+    #[inline]
+    pub fn tree(&self) -> &DeviceTree<'_> {
+        // SAFETY: The tree only borrows from the storage, which lives as long as `self`.
+        // The tree is only invariant over its lifetime due to the errors it holds as warnings, as for `OwnedDeviceTreeError::error`,
+        // so shortening its lifetime behind a shared reference cannot be used to store a shorter-lived borrow into it
+        unsafe { &*ptr::from_ref::<DeviceTree<'static>>(&self.tree).cast::<DeviceTree<'_>>() }
+    }
+
+    /// Returns the blob that the device tree was parsed from
+    #[must_use]
+    #[inline]
+    pub const fn blob(&self) -> &[u64] {
+        // SAFETY: The storage is valid until `self` is dropped, and is never mutated
+        unsafe { self.storage.as_ref() }
+    }
+
+    /// Drops the parsed device tree, returning the blob that it was parsed from
+    #[must_use]
+    #[inline]
+    pub fn into_blob(self) -> Box<[u64]> {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: The tree is taken exactly once, here, and `this` is never dropped afterwards
+        drop(unsafe { ManuallyDrop::take(&mut this.tree) });
+        // SAFETY: The storage came from `Box::leak`, and nothing borrows from it any longer
+        unsafe { Box::from_raw(this.storage.as_ptr()) }
+    }
+}
+
+impl Drop for OwnedDeviceTree {
+    #[inline]
+    fn drop(&mut self) {
+        // SAFETY: The tree is taken exactly once, here, before the storage it borrows from is freed
+        drop(unsafe { ManuallyDrop::take(&mut self.tree) });
+        // SAFETY: The storage came from `Box::leak`, and nothing borrows from it any longer
+        drop(unsafe { Box::from_raw(self.storage.as_ptr()) });
+    }
+}
+
+impl fmt::Debug for OwnedDeviceTree {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OwnedDeviceTree").field(self.tree()).finish()
+    }
+}
+
+impl OwnedDeviceTreeError {
+    /// Returns the error that occurred while parsing
+    #[must_use]
+    #[inline]
+    pub fn error(&self) -> &ParseError<'_> {
+        // SAFETY: The error only borrows from the storage, which lives as long as `self`.
+        // The error is only invariant over its lifetime due to an associated type projection, not interior mutability,
+        // so shortening its lifetime behind a shared reference cannot be used to store a shorter-lived borrow into it
+        unsafe { &*ptr::from_ref::<ParseError<'static>>(&**self.error).cast::<ParseError<'_>>() }
+    }
+
+    /// Drops the error, returning the blob that failed to parse
+    #[must_use]
+    #[inline]
+    pub fn into_blob(self) -> Box<[u64]> {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: The error is taken exactly once, here, and `this` is never dropped afterwards
+        drop(unsafe { ManuallyDrop::take(&mut this.error) });
+        // SAFETY: The storage came from `Box::leak`, and nothing borrows from it any longer
+        unsafe { Box::from_raw(this.storage.as_ptr()) }
+    }
+}
+
+impl Drop for OwnedDeviceTreeError {
+    #[inline]
+    fn drop(&mut self) {
+        // SAFETY: The error is taken exactly once, here, before the storage it borrows from is freed
+        drop(unsafe { ManuallyDrop::take(&mut self.error) });
+        // SAFETY: The storage came from `Box::leak`, and nothing borrows from it any longer
+        drop(unsafe { Box::from_raw(self.storage.as_ptr()) });
+    }
+}
+
+impl fmt::Debug for OwnedDeviceTreeError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("OwnedDeviceTreeError")
+            .field(self.error())
+            .finish()
+    }
+}
+
+// SAFETY: The storage is uniquely owned, like a `Box`, so the owned tree is as thread-safe as the tree itself
+#[cfg(feature = "sync")]
+unsafe impl Send for OwnedDeviceTree {}
+// SAFETY: The storage is only ever accessed immutably, so the owned tree is as thread-safe as the tree itself
+#[cfg(feature = "sync")]
+unsafe impl Sync for OwnedDeviceTree {}
+// SAFETY: The storage is uniquely owned, like a `Box`, so the owned error is as thread-safe as the error itself
+#[cfg(feature = "sync")]
+unsafe impl Send for OwnedDeviceTreeError {}
+// SAFETY: The storage is only ever accessed immutably, so the owned error is as thread-safe as the error itself
+#[cfg(feature = "sync")]
+unsafe impl Sync for OwnedDeviceTreeError {}
@@ -0,0 +1,991 @@
+//! Compilation of device tree source text into device trees
+//!
+//! The compiler accepts the source format of `dtc`: a `/dts-v1/;` header, `/memreserve/` entries, nodes and properties with labels,
+//! references to nodes by `&label` or `&{/path}`, `/include/` directives, and `/delete-node/` and `/delete-property/` directives.
+//! Property values are made up of strings, `<cells>` (of `/bits/` 8, 16, 32, or 64, containing literals, character literals, references, and parenthesized C-style expressions),
+//! `[byte strings]`, and references to the paths of nodes.
+//!
+//! Nodes that are referenced by phandle but do not have a `phandle` property are assigned the next unused phandle.
+
+use crate::edit::{self, Node, Tree};
+use crate::map::Map;
+use crate::node::PropertyKeys;
+use crate::parse::to_c_str;
+use alloc::{boxed::Box, ffi::CString, string::String, vec::Vec};
+use core::ffi::CStr;
+use core::{mem, str};
+
+/// Errors from compiling device tree source
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The source does not begin with `/dts-v1/;`
+    MissingVersion,
+    /// The source is malformed at the given line of the innermost file being read
+    Syntax {
+        /// The line, counting from 1, at which the error was found
+        line: usize,
+        /// A description of what was expected at that point
+        expected: &'static str,
+    },
+    /// An included file could not be found
+    Include(String),
+    /// Files are included too deeply, e.g. because a file includes itself
+    IncludeDepth,
+    /// A label is given to more than one node
+    DuplicateLabel(Box<[u8]>),
+    /// A reference names a label that is not defined
+    UnknownLabel(Box<[u8]>),
+    /// A reference names a path that does not exist
+    UnknownPath(Box<[u8]>),
+    /// An expression divides by zero at the given line
+    DivisionByZero(usize),
+    /// Assigning phandles to referenced nodes ran out of phandle values
+    PHandleOverflow,
+    /// Building the tree failed, e.g. because of an invalid node name
+    Edit(edit::Error),
+}
+
+/// A node referred to from within the source
+#[derive(Debug)]
+enum Reference {
+    /// The node with the given label
+    Label(Box<[u8]>),
+    /// The node at the given full path
+    Path(Box<[u8]>),
+}
+
+/// A segment of a property value
+#[derive(Debug)]
+enum Piece {
+    /// Literal bytes
+    Bytes(Vec<u8>),
+    /// The phandle of the referenced node, as a single cell
+    PHandle(Reference),
+    /// The full path of the referenced node, as a null-terminated string
+    Path(Reference),
+}
+
+/// A property as written in the source, before references are resolved
+#[derive(Debug)]
+struct Property {
+    /// The name of the property
+    name: CString,
+    /// The segments of the value of the property, in order
+    value: Vec<Piece>,
+}
+
+/// A node as written in the source, before references are resolved
+#[derive(Debug)]
+struct SourceNode {
+    /// The unit name of the node
+    name: Box<[u8]>,
+    /// The labels given to the node
+    labels: Labels,
+    /// The properties of the node, in order
+    properties: Vec<Property>,
+    /// The children of the node, in order
+    children: Vec<Self>,
+}
+
+/// The labels given to a node
+type Labels = Vec<Box<[u8]>>;
+
+/// The name of the legacy phandle property
+const LINUX_PHANDLE: &CStr = to_c_str(b"linux,phandle\0");
+/// The maximum depth of nested `/include/`s
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Binary operators, longest first so that e.g. `<<` is not read as `<`, along with their precedence (higher binds tighter)
+const BINARY_OPERATORS: [(&[u8], u8); 18] = [
+    (b"||", 1),
+    (b"&&", 2),
+    (b"<<", 8),
+    (b">>", 8),
+    (b"<=", 7),
+    (b">=", 7),
+    (b"==", 6),
+    (b"!=", 6),
+    (b"|", 3),
+    (b"^", 4),
+    (b"&", 5),
+    (b"<", 7),
+    (b">", 7),
+    (b"+", 9),
+    (b"-", 9),
+    (b"*", 10),
+    (b"/", 10),
+    (b"%", 10),
+];
+
+/// Returns whether or not the given character may appear in the name of a node or property
+const fn is_name_char(char: u8) -> bool {
+    char.is_ascii_alphanumeric()
+        || matches!(
+            char,
+            b',' | b'.' | b'_' | b'+' | b'*' | b'#' | b'?' | b'@' | b'-'
+        )
+}
+
+/// Returns whether or not the given character may appear in a label
+const fn is_label_char(char: u8) -> bool {
+    char.is_ascii_alphanumeric() || char == b'_'
+}
+
+/// Returns the value of the given character as a digit in the given radix
+fn digit(char: u8, radix: u32) -> Option<u8> {
+    u8::try_from(char::from(char).to_digit(radix)?).ok()
+}
+
+impl SourceNode {
+    /// Creates a new node with the given unit name and no labels, properties, or children
+    fn new(name: &[u8]) -> Self {
+        Self {
+            name: Box::from(name),
+            labels: Vec::new(),
+            properties: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns whether or not this node is referred to by the given path component,
+    /// either by its full unit name or by its node name alone
+    fn is_named(&self, name: &[u8]) -> bool {
+        *self.name == *name
+            || (!name.contains(&b'@')
+                && self
+                    .name
+                    .split(|&char| char == b'@')
+                    .next()
+                    .is_some_and(|node_name| node_name == name))
+    }
+
+    /// Returns the child with exactly the given unit name, adding a new child if none exists
+    fn child_or_insert(&mut self, name: &[u8]) -> &mut Self {
+        if let Some(index) = self.children.iter().position(|child| *child.name == *name) {
+            return self
+                .children
+                .get_mut(index)
+                .expect("Index of an existing child should be in bounds");
+        }
+        self.children.push(Self::new(name));
+        self.children.last_mut().expect("A child was just added")
+    }
+
+    /// Sets a property, replacing the value of any existing property with the same name
+    fn set_property(&mut self, property: Property) {
+        if let Some(existing) = self
+            .properties
+            .iter_mut()
+            .find(|existing| existing.name == property.name)
+        {
+            existing.value = property.value;
+        } else {
+            self.properties.push(property);
+        }
+    }
+
+    /// Returns the value of the given property, if it is a single literal cell
+    fn property_u32(&self, name: &CStr) -> Option<u32> {
+        self.properties
+            .iter()
+            .find(|property| *property.name == *name)
+            .and_then(|property| match property.value.as_slice() {
+                &[Piece::Bytes(ref bytes)] => <[u8; 4]>::try_from(bytes.as_slice())
+                    .ok()
+                    .map(u32::from_be_bytes),
+                _ => None,
+            })
+    }
+
+    /// Returns the largest phandle explicitly given to this node or any of its descendants
+    fn max_phandle(&self) -> u32 {
+        self.children.iter().map(Self::max_phandle).fold(
+            self.property_u32(PropertyKeys::PHANDLE)
+                .max(self.property_u32(LINUX_PHANDLE))
+                .unwrap_or(0),
+            u32::max,
+        )
+    }
+
+    /// Returns the node at the given indices of successive children
+    fn get_mut(&mut self, indices: &[usize]) -> Option<&mut Self> {
+        indices
+            .iter()
+            .try_fold(self, |node, &index| node.children.get_mut(index))
+    }
+
+    /// Searches this node and its descendants for the given label, appending the indices of the path to the labelled node
+    fn find_label(&self, label: &[u8], indices: &mut Vec<usize>) -> bool {
+        if self.labels.iter().any(|own| **own == *label) {
+            return true;
+        }
+        for (index, child) in self.children.iter().enumerate() {
+            indices.push(index);
+            if child.find_label(label, indices) {
+                return true;
+            }
+            indices.pop();
+        }
+        false
+    }
+
+    /// Returns the indices of successive children leading to the node with the given full path
+    fn find_path(&self, path: &[u8]) -> Option<Vec<usize>> {
+        let mut node = self;
+        let mut indices = Vec::new();
+        for component in path
+            .strip_prefix(b"/")?
+            .split(|&char| char == b'/')
+            .filter(|component| !component.is_empty())
+        {
+            let index = node
+                .children
+                .iter()
+                .position(|child| *child.name == *component)
+                .or_else(|| {
+                    node.children
+                        .iter()
+                        .position(|child| child.is_named(component))
+                })?;
+            node = node.children.get(index)?;
+            indices.push(index);
+        }
+        Some(indices)
+    }
+
+    /// Returns the indices of successive children leading to the referenced node
+    fn locate(&self, reference: &Reference) -> Result<Vec<usize>, Error> {
+        match *reference {
+            Reference::Label(ref label) => {
+                let mut indices = Vec::new();
+                if self.find_label(label, &mut indices) {
+                    Ok(indices)
+                } else {
+                    Err(Error::UnknownLabel(label.clone()))
+                }
+            }
+            Reference::Path(ref path) => self
+                .find_path(path)
+                .ok_or_else(|| Error::UnknownPath(path.clone())),
+        }
+    }
+
+    /// Returns the full path of the node at the given indices of successive children
+    fn path(&self, indices: &[usize]) -> Vec<u8> {
+        if indices.is_empty() {
+            return Vec::from(*b"/");
+        }
+        let mut path = Vec::new();
+        let mut node = self;
+        for &index in indices {
+            node = node
+                .children
+                .get(index)
+                .expect("Indices should come from locating a node");
+            path.push(b'/');
+            path.extend_from_slice(&node.name);
+        }
+        path
+    }
+
+    /// Checks that no label is given to more than one node, given the indices of the path to this node
+    fn check_labels(
+        &self,
+        indices: &mut Vec<usize>,
+        labels: &mut Map<Box<[u8]>, Vec<usize>>,
+    ) -> Result<(), Error> {
+        for label in &self.labels {
+            if let Some(existing) = labels.insert(label.clone(), indices.clone()) {
+                if existing != *indices {
+                    return Err(Error::DuplicateLabel(label.clone()));
+                }
+            }
+        }
+        for (index, child) in self.children.iter().enumerate() {
+            indices.push(index);
+            child.check_labels(indices, labels)?;
+            indices.pop();
+        }
+        Ok(())
+    }
+
+    /// Collects every reference to a phandle made by this node or any of its descendants, in order
+    fn phandle_references<'node>(&'node self, references: &mut Vec<&'node Reference>) {
+        for property in &self.properties {
+            for piece in &property.value {
+                if let Piece::PHandle(ref reference) = *piece {
+                    references.push(reference);
+                }
+            }
+        }
+        for child in &self.children {
+            child.phandle_references(references);
+        }
+    }
+
+    /// Converts this node into an editable node, resolving references relative to the given root
+    fn convert(
+        &self,
+        root: &Self,
+        phandles: &Map<Vec<usize>, u32>,
+        node: &mut Node,
+    ) -> Result<(), Error> {
+        for property in &self.properties {
+            let mut value = Vec::new();
+            for piece in &property.value {
+                match *piece {
+                    Piece::Bytes(ref bytes) => value.extend_from_slice(bytes),
+                    Piece::PHandle(ref reference) => value.extend_from_slice(
+                        &phandles
+                            .get(&root.locate(reference)?)
+                            .expect("Every referenced node should have been assigned a phandle")
+                            .to_be_bytes(),
+                    ),
+                    Piece::Path(ref reference) => {
+                        value.extend(root.path(&root.locate(reference)?));
+                        value.push(0);
+                    }
+                }
+            }
+            node.set_property(&property.name, value);
+        }
+        for child in &self.children {
+            let converted = node
+                .add_child(Node::new(&child.name).map_err(Error::Edit)?)
+                .map_err(Error::Edit)?;
+            child.convert(root, phandles, converted)?;
+        }
+        Ok(())
+    }
+}
+
+/// A file of source text being read
+#[derive(Debug)]
+struct Source {
+    /// The text of the file
+    text: Box<[u8]>,
+    /// The offset of the next character to read
+    position: usize,
+    /// The line of the next character to read, counting from 1
+    line: usize,
+}
+
+impl Source {
+    /// Creates a source positioned at the start of the given text
+    const fn new(text: Box<[u8]>) -> Self {
+        Self {
+            text,
+            position: 0,
+            line: 1,
+        }
+    }
+}
+
+/// A recursive-descent parser over device tree source and the files it includes
+struct Parser<F: FnMut(&str) -> Option<String>> {
+    /// The files being read, with the innermost include last
+    sources: Vec<Source>,
+    /// Looks up the contents of included files by name
+    includes: F,
+}
+
+impl<F: FnMut(&str) -> Option<String>> Parser<F> {
+    /// Returns the unread text of the innermost file
+    fn rest(&self) -> &[u8] {
+        self.sources
+            .last()
+            .and_then(|source| source.text.get(source.position..))
+            .unwrap_or_default()
+    }
+
+    /// Consumes and returns the next character of the innermost file
+    fn bump(&mut self) -> Option<u8> {
+        let source = self.sources.last_mut()?;
+        let char = *source.text.get(source.position)?;
+        source.position = source.position.saturating_add(1);
+        if char == b'\n' {
+            source.line = source.line.saturating_add(1);
+        }
+        Some(char)
+    }
+
+    /// Consumes the given number of characters
+    fn advance(&mut self, count: usize) {
+        for _ in 0..count {
+            self.bump();
+        }
+    }
+
+    /// Returns a syntax error at the current position
+    fn syntax(&self, expected: &'static str) -> Error {
+        Error::Syntax {
+            line: self.sources.last().map_or(0, |source| source.line),
+            expected,
+        }
+    }
+
+    /// Skips whitespace and comments, enters included files, and leaves included files that have been completely read
+    fn skip_trivia(&mut self) -> Result<(), Error> {
+        loop {
+            let rest = self.rest();
+            if rest.first().is_some_and(u8::is_ascii_whitespace) {
+                self.bump();
+            } else if rest.starts_with(b"//") {
+                while self.bump().is_some_and(|char| char != b'\n') {}
+            } else if rest.starts_with(b"/*") {
+                self.advance(2);
+                while !self.rest().starts_with(b"*/") {
+                    if self.bump().is_none() {
+                        return Err(self.syntax("*/"));
+                    }
+                }
+                self.advance(2);
+            } else if rest.starts_with(b"/include/") {
+                self.advance(b"/include/".len());
+                self.skip_trivia()?;
+                if self.bump() != Some(b'"') {
+                    return Err(self.syntax("file name"));
+                }
+                let name = self.string()?;
+                if self.sources.len() >= MAX_INCLUDE_DEPTH {
+                    return Err(Error::IncludeDepth);
+                }
+                let name = str::from_utf8(&name).map_err(|_err| self.syntax("file name"))?;
+                let text =
+                    (self.includes)(name).ok_or_else(|| Error::Include(String::from(name)))?;
+                self.sources
+                    .push(Source::new(text.into_bytes().into_boxed_slice()));
+            } else if rest.is_empty() && self.sources.len() > 1 {
+                self.sources.pop();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Consumes the given token if it is next, returning whether or not it was present
+    fn eat(&mut self, token: &[u8]) -> Result<bool, Error> {
+        self.skip_trivia()?;
+        let present = self.rest().starts_with(token);
+        if present {
+            self.advance(token.len());
+        }
+        Ok(present)
+    }
+
+    /// Consumes the given token, which must be next
+    fn expect(&mut self, token: &'static str) -> Result<(), Error> {
+        if self.eat(token.as_bytes())? {
+            Ok(())
+        } else {
+            Err(self.syntax(token))
+        }
+    }
+
+    /// Consumes the name of a node or property, or a label
+    fn word(&mut self) -> Result<Vec<u8>, Error> {
+        self.skip_trivia()?;
+        let mut word = Vec::new();
+        while let Some(&char) = self.rest().first().filter(|&&char| is_name_char(char)) {
+            word.push(char);
+            self.bump();
+        }
+        if word.is_empty() {
+            return Err(self.syntax("name"));
+        }
+        Ok(word)
+    }
+
+    /// Consumes any labels followed by the name of a node or property
+    fn labels_and_name(&mut self) -> Result<(Labels, Vec<u8>), Error> {
+        let mut labels = Vec::new();
+        loop {
+            let word = self.word()?;
+            if self.rest().first() != Some(&b':') {
+                return Ok((labels, word));
+            }
+            if word.first().is_some_and(u8::is_ascii_digit)
+                || !word.iter().all(|&char| is_label_char(char))
+            {
+                return Err(self.syntax("label"));
+            }
+            self.bump();
+            labels.push(word.into_boxed_slice());
+        }
+    }
+
+    /// Consumes the remainder of a reference, after the `&`
+    fn reference(&mut self) -> Result<Reference, Error> {
+        if self.rest().first() == Some(&b'{') {
+            self.bump();
+            let mut path = Vec::new();
+            loop {
+                match self.bump() {
+                    Some(b'}') => return Ok(Reference::Path(path.into_boxed_slice())),
+                    Some(char) if char == b'/' || is_name_char(char) => path.push(char),
+                    _ => return Err(self.syntax("}")),
+                }
+            }
+        }
+        let mut label = Vec::new();
+        while let Some(&char) = self.rest().first().filter(|&&char| is_label_char(char)) {
+            label.push(char);
+            self.bump();
+        }
+        if label.is_empty() {
+            return Err(self.syntax("label"));
+        }
+        Ok(Reference::Label(label.into_boxed_slice()))
+    }
+
+    /// Consumes the remainder of an escape sequence, after the `\`
+    fn escape(&mut self) -> Result<u8, Error> {
+        Ok(match self.bump() {
+            Some(b'a') => 0x07,
+            Some(b'b') => 0x08,
+            Some(b't') => b'\t',
+            Some(b'n') => b'\n',
+            Some(b'v') => 0x0B,
+            Some(b'f') => 0x0C,
+            Some(b'r') => b'\r',
+            Some(b'x') => {
+                let mut value: Option<u8> = None;
+                for _ in 0..2 {
+                    let Some(next) = self.rest().first().and_then(|&next| digit(next, 16)) else {
+                        break;
+                    };
+                    self.bump();
+                    value = Some(
+                        value
+                            .unwrap_or_default()
+                            .wrapping_mul(16)
+                            .wrapping_add(next),
+                    );
+                }
+                value.ok_or_else(|| self.syntax("hexadecimal digit"))?
+            }
+            Some(char @ b'0'..=b'7') => {
+                let mut value = char.wrapping_sub(b'0');
+                for _ in 0..2 {
+                    let Some(next) = self.rest().first().and_then(|&next| digit(next, 8)) else {
+                        break;
+                    };
+                    self.bump();
+                    value = value.wrapping_mul(8).wrapping_add(next);
+                }
+                value
+            }
+            Some(char) => char,
+            None => return Err(self.syntax("escape sequence")),
+        })
+    }
+
+    /// Consumes the remainder of a string literal, after the opening `"`
+    fn string(&mut self) -> Result<Vec<u8>, Error> {
+        let mut string = Vec::new();
+        loop {
+            match self.bump() {
+                Some(b'"') => return Ok(string),
+                Some(b'\\') => string.push(self.escape()?),
+                Some(b'\n') | None => return Err(self.syntax("\"")),
+                Some(char) => string.push(char),
+            }
+        }
+    }
+
+    /// Consumes the remainder of a byte string, after the `[`
+    fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            match self.bump() {
+                Some(b']') => return Ok(bytes),
+                Some(high) => {
+                    let low = self.bump();
+                    match (digit(high, 16), low.and_then(|low| digit(low, 16))) {
+                        (Some(high), Some(low)) => bytes.push(high.wrapping_mul(16) | low),
+                        _ => return Err(self.syntax("hexadecimal byte")),
+                    }
+                }
+                None => return Err(self.syntax("]")),
+            }
+        }
+    }
+
+    /// Consumes an integer literal, in hexadecimal, octal, or decimal
+    fn literal(&mut self) -> Result<u64, Error> {
+        let rest = self.rest();
+        let (radix, prefix) = if rest.starts_with(b"0x") || rest.starts_with(b"0X") {
+            (16, 2)
+        } else if rest.starts_with(b"0") && rest.get(1).is_some_and(u8::is_ascii_digit) {
+            (8, 1)
+        } else {
+            (10, 0)
+        };
+        self.advance(prefix);
+        let mut value: Option<u64> = None;
+        while let Some(next) = self.rest().first().and_then(|&char| digit(char, radix)) {
+            self.bump();
+            value = Some(
+                value
+                    .unwrap_or_default()
+                    .checked_mul(u64::from(radix))
+                    .and_then(|value| value.checked_add(u64::from(next)))
+                    .ok_or_else(|| self.syntax("64-bit integer"))?,
+            );
+        }
+        let value = value.ok_or_else(|| self.syntax("digit"))?;
+        while matches!(self.rest().first(), Some(b'U' | b'u' | b'L' | b'l')) {
+            self.bump();
+        }
+        Ok(value)
+    }
+
+    /// Consumes an integer literal, character literal, or parenthesized expression
+    fn primary(&mut self) -> Result<u64, Error> {
+        self.skip_trivia()?;
+        match self.rest().first().copied() {
+            Some(b'(') => {
+                self.bump();
+                let value = self.expression()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Some(b'\'') => {
+                self.bump();
+                let value = match self.bump() {
+                    Some(b'\\') => self.escape()?,
+                    Some(b'\'' | b'\n') | None => return Err(self.syntax("character")),
+                    Some(char) => char,
+                };
+                if self.bump() != Some(b'\'') {
+                    return Err(self.syntax("'"));
+                }
+                Ok(u64::from(value))
+            }
+            Some(char) if char.is_ascii_digit() => self.literal(),
+            _ => Err(self.syntax("integer")),
+        }
+    }
+
+    /// Consumes a primary expression with any number of unary operators
+    fn unary(&mut self) -> Result<u64, Error> {
+        if self.eat(b"-")? {
+            Ok(self.unary()?.wrapping_neg())
+        } else if self.eat(b"~")? {
+            Ok(!self.unary()?)
+        } else if self.eat(b"!")? {
+            Ok(u64::from(self.unary()? == 0))
+        } else {
+            self.primary()
+        }
+    }
+
+    /// Consumes a chain of binary operations whose operators bind at least as tightly as the given precedence
+    fn binary(&mut self, min_precedence: u8) -> Result<u64, Error> {
+        let mut left = self.unary()?;
+        loop {
+            self.skip_trivia()?;
+            let rest = self.rest();
+            let Some(&(operator, precedence)) = BINARY_OPERATORS
+                .iter()
+                .find(|&&(operator, _)| rest.starts_with(operator))
+            else {
+                return Ok(left);
+            };
+            if precedence < min_precedence {
+                return Ok(left);
+            }
+            self.advance(operator.len());
+            let right = self.binary(precedence.saturating_add(1))?;
+            let division_by_zero =
+                || Error::DivisionByZero(self.sources.last().map_or(0, |source| source.line));
+            left = match operator {
+                b"||" => u64::from(left != 0 || right != 0),
+                b"&&" => u64::from(left != 0 && right != 0),
+                b"|" => left | right,
+                b"^" => left ^ right,
+                b"&" => left & right,
+                b"==" => u64::from(left == right),
+                b"!=" => u64::from(left != right),
+                b"<" => u64::from(left < right),
+                b">" => u64::from(left > right),
+                b"<=" => u64::from(left <= right),
+                b">=" => u64::from(left >= right),
+                b"<<" => u32::try_from(right)
+                    .ok()
+                    .and_then(|shift| left.checked_shl(shift))
+                    .unwrap_or(0),
+                b">>" => u32::try_from(right)
+                    .ok()
+                    .and_then(|shift| left.checked_shr(shift))
+                    .unwrap_or(0),
+                b"+" => left.wrapping_add(right),
+                b"-" => left.wrapping_sub(right),
+                b"*" => left.wrapping_mul(right),
+                b"/" => left.checked_div(right).ok_or_else(division_by_zero)?,
+                b"%" => left.checked_rem(right).ok_or_else(division_by_zero)?,
+                _ => unreachable!("Every binary operator should be handled"),
+            };
+        }
+    }
+
+    /// Consumes an expression, including the conditional operator
+    fn expression(&mut self) -> Result<u64, Error> {
+        let condition = self.binary(1)?;
+        if self.eat(b"?")? {
+            let if_true = self.expression()?;
+            self.expect(":")?;
+            let if_false = self.expression()?;
+            Ok(if condition == 0 { if_false } else { if_true })
+        } else {
+            Ok(condition)
+        }
+    }
+
+    /// Consumes the remainder of a list of cells of the given size in bits, after the `<`
+    fn cells(&mut self, bits: u64, value: &mut Vec<Piece>) -> Result<(), Error> {
+        let size = match bits {
+            8 => 1,
+            16 => 2,
+            32 => 4,
+            64 => 8,
+            _ => return Err(self.syntax("8, 16, 32, or 64 bits")),
+        };
+        let mut bytes = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            match self.rest().first().copied() {
+                Some(b'>') => {
+                    self.bump();
+                    value.push(Piece::Bytes(bytes));
+                    return Ok(());
+                }
+                Some(b'&') => {
+                    if size != mem::size_of::<u32>() {
+                        return Err(self.syntax("32-bit cells for a reference"));
+                    }
+                    self.bump();
+                    let reference = self.reference()?;
+                    value.push(Piece::Bytes(mem::take(&mut bytes)));
+                    value.push(Piece::PHandle(reference));
+                }
+                _ => {
+                    let cell = self.primary()?.to_be_bytes();
+                    bytes.extend_from_slice(
+                        cell.get(cell.len().saturating_sub(size)..)
+                            .unwrap_or_default(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Consumes a comma-separated property value
+    fn value(&mut self) -> Result<Vec<Piece>, Error> {
+        let mut value = Vec::new();
+        loop {
+            if self.eat(b"/bits/")? {
+                self.skip_trivia()?;
+                let bits = self.literal()?;
+                self.expect("<")?;
+                self.cells(bits, &mut value)?;
+            } else {
+                match self.rest().first().copied() {
+                    Some(b'"') => {
+                        self.bump();
+                        let mut string = self.string()?;
+                        string.push(0);
+                        value.push(Piece::Bytes(string));
+                    }
+                    Some(b'<') => {
+                        self.bump();
+                        self.cells(32, &mut value)?;
+                    }
+                    Some(b'[') => {
+                        self.bump();
+                        value.push(Piece::Bytes(self.bytes()?));
+                    }
+                    Some(b'&') => {
+                        self.bump();
+                        value.push(Piece::Path(self.reference()?));
+                    }
+                    _ => return Err(self.syntax("property value")),
+                }
+            }
+            if !self.eat(b",")? {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Consumes the remainder of the body of a node, after the `{`, merging its contents into the given node
+    #[expect(clippy::unwrap_in_result, reason = "Checks should never fail")]
+    fn body(&mut self, node: &mut SourceNode) -> Result<(), Error> {
+        loop {
+            if self.eat(b"}")? {
+                return self.expect(";");
+            }
+            if self.eat(b"/delete-property/")? {
+                let name = self.word()?;
+                self.expect(";")?;
+                node.properties
+                    .retain(|property| property.name.as_bytes() != name);
+            } else if self.eat(b"/delete-node/")? {
+                let name = self.word()?;
+                self.expect(";")?;
+                node.children.retain(|child| *child.name != *name);
+            } else {
+                let (labels, name) = self.labels_and_name()?;
+                if self.eat(b"{")? {
+                    let child = node.child_or_insert(&name);
+                    child.labels.extend(labels);
+                    self.body(child)?;
+                } else {
+                    let value = if self.eat(b"=")? {
+                        self.value()?
+                    } else {
+                        Vec::new()
+                    };
+                    self.expect(";")?;
+                    node.set_property(Property {
+                        name: CString::new(name).expect("Names should never contain NUL"),
+                        value,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Consumes an entire source file, adding its nodes to the given root and its memory reservations to the given list
+    fn source_file(
+        &mut self,
+        root: &mut SourceNode,
+        memory_reservations: &mut Vec<(u64, u64)>,
+    ) -> Result<(), Error> {
+        if !self.eat(b"/dts-v1/")? {
+            return Err(Error::MissingVersion);
+        }
+        self.expect(";")?;
+        loop {
+            self.skip_trivia()?;
+            if self.rest().is_empty() {
+                return Ok(());
+            }
+            if self.eat(b"/dts-v1/")? {
+                self.expect(";")?;
+            } else if self.eat(b"/memreserve/")? {
+                let address = self.primary()?;
+                let size = self.primary()?;
+                self.expect(";")?;
+                memory_reservations.push((address, size));
+            } else if self.eat(b"/delete-node/")? {
+                self.expect("&")?;
+                let reference = self.reference()?;
+                self.expect(";")?;
+                let indices = root.locate(&reference)?;
+                let (&index, parent) = indices
+                    .split_last()
+                    .ok_or_else(|| self.syntax("reference to a non-root node"))?;
+                root.get_mut(parent)
+                    .expect("Parent of a located node should exist")
+                    .children
+                    .remove(index);
+            } else {
+                let mut labels = Vec::new();
+                while self
+                    .rest()
+                    .first()
+                    .is_some_and(|&char| char == b'_' || char.is_ascii_alphabetic())
+                {
+                    let label = self.word()?;
+                    if self.bump() != Some(b':') || !label.iter().all(|&char| is_label_char(char)) {
+                        return Err(self.syntax("label"));
+                    }
+                    labels.push(label.into_boxed_slice());
+                    self.skip_trivia()?;
+                }
+                let node = if self.eat(b"/")? {
+                    &mut *root
+                } else if self.eat(b"&")? {
+                    let indices = root.locate(&self.reference()?)?;
+                    root.get_mut(&indices).expect("Located node should exist")
+                } else {
+                    return Err(self.syntax("node definition"));
+                };
+                node.labels.extend(labels);
+                self.expect("{")?;
+                self.body(node)?;
+            }
+        }
+    }
+}
+
+/// Compiles the given device tree source into an editable tree, which can then be serialized into a blob
+///
+/// # Errors
+/// Returns an error if the source is malformed, includes another file, or references a nonexistent node
+#[inline]
+pub fn compile(source: &str) -> Result<Tree, Error> {
+    compile_with_includes(source, |_| None)
+}
+
+/// Compiles the given device tree source into an editable tree, which can then be serialized into a blob.
+/// The contents of files named by `/include/` directives are looked up with the given function
+///
+/// # Errors
+/// Returns an error if the source is malformed, an included file is not found, or the source references a nonexistent node
+#[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+#[inline]
+pub fn compile_with_includes<F>(source: &str, includes: F) -> Result<Tree, Error>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut parser = Parser {
+        sources: Vec::from([Source::new(Box::from(source.as_bytes()))]),
+        includes,
+    };
+    let mut root = SourceNode::new(&[]);
+    let mut memory_reservations = Vec::new();
+    parser.source_file(&mut root, &mut memory_reservations)?;
+
+    root.check_labels(&mut Vec::new(), &mut Map::new())?;
+
+    let mut references = Vec::new();
+    root.phandle_references(&mut references);
+    let targets = references
+        .into_iter()
+        .map(|reference| root.locate(reference))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut next_phandle = root.max_phandle();
+    let mut phandles = Map::new();
+    for indices in targets {
+        if phandles.get(&indices).is_some() {
+            continue;
+        }
+        let node = root.get_mut(&indices).expect("Located node should exist");
+        let phandle = if let Some(phandle) = node
+            .property_u32(PropertyKeys::PHANDLE)
+            .or_else(|| node.property_u32(LINUX_PHANDLE))
+        {
+            phandle
+        } else {
+            next_phandle = next_phandle
+                .checked_add(1)
+                .filter(|&phandle| phandle != u32::MAX)
+                .ok_or(Error::PHandleOverflow)?;
+            node.set_property(Property {
+                name: CString::from(PropertyKeys::PHANDLE),
+                value: Vec::from([Piece::Bytes(Vec::from(next_phandle.to_be_bytes()))]),
+            });
+            next_phandle
+        };
+        phandles.insert(indices, phandle);
+    }
+
+    let mut tree = Tree::new(0);
+    *tree.memory_reservations_mut() = memory_reservations;
+    root.convert(&root, &phandles, tree.root_mut())?;
+    Ok(tree)
+}
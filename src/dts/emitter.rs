@@ -0,0 +1,198 @@
+//! Rendering of device trees as device tree source text
+//!
+//! Property values are untyped in a blob, so the emitter guesses how to display each value:
+//! values made up of printable null-terminated strings are rendered as strings, values that are a whole number of cells as `<cells>`, and anything else as a `[byte string]`.
+
+use crate::dtb::DeviceTree;
+use crate::edit::{Node, Tree};
+use alloc::vec::Vec;
+use core::ffi::CStr;
+use core::fmt::{self, Display, Formatter, Write};
+use core::mem;
+
+/// A `Display`able rendering of a tree as device tree source, with labels taken from the tree's `__symbols__` node
+#[derive(Debug, Clone, Copy)]
+pub struct Dts<'tree> {
+    /// The tree to render
+    tree: &'tree Tree,
+}
+
+/// The name of the node mapping labels to paths
+const SYMBOLS: &[u8] = b"__symbols__";
+
+/// Returns whether or not a property value should be rendered as a list of strings
+fn is_string_list(value: &[u8]) -> bool {
+    value.last() == Some(&0)
+        && value.first() != Some(&0)
+        && !value.windows(2).any(|pair| pair == [0, 0])
+        && value.iter().all(|&byte| {
+            byte == 0 || byte.is_ascii_graphic() || matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+        })
+}
+
+/// Writes the indentation for a line at the given depth
+fn indent(formatter: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+    (0..depth).try_for_each(|_| formatter.write_char('\t'))
+}
+
+/// Writes a property value as a comma-separated list of quoted strings
+fn write_strings(formatter: &mut Formatter<'_>, value: &[u8]) -> fmt::Result {
+    for (index, string) in value
+        .split(|&byte| byte == 0)
+        .take_while(|string| !string.is_empty())
+        .enumerate()
+    {
+        if index != 0 {
+            formatter.write_str(", ")?;
+        }
+        formatter.write_char('"')?;
+        for &byte in string {
+            match byte {
+                b'"' => formatter.write_str("\\\"")?,
+                b'\\' => formatter.write_str("\\\\")?,
+                b'\n' => formatter.write_str("\\n")?,
+                b'\t' => formatter.write_str("\\t")?,
+                b'\r' => formatter.write_str("\\r")?,
+                printable if printable.is_ascii_graphic() || printable == b' ' => {
+                    formatter.write_char(char::from(printable))?;
+                }
+                other => write!(formatter, "\\x{other:02x}")?,
+            }
+        }
+        formatter.write_char('"')?;
+    }
+    Ok(())
+}
+
+/// Writes a property value as a list of `u32` cells
+fn write_cells(formatter: &mut Formatter<'_>, value: &[u8]) -> fmt::Result {
+    formatter.write_char('<')?;
+    for (index, cell) in value
+        .array_chunks::<{ mem::size_of::<u32>() }>()
+        .enumerate()
+    {
+        if index != 0 {
+            formatter.write_char(' ')?;
+        }
+        write!(formatter, "{:#x}", u32::from_be_bytes(*cell))?;
+    }
+    formatter.write_char('>')
+}
+
+/// Writes a property value as a byte string
+fn write_bytes(formatter: &mut Formatter<'_>, value: &[u8]) -> fmt::Result {
+    formatter.write_char('[')?;
+    for (index, byte) in value.iter().enumerate() {
+        if index != 0 {
+            formatter.write_char(' ')?;
+        }
+        write!(formatter, "{byte:02x}")?;
+    }
+    formatter.write_char(']')
+}
+
+impl<'tree> Dts<'tree> {
+    /// Creates a rendering of the given tree
+    #[must_use]
+    #[inline]
+    pub const fn new(tree: &'tree Tree) -> Self {
+        Self { tree }
+    }
+
+    /// Returns the (path, label) pairs described by the `__symbols__` node
+    fn labels(self) -> Vec<(&'tree [u8], &'tree CStr)> {
+        self.tree
+            .root()
+            .child(SYMBOLS)
+            .map(|symbols| {
+                symbols
+                    .properties()
+                    .filter_map(|(label, path)| {
+                        CStr::from_bytes_until_nul(path)
+                            .ok()
+                            .map(|path| (path.to_bytes(), label))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Writes a node and all of its descendants at the given depth
+    fn write_node(
+        formatter: &mut Formatter<'_>,
+        node: &Node,
+        path: &mut Vec<u8>,
+        depth: usize,
+        labels: &[(&[u8], &CStr)],
+    ) -> fmt::Result {
+        indent(formatter, depth)?;
+        let node_path: &[u8] = if path.is_empty() { b"/" } else { path };
+        for &(_, label) in labels
+            .iter()
+            .filter(|&&(labelled, _)| labelled == node_path)
+        {
+            write!(formatter, "{}: ", label.to_string_lossy())?;
+        }
+        if depth == 0 {
+            formatter.write_str("/ {\n")?;
+        } else {
+            writeln!(formatter, "{} {{", node.name().escape_ascii())?;
+        }
+
+        for (name, value) in node.properties() {
+            indent(formatter, depth.saturating_add(1))?;
+            write!(formatter, "{}", name.to_string_lossy())?;
+            if !value.is_empty() {
+                formatter.write_str(" = ")?;
+                if is_string_list(value) {
+                    write_strings(formatter, value)?;
+                } else if value.len().checked_rem(mem::size_of::<u32>()) == Some(0) {
+                    write_cells(formatter, value)?;
+                } else {
+                    write_bytes(formatter, value)?;
+                }
+            }
+            formatter.write_str(";\n")?;
+        }
+
+        for child in node.children() {
+            let length = path.len();
+            path.push(b'/');
+            path.extend_from_slice(child.name());
+            formatter.write_char('\n')?;
+            Self::write_node(formatter, child, path, depth.saturating_add(1), labels)?;
+            path.truncate(length);
+        }
+
+        indent(formatter, depth)?;
+        formatter.write_str("};\n")
+    }
+}
+
+impl Display for Dts<'_> {
+    #[inline]
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter.write_str("/dts-v1/;\n\n")?;
+        for &(address, size) in self.tree.memory_reservations() {
+            writeln!(formatter, "/memreserve/ {address:#018x} {size:#018x};")?;
+        }
+        if !self.tree.memory_reservations().is_empty() {
+            formatter.write_char('\n')?;
+        }
+        Self::write_node(
+            formatter,
+            self.tree.root(),
+            &mut Vec::new(),
+            0,
+            &self.labels(),
+        )
+    }
+}
+
+impl Display for DeviceTree<'_> {
+    /// Renders this device tree as device tree source
+    #[inline]
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        self.to_tree().dts().fmt(formatter)
+    }
+}
@@ -0,0 +1,7 @@
+//! Device tree source (DTS), the human-readable text format of device trees
+//!
+//! The `emitter` renders a tree as DTS text, e.g. to inspect a blob supplied by firmware,
+//! while the `compiler` builds a tree from DTS text, e.g. to write small trees inline.
+
+pub mod compiler;
+pub mod emitter;
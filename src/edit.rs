@@ -0,0 +1,547 @@
+//! A mutable model of a device tree, for editing a tree before handing it off to another program
+//!
+//! Unlike the parsed nodes in `node`, which interpret their properties and are immutable once built, an editable `Tree` keeps every node and property in its raw form.
+//! Nodes and properties can be freely added, removed, and renamed, and the tree can then be serialized back into a blob with `Tree::to_bytes`.
+
+use crate::dtb::{Blocks, DeviceTreeError, Token, TokenError};
+use crate::dts::emitter::Dts;
+use crate::memory_reservation::MemoryReservations;
+use crate::node::PropertyKeys;
+use crate::node_name::{Char, NameRef};
+use crate::parse::U32ByteSlice;
+use crate::transmute_slice_down;
+use crate::writer::{self, Writer};
+use alloc::{boxed::Box, ffi::CString, vec, vec::Vec};
+use core::ffi::CStr;
+use core::mem;
+
+/// Errors from editing a tree
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A node name was not a valid `node-name@unit-address`
+    InvalidName,
+    /// A node with the same name already exists under the parent
+    DuplicateChild,
+    /// The node at the given path does not exist
+    NotFound,
+}
+
+/// An editable device tree node
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// The unit name of the node, i.e. `node-name@unit-address`
+    name: Box<[u8]>,
+    /// The properties of the node, in order
+    properties: Vec<(CString, Box<[u8]>)>,
+    /// The children of the node, in order
+    children: Vec<Self>,
+}
+
+/// Checks that the given name is a valid unit name for a (non-root) node
+fn validate_name(name: &[u8]) -> Result<(), Error> {
+    let (node_name, unit_address) = name
+        .split_once(|&char| char == b'@')
+        .map_or((name, None), |(node_name, unit_address)| {
+            (node_name, Some(unit_address))
+        });
+    if node_name.is_empty()
+        || node_name.len() > NameRef::MAX_NODE_NAME_LENGTH
+        || unit_address.is_some_and(<[u8]>::is_empty)
+        || !node_name
+            .iter()
+            .chain(unit_address.unwrap_or_default())
+            .all(|&byte| Char::is_valid(byte))
+    {
+        return Err(Error::InvalidName);
+    }
+    Ok(())
+}
+
+impl Node {
+    /// Creates a new node with the given unit name and no properties or children
+    ///
+    /// # Errors
+    /// Returns an error if the name is not a valid `node-name@unit-address`
+    #[inline]
+    pub fn new(name: &[u8]) -> Result<Self, Error> {
+        validate_name(name)?;
+        Ok(Self {
+            name: Box::from(name),
+            properties: Vec::new(),
+            children: Vec::new(),
+        })
+    }
+
+    /// Creates an empty root node, which is the only node with an empty name
+    fn root() -> Self {
+        Self {
+            name: Box::default(),
+            properties: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the unit name of this node
+    #[must_use]
+    #[inline]
+    pub const fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    /// Returns whether or not this node is referred to by the given path component,
+    /// either by its full unit name or by its node name alone
+    fn is_named(&self, name: &[u8]) -> bool {
+        *self.name == *name
+            || (!name.contains(&b'@')
+                && self
+                    .name
+                    .split(|&char| char == b'@')
+                    .next()
+                    .is_some_and(|node_name| node_name == name))
+    }
+
+    /// Returns the index of the child with the given name, preferring an exact match of the unit name.
+    /// A name without a unit address only matches if exactly one child has that node name
+    fn child_index(&self, name: &[u8]) -> Option<usize> {
+        self.children
+            .iter()
+            .position(|child| *child.name == *name)
+            .or_else(|| {
+                let mut matches = self
+                    .children
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, child)| child.is_named(name))
+                    .map(|(index, _)| index);
+                matches.next().filter(|_| matches.next().is_none())
+            })
+    }
+
+    /// Returns the children of this node
+    #[must_use]
+    #[inline]
+    pub fn children(&self) -> &[Self] {
+        &self.children
+    }
+
+    /// Returns the children of this node mutably
+    #[must_use]
+    #[inline]
+    pub fn children_mut(&mut self) -> &mut [Self] {
+        &mut self.children
+    }
+
+    /// Returns the child with the given name, if present.
+    /// The unit address may be omitted from the name if it is unambiguous
+    #[must_use]
+    #[inline]
+    pub fn child(&self, name: &[u8]) -> Option<&Self> {
+        self.children.get(self.child_index(name)?)
+    }
+
+    /// Returns the child with the given name mutably, if present
+    #[must_use]
+    #[inline]
+    pub fn child_mut(&mut self, name: &[u8]) -> Option<&mut Self> {
+        let index = self.child_index(name)?;
+        self.children.get_mut(index)
+    }
+
+    /// Adds a node as the last child of this node, and returns a reference to it
+    ///
+    /// # Errors
+    /// Returns an error if a child with the same unit name already exists
+    #[inline]
+    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+    pub fn add_child(&mut self, child: Self) -> Result<&mut Self, Error> {
+        if self
+            .children
+            .iter()
+            .any(|existing| existing.name == child.name)
+        {
+            return Err(Error::DuplicateChild);
+        }
+        self.children.push(child);
+        Ok(self.children.last_mut().expect("A child was just pushed"))
+    }
+
+    /// Returns the child with the given unit name, creating an empty child if none exists
+    ///
+    /// # Errors
+    /// Returns an error if the name is not a valid `node-name@unit-address`
+    #[inline]
+    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+    pub fn child_or_insert(&mut self, name: &[u8]) -> Result<&mut Self, Error> {
+        match self.children.iter().position(|child| *child.name == *name) {
+            Some(index) => Ok(self
+                .children
+                .get_mut(index)
+                .expect("The index was just found")),
+            None => self.add_child(Self::new(name)?),
+        }
+    }
+
+    /// Removes the child with the given name, returning it if present
+    #[inline]
+    pub fn remove_child(&mut self, name: &[u8]) -> Option<Self> {
+        self.child_index(name)
+            .map(|index| self.children.remove(index))
+    }
+
+    /// Renames the child with the given name
+    ///
+    /// # Errors
+    /// Returns an error if the child does not exist, the new name is invalid, or a sibling already has the new name
+    #[inline]
+    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+    pub fn rename_child(&mut self, name: &[u8], new_name: &[u8]) -> Result<(), Error> {
+        validate_name(new_name)?;
+        let index = self.child_index(name).ok_or(Error::NotFound)?;
+        if self
+            .children
+            .iter()
+            .enumerate()
+            .any(|(other, child)| other != index && *child.name == *new_name)
+        {
+            return Err(Error::DuplicateChild);
+        }
+        self.children
+            .get_mut(index)
+            .expect("The index was just found")
+            .name = Box::from(new_name);
+        Ok(())
+    }
+
+    /// Returns an iterator over the properties of this node, in order
+    #[inline]
+    pub fn properties(&self) -> impl Iterator<Item = (&CStr, &[u8])> {
+        self.properties
+            .iter()
+            .map(|&(ref name, ref value)| (name.as_c_str(), value.as_ref()))
+    }
+
+    /// Returns the value of the given property, if present
+    #[must_use]
+    #[inline]
+    pub fn property(&self, name: &CStr) -> Option<&[u8]> {
+        self.properties
+            .iter()
+            .find(|&&(ref key, _)| key.as_c_str() == name)
+            .map(|&(_, ref value)| value.as_ref())
+    }
+
+    /// Returns the value of the given property as a single `u32` cell, if present and exactly one cell long
+    #[must_use]
+    #[inline]
+    pub fn property_u32(&self, name: &CStr) -> Option<u32> {
+        self.property(name)
+            .and_then(|value| <[u8; mem::size_of::<u32>()]>::try_from(value).ok())
+            .map(u32::from_be_bytes)
+    }
+
+    /// Sets the value of the given property, adding it if not present, and returns the previous value if any
+    #[inline]
+    pub fn set_property<V>(&mut self, name: &CStr, value: V) -> Option<Box<[u8]>>
+    where
+        V: Into<Box<[u8]>>,
+    {
+        let value = value.into();
+        if let Some(&mut (_, ref mut existing)) = self
+            .properties
+            .iter_mut()
+            .find(|&&mut (ref key, _)| key.as_c_str() == name)
+        {
+            Some(mem::replace(existing, value))
+        } else {
+            self.properties.push((CString::from(name), value));
+            None
+        }
+    }
+
+    /// Sets the value of the given property to a list of big-endian `u32` cells
+    #[inline]
+    pub fn set_property_cells(&mut self, name: &CStr, cells: &[u32]) -> Option<Box<[u8]>> {
+        self.set_property(
+            name,
+            cells
+                .iter()
+                .flat_map(|cell| cell.to_be_bytes())
+                .collect::<Box<[u8]>>(),
+        )
+    }
+
+    /// Sets the value of the given property to a single big-endian `u32` cell
+    #[inline]
+    pub fn set_property_u32(&mut self, name: &CStr, value: u32) -> Option<Box<[u8]>> {
+        self.set_property_cells(name, &[value])
+    }
+
+    /// Sets the value of the given property to a null-terminated string
+    #[inline]
+    pub fn set_property_str(&mut self, name: &CStr, value: &CStr) -> Option<Box<[u8]>> {
+        self.set_property(name, value.to_bytes_with_nul())
+    }
+
+    /// Removes the given property, returning its value if present
+    #[inline]
+    pub fn remove_property(&mut self, name: &CStr) -> Option<Box<[u8]>> {
+        self.properties
+            .iter()
+            .position(|&(ref key, _)| key.as_c_str() == name)
+            .map(|index| self.properties.remove(index).1)
+    }
+
+    /// Marks this node as disabled via its `status` property
+    #[inline]
+    pub fn disable(&mut self) {
+        self.set_property(PropertyKeys::STATUS, b"disabled\0".as_slice());
+    }
+
+    /// Writes this node and all of its descendants into the given writer
+    fn write(&self, writer: &mut Writer) -> Result<(), writer::Error> {
+        writer.begin_node(&self.name)?;
+        for &(ref name, ref value) in &self.properties {
+            writer.property(name, value)?;
+        }
+        for child in &self.children {
+            child.write(writer)?;
+        }
+        writer.end_node()
+    }
+}
+
+/// An editable device tree, along with the information in its header needed to serialize it
+#[derive(Debug, Clone)]
+pub struct Tree {
+    /// The root node
+    root: Node,
+    /// The regions of physical memory reserved by the memory reservation block
+    memory_reservations: Vec<(u64, u64)>,
+    /// The physical ID of the system’s boot CPU
+    boot_cpuid_phys: u32,
+}
+
+impl Tree {
+    /// Creates a new tree with an empty root node, no memory reservations, and the given boot CPU
+    #[must_use]
+    #[inline]
+    pub fn new(boot_cpuid_phys: u32) -> Self {
+        Self {
+            root: Node::root(),
+            memory_reservations: Vec::new(),
+            boot_cpuid_phys,
+        }
+    }
+
+    /// Parses a device tree blob into an editable tree.
+    ///
+    /// Only the structure of the blob is checked; the contents of nodes and properties are not interpreted
+    ///
+    /// # Errors
+    /// Returns an error if the header, memory reservation block, or structure block is malformed
+    #[inline]
+    pub fn from_bytes(dtb: &[u64]) -> Result<Self, DeviceTreeError<'_>> {
+        // SAFETY: It is safe to transmute a `u64` to `u8`s
+        let blocks = Blocks::locate(unsafe { transmute_slice_down(dtb) })?;
+        let memory_reservations = MemoryReservations::try_from(blocks.memory_reservations)
+            .map_err(DeviceTreeError::MemoryReservations)?;
+        Self::from_structure(
+            blocks.structure,
+            blocks.strings,
+            memory_reservations.entries(),
+            blocks.header.boot_cpuid_phys(),
+        )
+    }
+
+    /// Builds an editable tree from the tokens of a structure block
+    #[expect(clippy::unwrap_in_result, reason = "Checks should never fail")]
+    pub(crate) fn from_structure<'dtb>(
+        structure: U32ByteSlice<'dtb>,
+        strings: &'dtb [u8],
+        memory_reservations: &[(u64, u64)],
+        boot_cpuid_phys: u32,
+    ) -> Result<Self, DeviceTreeError<'dtb>> {
+        let mut stack = vec![Node::root()];
+        let mut root_names = Vec::new();
+        let mut finished = false;
+        for token in Token::iterate_bytes(structure, strings) {
+            match token.map_err(DeviceTreeError::Token)? {
+                Token::BeginNode(name) => {
+                    if finished {
+                        return Err(DeviceTreeError::Parsing);
+                    }
+                    if stack.len() == 1 {
+                        root_names.push(NameRef::try_from(name).map_err(|err| {
+                            DeviceTreeError::Token(TokenError::NodeNameInvalid(err))
+                        })?);
+                    }
+                    stack.push(Node {
+                        name: Box::from(name),
+                        properties: Vec::new(),
+                        children: Vec::new(),
+                    });
+                }
+                Token::EndNode => {
+                    if stack.len() < 2 {
+                        return Err(DeviceTreeError::TooManyEnds);
+                    }
+                    let node = stack.pop().expect("The stack depth was just checked");
+                    stack
+                        .last_mut()
+                        .expect("The stack depth was just checked")
+                        .children
+                        .push(node);
+                }
+                Token::Prop(name, value) => {
+                    if stack.len() < 2 {
+                        return Err(DeviceTreeError::InvalidProp);
+                    }
+                    let node = stack.last_mut().expect("The stack depth was just checked");
+                    if node.property(name).is_some() {
+                        // Duplicate property is bad
+                        return Err(DeviceTreeError::DuplicateProperty);
+                    }
+                    node.properties
+                        .push((CString::from(name), Box::from(<&[u8]>::from(value))));
+                }
+                Token::Nop => {}
+                Token::End => {
+                    if finished {
+                        return Err(DeviceTreeError::TooManyEnds);
+                    }
+                    if stack.len() != 1 {
+                        return Err(DeviceTreeError::BadDepth(stack.len()));
+                    }
+                    finished = true;
+                }
+            }
+        }
+
+        let mut roots = stack
+            .pop()
+            .expect("The stack should never be empty")
+            .children;
+        if !finished {
+            return Err(DeviceTreeError::EoF);
+        }
+        // There should be exactly one root
+        if roots.len() != 1 {
+            return Err(DeviceTreeError::BadRoots(root_names.into_boxed_slice()));
+        }
+        let root = roots
+            .pop()
+            .expect("Number of roots should be exactly one after the check");
+        // The full path to the root node is /
+        if !root.name.is_empty() {
+            return Err(DeviceTreeError::BadRootName(
+                root_names
+                    .pop()
+                    .expect("Each root should have its name recorded"),
+            ));
+        }
+
+        Ok(Self {
+            root,
+            memory_reservations: memory_reservations.to_vec(),
+            boot_cpuid_phys,
+        })
+    }
+
+    /// Serializes this tree into a device tree blob
+    ///
+    /// # Errors
+    /// Returns an error if the tree is too large to be represented in a blob
+    #[inline]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, writer::Error> {
+        let mut writer = Writer::new();
+        self.root.write(&mut writer)?;
+        writer.finish(&self.memory_reservations, self.boot_cpuid_phys)
+    }
+
+    /// Returns a `Display`able rendering of this tree as device tree source
+    #[must_use]
+    #[inline]
+    pub const fn dts(&self) -> Dts<'_> {
+        Dts::new(self)
+    }
+
+    /// Returns the root node of the tree
+    #[must_use]
+    #[inline]
+    pub const fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// Returns the root node of the tree mutably
+    #[must_use]
+    #[inline]
+    pub fn root_mut(&mut self) -> &mut Node {
+        &mut self.root
+    }
+
+    /// Returns the node at the given absolute path, e.g. `/soc/serial@7e201000`, if present
+    #[must_use]
+    #[inline]
+    pub fn find(&self, path: &[u8]) -> Option<&Node> {
+        path.strip_prefix(b"/")?
+            .split(|&char| char == b'/')
+            .filter(|component| !component.is_empty())
+            .try_fold(&self.root, |node, component| node.child(component))
+    }
+
+    /// Returns the node at the given absolute path mutably, if present
+    #[must_use]
+    #[inline]
+    pub fn find_mut(&mut self, path: &[u8]) -> Option<&mut Node> {
+        path.strip_prefix(b"/")?
+            .split(|&char| char == b'/')
+            .filter(|component| !component.is_empty())
+            .try_fold(&mut self.root, |node, component| node.child_mut(component))
+    }
+
+    /// Removes the node at the given absolute path, returning it if present
+    ///
+    /// # Errors
+    /// Returns an error if the node does not exist, or if the path refers to the root node
+    #[inline]
+    pub fn remove_node(&mut self, path: &[u8]) -> Result<Node, Error> {
+        let (parent, name) = path
+            .rsplit_once(|&char| char == b'/')
+            .filter(|&(_, name)| !name.is_empty())
+            .ok_or(Error::NotFound)?;
+        let parent = if parent.is_empty() {
+            &mut self.root
+        } else {
+            self.find_mut(parent).ok_or(Error::NotFound)?
+        };
+        parent.remove_child(name).ok_or(Error::NotFound)
+    }
+
+    /// Returns the memory reservations of this tree, as (address, size) pairs
+    #[must_use]
+    #[inline]
+    pub fn memory_reservations(&self) -> &[(u64, u64)] {
+        &self.memory_reservations
+    }
+
+    /// Returns the memory reservations of this tree mutably
+    #[must_use]
+    #[inline]
+    pub fn memory_reservations_mut(&mut self) -> &mut Vec<(u64, u64)> {
+        &mut self.memory_reservations
+    }
+
+    /// Returns the physical ID of the boot CPU
+    #[must_use]
+    #[inline]
+    pub const fn boot_cpuid_phys(&self) -> u32 {
+        self.boot_cpuid_phys
+    }
+
+    /// Sets the physical ID of the boot CPU
+    #[inline]
+    pub fn set_boot_cpuid_phys(&mut self, boot_cpuid_phys: u32) {
+        self.boot_cpuid_phys = boot_cpuid_phys;
+    }
+}
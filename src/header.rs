@@ -0,0 +1,301 @@
+//! The header of a flattened device tree blob
+//!
+//! The header locates each of the blocks of the blob, and records the versions of the blob format along with the physical ID of the boot CPU.
+//! It can be parsed and validated on its own, such as to size or relocate a blob without parsing its contents
+
+use crate::dtb::DeviceTree;
+use core::{fmt, mem};
+
+/// The magic bytes located at the start of the device tree
+const FDT_HEADER_MAGIC: u32 = 0xD00D_FEED;
+/// The size in bytes of a single entry of the memory reservation block, which is also the size of its terminating entry
+const RESERVATION_ENTRY_SIZE: usize = 2 * mem::size_of::<u64>();
+
+/// Errors from parsing or validating a header
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Fewer bytes were provided than the size of the header
+    EoF,
+    /// The magic bytes at the beginning of the header were incorrect
+    Magic,
+    /// The blob is not backwards compatible with the version parsed by this crate, as (version, last compatible version)
+    NewerVersion((u32, u32)),
+    /// Some field does not fit into a `usize`
+    Size,
+    /// A block is not aligned as required, relative to the start of the blob
+    Alignment,
+    /// The memory reservation block does not fit within the blob, as its offset
+    ReservationsIndex(usize),
+    /// The structure block does not fit within the blob, as (offset, size)
+    StructIndex((usize, usize)),
+    /// The strings block does not fit within the blob, as (offset, size)
+    StringsIndex((usize, usize)),
+    /// Two blocks, or a block and the header, overlap each other
+    Overlap,
+}
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::EoF => write!(f, "header is shorter than {} bytes", Header::SIZE),
+            Self::Magic => f.write_str("incorrect magic bytes"),
+            Self::NewerVersion((version, last_compatible_version)) => write!(
+                f,
+                "version {version} is only compatible back to version {last_compatible_version}, newer than the supported version {}",
+                DeviceTree::VERSION_PARSED
+            ),
+            Self::Size => f.write_str("some field does not fit into a `usize`"),
+            Self::Alignment => f.write_str("some block is misaligned"),
+            Self::ReservationsIndex(offset) => {
+                write!(f, "memory reservation block at offset {offset:#X} is out of bounds")
+            }
+            Self::StructIndex((offset, size)) => write!(
+                f,
+                "structure block of {size:#X} bytes at offset {offset:#X} is out of bounds"
+            ),
+            Self::StringsIndex((offset, size)) => write!(
+                f,
+                "strings block of {size:#X} bytes at offset {offset:#X} is out of bounds"
+            ),
+            Self::Overlap => f.write_str("some blocks overlap"),
+        }
+    }
+}
+
+/// The header of a flattened device tree blob, which is located at the very start of the blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    /// This field shall contain the total size in bytes of the devicetree data structure.
+    /// This size shall encompass all sections of the structure: the header, the memory reservation block, structure block and strings block,
+    /// as well as any free space gaps between the blocks or after the final block.
+    total_size: usize,
+    /// This field shall contain the offset in bytes of the structure block from the beginning of the header.
+    structure_offset: usize,
+    /// This field shall contain the offset in bytes of the strings block from the beginning of the header.
+    strings_offset: usize,
+    /// This field shall contain the offset in bytes of the memory reservation block from the beginning of the header.
+    memory_reservations_offset: usize,
+    /// This field shall contain the version of the devicetree data structure.
+    /// The version is 17 if using the structure as parsed in this crate.
+    /// An `DTSpec` boot program may provide the devicetree of a later version,
+    /// in which case this field shall contain the version number defined in whichever later document gives the details of that version.
+    ///
+    /// Note: The version is with respect to the binary structure of the device tree, not its content.
+    version: u32,
+    /// This field shall contain the lowest version of the devicetree data structure with which the version used is backwards compatible.
+    /// So, for the structure as parsed in this crate (version 17),
+    /// this field shall contain 16 because version 17 is backwards compatible with version 16,
+    /// but not earlier versions.
+    /// A `DTSpec` boot program should provide a devicetree in a format which is backwards compatible with version 16,
+    /// and thus this field shall always contain 16.
+    last_compatible_version: u32,
+    /// This field shall contain the physical ID of the system’s boot CPU.
+    /// It shall be identical to the physical ID given in the `reg` property of that CPU node within the devicetree.
+    boot_cpuid_phys: u32,
+    /// This field shall contain the length in bytes of the strings block section of the devicetree blob.
+    strings_size: usize,
+    /// This field shall contain the length in bytes of the structure block section of the devicetree blob.
+    structure_size: usize,
+}
+
+/// Returns whether or not the two given ranges of bytes overlap, as (offset, size) pairs
+const fn overlaps(
+    (first_offset, first_size): (usize, usize),
+    (second_offset, second_size): (usize, usize),
+) -> bool {
+    first_size != 0
+        && second_size != 0
+        && first_offset < second_offset.saturating_add(second_size)
+        && second_offset < first_offset.saturating_add(first_size)
+}
+
+impl Header {
+    /// The number of fields in the header
+    const FIELDS: usize = 10;
+    /// The size of the header in bytes
+    pub const SIZE: usize = Self::FIELDS * mem::size_of::<u32>();
+
+    /// Parses and validates the header at the start of the given bytes, which need not be aligned nor contain the rest of the blob.
+    ///
+    /// Each block is checked to lie within the `total_size` of the blob, with its required alignment, and without overlapping the header or other blocks
+    ///
+    /// # Errors
+    /// Returns an error if the header is malformed, or if the blocks it describes are out of bounds, misaligned, or overlapping
+    #[expect(clippy::unwrap_in_result, reason = "Checks should never fail")]
+    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+    #[inline]
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let (raw, _) = bytes
+            .split_first_chunk::<{ Self::SIZE }>()
+            .ok_or(Error::EoF)?;
+        let mut fields = [0; Self::FIELDS];
+        for (field, field_bytes) in fields
+            .iter_mut()
+            .zip(raw.chunks_exact(mem::size_of::<u32>()))
+        {
+            *field = u32::from_be_bytes(
+                field_bytes
+                    .try_into()
+                    .expect("Chunks should be exactly the size of a `u32`"),
+            );
+        }
+        let [magic, total_size, structure_offset, strings_offset, memory_reservations_offset, version, last_compatible_version, boot_cpuid_phys, strings_size, structure_size] =
+            fields;
+        let size = |field: u32| usize::try_from(field).map_err(|_err| Error::Size);
+
+        if magic != FDT_HEADER_MAGIC {
+            return Err(Error::Magic);
+        }
+        if last_compatible_version > DeviceTree::VERSION_PARSED {
+            return Err(Error::NewerVersion((version, last_compatible_version)));
+        }
+
+        let header = Self {
+            total_size: size(total_size)?,
+            structure_offset: size(structure_offset)?,
+            strings_offset: size(strings_offset)?,
+            memory_reservations_offset: size(memory_reservations_offset)?,
+            version,
+            last_compatible_version,
+            boot_cpuid_phys,
+            strings_size: size(strings_size)?,
+            structure_size: size(structure_size)?,
+        };
+        header.validate()?;
+        Ok(header)
+    }
+
+    /// Checks that each block lies within the blob, is properly aligned, and does not overlap the header or any other block
+    fn validate(&self) -> Result<(), Error> {
+        /// Returns whether or not the block at the given offset and of the given size fits within the given total size
+        fn fits(offset: usize, size: usize, total_size: usize) -> bool {
+            offset
+                .checked_add(size)
+                .is_some_and(|end| end <= total_size)
+        }
+
+        // The memory reservation block shall be aligned to an 8-byte boundary,
+        // while the structure block and its contents shall be aligned to 4-byte boundaries
+        if self
+            .memory_reservations_offset
+            .checked_rem(mem::size_of::<u64>())
+            != Some(0)
+            || self.structure_offset.checked_rem(mem::size_of::<u32>()) != Some(0)
+            || self.structure_size.checked_rem(mem::size_of::<u32>()) != Some(0)
+        {
+            return Err(Error::Alignment);
+        }
+
+        // The memory reservation block must contain at least the terminating entry
+        if !fits(
+            self.memory_reservations_offset,
+            RESERVATION_ENTRY_SIZE,
+            self.total_size,
+        ) {
+            return Err(Error::ReservationsIndex(self.memory_reservations_offset));
+        }
+        if !fits(self.structure_offset, self.structure_size, self.total_size) {
+            return Err(Error::StructIndex((
+                self.structure_offset,
+                self.structure_size,
+            )));
+        }
+        if !fits(self.strings_offset, self.strings_size, self.total_size) {
+            return Err(Error::StringsIndex((
+                self.strings_offset,
+                self.strings_size,
+            )));
+        }
+
+        let header = (0, Self::SIZE);
+        let memory_reservations = (self.memory_reservations_offset, RESERVATION_ENTRY_SIZE);
+        let structure = (self.structure_offset, self.structure_size);
+        let strings = (self.strings_offset, self.strings_size);
+        if overlaps(header, memory_reservations)
+            || overlaps(header, structure)
+            || overlaps(header, strings)
+            || overlaps(memory_reservations, structure)
+            || overlaps(memory_reservations, strings)
+            || overlaps(structure, strings)
+        {
+            return Err(Error::Overlap);
+        }
+        Ok(())
+    }
+
+    /// Returns the offset of the end of the memory reservation block,
+    /// which is the start of the first block after it, or the end of the blob if no block follows it
+    #[must_use]
+    #[inline]
+    pub fn memory_reservations_end(&self) -> usize {
+        [self.structure_offset, self.strings_offset]
+            .into_iter()
+            .filter(|&offset| offset > self.memory_reservations_offset)
+            .fold(self.total_size, usize::min)
+    }
+
+    /// Returns the total size of the blob in bytes, including all blocks and any free space
+    #[must_use]
+    #[inline]
+    pub const fn total_size(&self) -> usize {
+        self.total_size
+    }
+
+    /// Returns the offset in bytes of the structure block from the start of the blob
+    #[must_use]
+    #[inline]
+    pub const fn structure_offset(&self) -> usize {
+        self.structure_offset
+    }
+
+    /// Returns the size in bytes of the structure block
+    #[must_use]
+    #[inline]
+    pub const fn structure_size(&self) -> usize {
+        self.structure_size
+    }
+
+    /// Returns the offset in bytes of the strings block from the start of the blob
+    #[must_use]
+    #[inline]
+    pub const fn strings_offset(&self) -> usize {
+        self.strings_offset
+    }
+
+    /// Returns the size in bytes of the strings block
+    #[must_use]
+    #[inline]
+    pub const fn strings_size(&self) -> usize {
+        self.strings_size
+    }
+
+    /// Returns the offset in bytes of the memory reservation block from the start of the blob
+    #[must_use]
+    #[inline]
+    pub const fn memory_reservations_offset(&self) -> usize {
+        self.memory_reservations_offset
+    }
+
+    /// Returns the version of the blob format
+    #[must_use]
+    #[inline]
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the lowest version of the blob format with which the blob is backwards compatible
+    #[must_use]
+    #[inline]
+    pub const fn last_compatible_version(&self) -> u32 {
+        self.last_compatible_version
+    }
+
+    /// Returns the physical ID of the boot CPU
+    #[must_use]
+    #[inline]
+    pub const fn boot_cpuid_phys(&self) -> u32 {
+        self.boot_cpuid_phys
+    }
+}
@@ -42,7 +42,6 @@
 #![feature(let_chains)]
 #![feature(ptr_metadata)]
 #![feature(stmt_expr_attributes)]
-#![feature(iterator_try_reduce)]
 #![feature(extract_if)]
 #![feature(slice_ptr_get)]
 #![feature(concat_bytes)]
@@ -53,12 +52,20 @@ use core::{mem, num::NonZeroUsize, ptr::NonNull};
 extern crate alloc;
 
 pub mod dtb;
+pub mod dts;
+pub mod edit;
+pub mod header;
 mod map;
-mod memory_reservation;
+pub mod memory_map;
+pub mod memory_reservation;
 pub mod node;
 mod node_name;
+pub mod overlay;
 mod parse;
 mod property;
+pub mod rc;
+pub mod walker;
+pub mod writer;
 
 /// Splits a slice at the first instance of the given value, returning the slice up to, but not including, said element, and the slice beginning immediately after.
 /// In other words, returns the two slices formed by introducing a "hole" at the first matching element
@@ -99,3 +106,32 @@ unsafe fn transmute_slice_down<P, Q>(slice: &[P]) -> &[Q] {
     // The lifetime of the underlying data is guaranteed to be immutable because the original shared slice guarantees the underlying bytes are not mutated
     unsafe { transmuted_pointer.as_ref() }
 }
+
+/// Returns whether or not the given pointer is suitably aligned to point to a `T`
+fn is_aligned_for<T>(pointer: *const u8) -> bool {
+    pointer.addr().checked_rem(mem::align_of::<T>()) == Some(0)
+}
+
+/// Plain integer types, for which any bytes of the correct size are a valid value
+trait Plain: Copy {}
+impl Plain for u32 {}
+impl Plain for u64 {}
+
+/// Reinterprets a slice of bytes as a slice of a larger, plain type, ignoring any trailing bytes that do not make up a whole element
+///
+/// Returns `None` if the slice is not properly aligned for the larger type
+fn transmute_bytes_up<T>(bytes: &[u8]) -> Option<&[T]>
+where
+    T: Plain,
+{
+    if !is_aligned_for::<T>(bytes.as_ptr()) {
+        return None;
+    }
+    let pointer = NonNull::from(bytes).as_non_null_ptr().cast::<T>();
+    let length = bytes.len().checked_div(mem::size_of::<T>())?;
+    // SAFETY: The pointer is valid, accessible, initialized, and aligned because it comes from a valid, initialized region of memory and was checked above;
+    // the length covers only whole elements within the original slice;
+    // and any bytes are valid values of a `Plain` type.
+    // The lifetime of the underlying data is guaranteed to be immutable because the original shared slice guarantees the underlying bytes are not mutated
+    Some(unsafe { NonNull::slice_from_raw_parts(pointer, length).as_ref() })
+}
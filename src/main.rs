@@ -1,7 +1,5 @@
-#![feature(iter_array_chunks)]
 #![feature(iter_intersperse)]
 
-use core::mem;
 use std::{
     env,
     error::Error,
@@ -13,27 +11,23 @@ use device_tree::node::Node;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let dt_path = env::args().nth(1).ok_or("Missing path to DTB")?;
-    let mut dt_contents = fs::read(dt_path)?;
-    while dt_contents.len() % mem::size_of::<u64>() != 0 {
-        dt_contents.push(0)
+    let aligned_dt = device_tree::dtb::copy_aligned(&fs::read(dt_path)?);
+    let device_tree = device_tree::dtb::DeviceTree::from_bytes_with(
+        &aligned_dt,
+        device_tree::dtb::ParseOptions::lenient(),
+    )
+    .map_err(|err| err.to_string())?;
+    for warning in device_tree.warnings() {
+        eprintln!("warning: {warning}");
     }
 
-    let aligned_dt: Box<_> = dt_contents
-        .into_iter()
-        .array_chunks::<{ mem::size_of::<u64>() / mem::size_of::<u8>() }>()
-        .map(u64::from_ne_bytes)
-        .collect();
-    // let aligned_dt_addr =
-    // NonNull::new(Box::into_raw(aligned_dt)).expect("Boxes should never be null pointers");
-    let device_tree = device_tree::dtb::DeviceTree::from_bytes(&aligned_dt).unwrap();
-
     let root = device_tree.root();
     println!(
         "{:#X?}",
         device_tree
             .root()
             .find_str("/soc".as_bytes())
-            .unwrap()
+            .ok_or("Missing /soc node")?
             .children()
             .iter()
             .map(|(x, _)| x)
@@ -0,0 +1,396 @@
+//! The usable physical memory of a system, computed from the `/memory` nodes, the memory reservation block, and the `/reserved-memory` node
+//!
+//! Memory is free for general use if some `/memory` node lists it, and neither the memory reservation block nor a static `/reserved-memory` allocation covers it.
+//! Ranges are computed with exclusive ends wider than any address, so that regions reaching the top of the address space are handled exactly.
+//!
+//! Dynamic `/reserved-memory` allocations are then placed within the free memory, as Linux does at boot:
+//! each is placed at the highest suitably aligned address within the first of its `alloc-ranges` that can hold it.
+//! Unlike Linux, which places them in the order they appear in the devicetree, they are placed in order of their unit names,
+//! as the children of `/reserved-memory` are kept sorted by name once parsed
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{fmt, mem};
+
+use crate::map::Map;
+use crate::memory_reservation::MemoryReservations;
+use crate::node::{memory_region::MemoryRegion, reserved_memory, root};
+use crate::rc::Rc;
+
+/// A range of addresses, as its start and exclusive end
+type Span = (u128, u128);
+
+/// The reason that a range of memory is not free for general use
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Reason<'dtb> {
+    /// The range is listed in the memory reservation block of the blob's header
+    Reservation,
+    /// The range is statically allocated by the given child of the `/reserved-memory` node
+    ReservedMemory(Rc<reserved_memory::Node<'dtb>>),
+}
+
+/// A range of memory that is not free for general use, along with the reason why.
+/// Ranges excluded for several reasons are listed once per reason
+#[derive(Debug, Clone)]
+pub struct Excluded<'dtb> {
+    /// The address of the start of the range
+    address: u64,
+    /// The size of the range
+    size: u64,
+    /// The reason that the range is excluded
+    reason: Reason<'dtb>,
+}
+
+impl<'dtb> Excluded<'dtb> {
+    /// Returns the address of the start of the range
+    #[must_use]
+    #[inline]
+    pub const fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Returns the size of the range
+    #[must_use]
+    #[inline]
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the reason that the range is excluded
+    #[must_use]
+    #[inline]
+    pub const fn reason(&self) -> &Reason<'dtb> {
+        &self.reason
+    }
+}
+
+/// The physical memory of a system, split into the ranges that are free for general use and the ranges that are excluded from it
+#[derive(Debug, Clone)]
+pub struct MemoryMap<'dtb> {
+    /// The free ranges, as (address, size) pairs sorted by address. Adjacent and overlapping ranges are coalesced
+    free: Box<[(u64, u64)]>,
+    /// The excluded ranges that lie within memory, sorted by address
+    excluded: Box<[Excluded<'dtb>]>,
+}
+
+/// Errors from placing a dynamic reservation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AllocationError {
+    /// The requested size is zero
+    Size,
+    /// The requested alignment is not a power of two
+    Alignment(u64),
+    /// No free memory within the permitted ranges can hold the reservation at the requested alignment
+    NoSpace,
+}
+
+impl fmt::Display for AllocationError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Size => f.write_str("cannot allocate an empty region"),
+            Self::Alignment(alignment) => {
+                write!(f, "alignment {alignment:#X} is not a power of two")
+            }
+            Self::NoSpace => {
+                f.write_str("no free memory within `alloc-ranges` can hold the region")
+            }
+        }
+    }
+}
+
+/// A dynamic reservation, along with the address it was placed at
+#[derive(Debug, Clone)]
+pub struct Placement<'dtb> {
+    /// The child of the `/reserved-memory` node that requested the reservation
+    node: Rc<reserved_memory::Node<'dtb>>,
+    /// The size of the reservation
+    size: u64,
+    /// The address that the reservation was placed at, or why it could not be placed
+    address: Result<u64, AllocationError>,
+}
+
+impl<'dtb> Placement<'dtb> {
+    /// Returns the child of the `/reserved-memory` node that requested the reservation
+    #[must_use]
+    #[inline]
+    pub const fn node(&self) -> &Rc<reserved_memory::Node<'dtb>> {
+        &self.node
+    }
+
+    /// Returns the size of the reservation
+    #[must_use]
+    #[inline]
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the address that the reservation was placed at
+    ///
+    /// # Errors
+    /// Returns an error if the reservation could not be satisfied
+    #[inline]
+    pub const fn address(&self) -> Result<u64, AllocationError> {
+        self.address
+    }
+}
+
+/// The memory map of a system after placing every dynamic reservation of the `/reserved-memory` node
+#[derive(Debug, Clone)]
+pub struct Allocation<'dtb> {
+    /// The memory map, in which each placed reservation is excluded
+    memory_map: MemoryMap<'dtb>,
+    /// Every dynamic reservation, in the order they were placed, which is sorted by unit name
+    placements: Box<[Placement<'dtb>]>,
+}
+
+impl<'dtb> Allocation<'dtb> {
+    /// Returns the memory map, in which each placed reservation is excluded from the free memory
+    #[must_use]
+    #[inline]
+    pub const fn memory_map(&self) -> &MemoryMap<'dtb> {
+        &self.memory_map
+    }
+
+    /// Returns every dynamic reservation, in the order they were placed, along with where each was placed.
+    /// Reservations are placed in order of their unit names, rather than the order they appear in the devicetree
+    #[must_use]
+    #[inline]
+    pub fn placements(&self) -> &[Placement<'dtb>] {
+        &self.placements
+    }
+
+    /// Returns an iterator over the dynamic reservations that could not be placed
+    #[inline]
+    pub fn unsatisfied(&self) -> impl Iterator<Item = &Placement<'dtb>> {
+        self.placements
+            .iter()
+            .filter(|placement| placement.address.is_err())
+    }
+}
+
+/// Returns the span of the region of the given size at the given address
+fn span(address: u64, size: u64) -> Span {
+    let start = u128::from(address);
+    (start, start.saturating_add(size.into()))
+}
+
+/// Converts a non-empty span back into an (address, size) pair
+///
+/// Every span starts at a `u64` address, and the only span too large for a `u64` size is the entire address space, whose size is rounded down
+fn region((start, end): Span) -> (u64, u64) {
+    (
+        u64::try_from(start).expect("Spans should start at a `u64` address"),
+        u64::try_from(end.saturating_sub(start)).unwrap_or(u64::MAX),
+    )
+}
+
+impl<'dtb> MemoryMap<'dtb> {
+    /// Computes the memory map from the `/memory` and `/reserved-memory` nodes under the given root node, and the given memory reservation block
+    pub(crate) fn new(root: &root::Node<'dtb>, reservations: &MemoryReservations) -> Self {
+        let mut memory: Vec<Span> = root
+            .memory()
+            .iter()
+            .flat_map(MemoryRegion::regions)
+            .map(|&(address, size)| span(address, size))
+            .filter(|&(start, end)| start < end)
+            .collect();
+        memory.sort_unstable();
+        let mut coalesced: Vec<Span> = Vec::with_capacity(memory.len());
+        for (start, end) in memory {
+            match coalesced.last_mut().filter(|last| start <= last.1) {
+                Some(last) => last.1 = last.1.max(end),
+                None => coalesced.push((start, end)),
+            }
+        }
+
+        let mut exclusions: Vec<(Span, Reason<'dtb>)> = reservations
+            .entries()
+            .iter()
+            .map(|&(address, size)| (span(address, size), Reason::Reservation))
+            .collect();
+        for &(_, ref node) in root.reserved_memory().into_iter().flat_map(Map::iter) {
+            if let reserved_memory::Range::Static(ref regions) = *node.memory() {
+                // Regions above the 64-bit address space cannot overlap any memory
+                exclusions.extend(regions.iter().filter_map(|&(address, size)| {
+                    Some((
+                        span(address.to_u64()?, size),
+                        Reason::ReservedMemory(Rc::clone(node)),
+                    ))
+                }));
+            }
+        }
+        exclusions.retain(|&((start, end), _)| start < end);
+        exclusions.sort_unstable_by_key(|&(span, _)| span);
+
+        let mut free = Vec::new();
+        for &(start, end) in &coalesced {
+            let mut cursor = start;
+            for &((exclusion_start, exclusion_end), _) in &exclusions {
+                if exclusion_start >= end {
+                    break;
+                }
+                if exclusion_start > cursor {
+                    free.push(region((cursor, exclusion_start)));
+                }
+                cursor = cursor.max(exclusion_end);
+            }
+            if cursor < end {
+                free.push(region((cursor, end)));
+            }
+        }
+
+        let mut excluded: Vec<_> = exclusions
+            .iter()
+            .flat_map(|&((exclusion_start, exclusion_end), ref reason)| {
+                coalesced.iter().filter_map(move |&(start, end)| {
+                    let overlap = (start.max(exclusion_start), end.min(exclusion_end));
+                    (overlap.0 < overlap.1).then(|| {
+                        let (address, size) = region(overlap);
+                        Excluded {
+                            address,
+                            size,
+                            reason: reason.clone(),
+                        }
+                    })
+                })
+            })
+            .collect();
+        excluded.sort_by_key(|exclusion| exclusion.address);
+
+        Self {
+            free: free.into_boxed_slice(),
+            excluded: excluded.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the ranges of memory that are free for general use, as (address, size) pairs sorted by address.
+    /// No two ranges overlap or are adjacent
+    #[must_use]
+    #[inline]
+    pub fn free(&self) -> &[(u64, u64)] {
+        &self.free
+    }
+
+    /// Returns the ranges of memory that are excluded from general use, sorted by address, each tagged with the reason it is excluded.
+    /// Only the parts of reservations that lie within memory are listed
+    #[must_use]
+    #[inline]
+    pub fn excluded(&self) -> &[Excluded<'dtb>] {
+        &self.excluded
+    }
+
+    /// Places the given dynamic reservation at the highest suitably aligned free address within the first of the given windows that can hold it,
+    /// and excludes it from the free memory
+    fn allocate(
+        &mut self,
+        size: u64,
+        alignment: Option<u64>,
+        windows: &[Span],
+        reason: Reason<'dtb>,
+    ) -> Result<u64, AllocationError> {
+        if size == 0 {
+            return Err(AllocationError::Size);
+        }
+        let alignment = alignment.unwrap_or(1);
+        if !alignment.is_power_of_two() {
+            return Err(AllocationError::Alignment(alignment));
+        }
+        let mask = !u128::from(alignment.wrapping_sub(1));
+        let (index, start) = windows
+            .iter()
+            .find_map(|&(window_start, window_end)| {
+                self.free
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find_map(|(index, &free)| {
+                        let (free_start, free_end) = span(free.0, free.1);
+                        let (start, end) = (free_start.max(window_start), free_end.min(window_end));
+                        let top = end.checked_sub(size.into())? & mask;
+                        (top >= start).then_some((index, top))
+                    })
+            })
+            .ok_or(AllocationError::NoSpace)?;
+
+        let (free_start, free_end) = self
+            .free
+            .get(index)
+            .map(|&(address, free_size)| span(address, free_size))
+            .expect("The index should be that of a free range");
+        let end = start.saturating_add(size.into());
+        let mut free = Vec::from(mem::take(&mut self.free));
+        free.splice(
+            index..=index,
+            [(free_start, start), (end, free_end)]
+                .into_iter()
+                .filter(|&(remaining_start, remaining_end)| remaining_start < remaining_end)
+                .map(region),
+        );
+        self.free = free.into_boxed_slice();
+
+        let (address, _) = region((start, end));
+        let mut excluded = Vec::from(mem::take(&mut self.excluded));
+        excluded.insert(
+            excluded.partition_point(|exclusion| exclusion.address <= address),
+            Excluded {
+                address,
+                size,
+                reason,
+            },
+        );
+        self.excluded = excluded.into_boxed_slice();
+        Ok(address)
+    }
+
+    /// Places every dynamic reservation of the `/reserved-memory` node under the given root node in order of their unit names, excluding each from the free memory.
+    /// Reservations that cannot be placed are reported instead of failing the whole allocation
+    pub(crate) fn allocate_dynamic(mut self, root: &root::Node<'dtb>) -> Allocation<'dtb> {
+        let mut placements = Vec::new();
+        for &(_, ref node) in root.reserved_memory().into_iter().flat_map(Map::iter) {
+            if let reserved_memory::Range::Dynamic(size, alignment, ref alloc_ranges) =
+                *node.memory()
+            {
+                let windows: Vec<Span> = alloc_ranges.as_ref().map_or_else(
+                    || Vec::from([(0, u128::from(u64::MAX).saturating_add(1))]),
+                    |alloc_ranges| {
+                        // Ranges above the 64-bit address space cannot overlap any memory
+                        alloc_ranges
+                            .iter()
+                            .filter_map(|&(address, length)| {
+                                address.to_u64().map(|start| span(start, length))
+                            })
+                            .collect()
+                    },
+                );
+                let address = self.allocate(
+                    size,
+                    alignment,
+                    &windows,
+                    Reason::ReservedMemory(Rc::clone(node)),
+                );
+                placements.push(Placement {
+                    node: Rc::clone(node),
+                    size,
+                    address,
+                });
+            }
+        }
+        Allocation {
+            memory_map: self,
+            placements: placements.into_boxed_slice(),
+        }
+    }
+
+    /// Returns whether or not the given address lies within some free range
+    #[must_use]
+    #[inline]
+    pub fn is_free(&self, address: u64) -> bool {
+        self.free.iter().any(|&(start, size)| {
+            address
+                .checked_sub(start)
+                .is_some_and(|offset| offset < size)
+        })
+    }
+}
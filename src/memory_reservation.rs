@@ -13,30 +13,101 @@
 //! This requirement is necessary because the client program is permitted to map memory with storage attributes specified as not Write Through Required, not Caching Inhibited, and Memory Coherence Required (i.e., WIMG = 0b001x), and VLE=0 where supported. The client program may use large virtual pages that contain reserved memory. However, the client program may not modify reserved memory, so the boot program may perform accesses to reserved memory as Write Through Required where conflicting values for this storage attribute are architecturally permissible.
 
 use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
 
 /// Each pair gives the physical address and size in bytes of a reserved memory region. These given regions shall not overlap each other. The list of reserved blocks shall be terminated with an entry where both address and size are equal to 0.
+///
+/// The entries are sorted by address.
+#[derive(Debug)]
+pub struct MemoryReservations(Box<[(u64, u64)]>);
 
+/// Errors from parsing the memory reservation block
 #[derive(Debug)]
-pub struct MemoryReservations(pub Box<[(u64, u64)]>);
+#[non_exhaustive]
+pub enum Error {
+    /// The block ran past the start of the next block, or the end of the device tree, before the terminating entry was found
+    Unterminated,
+    /// A reserved region extends past the end of the 64-bit address space
+    Overflow((u64, u64)),
+    /// Two reserved regions overlap each other
+    Overlap((u64, u64), (u64, u64)),
+}
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Unterminated => f.write_str("memory reservation block is not terminated"),
+            Self::Overflow((address, size)) => write!(
+                f,
+                "reserved region of {size:#X} bytes at {address:#X} extends past the end of the address space"
+            ),
+            Self::Overlap((first_address, first_size), (second_address, second_size)) => write!(
+                f,
+                "reserved regions of {first_size:#X} bytes at {first_address:#X} and {second_size:#X} bytes at {second_address:#X} overlap"
+            ),
+        }
+    }
+}
+
+impl MemoryReservations {
+    /// Returns the reserved regions, as (address, size) pairs sorted by address
+    #[must_use]
+    #[inline]
+    pub fn entries(&self) -> &[(u64, u64)] {
+        &self.0
+    }
+
+    /// Returns whether or not the given address lies within some reserved region
+    #[must_use]
+    #[inline]
+    pub fn contains(&self, address: u64) -> bool {
+        self.0.iter().any(|&(start, size)| {
+            address
+                .checked_sub(start)
+                .is_some_and(|offset| offset < size)
+        })
+    }
+}
 
 impl TryFrom<&[u64]> for MemoryReservations {
-    type Error = ();
+    type Error = Error;
 
+    /// Parses the memory reservation block beginning at the start of the given slice.
+    /// The slice may extend past the terminating entry, but the terminating entry must lie within it.
+    /// A region may end exactly at the end of the address space
+    #[inline]
     fn try_from(value: &[u64]) -> Result<Self, Self::Error> {
-        if value.len() % 2 != 0 {
-            return Err(());
+        let mut entries = Vec::new();
+        let mut terminated = false;
+        for &[address, size] in value.array_chunks::<2>() {
+            let (address, size) = (u64::from_be(address), u64::from_be(size));
+            if address == 0 && size == 0 {
+                terminated = true;
+                break;
+            }
+            // Compare against the last byte of the region, so that regions ending exactly at the end of the address space are allowed
+            if size
+                .checked_sub(1)
+                .is_some_and(|last| address.checked_add(last).is_none())
+            {
+                return Err(Error::Overflow((address, size)));
+            }
+            entries.push((address, size));
         }
 
-        let mut entries: Vec<_> = value
-            .array_chunks::<2>()
-            .map(|&[address, size]| (u64::from_be(address), u64::from_be(size)))
-            .collect();
-
-        if entries.pop() != Some((0, 0)) {
-            return Err(());
+        if !terminated {
+            return Err(Error::Unterminated);
         }
         entries.sort_unstable();
 
+        // Sorted by address, so it is only necessary to check adjacent entries
+        if let Some(&[first, second]) = entries.windows(2).find(|pair| {
+            matches!(*pair, [(start, size), (next_start, _)] if next_start.checked_sub(*start).is_some_and(|gap| gap < *size))
+        }) {
+            return Err(Error::Overlap(first, second));
+        }
+
         Ok(Self(entries.into_boxed_slice()))
     }
 }
@@ -2,10 +2,15 @@
 //!
 //! The device tree provides information as caches both as a part of CPU nodes (for L1 caches) or as independent nodes (for higher caches)
 
-use alloc::rc::Rc;
+use crate::rc::{Rc, Weak};
+use alloc::string::String;
 
-use super::{device, ChildMap, Node, PropertyMap, RawNode, RawNodeError};
-use crate::{map::Map, node::PropertyKeys, parse::U32ByteSlice};
+use super::{
+    device, Ancestors, ChildMap, Locate, Node, Parent, ParentLink, PropertyMap, RawNode,
+    RawNodeError,
+};
+use crate::{map::Map, node::PropertyKeys, node_name::NameRef, parse::U32ByteSlice};
+use core::fmt::{self, Display};
 use core::{ffi::CStr, num::NonZeroU32};
 
 // TODO: Are these not actually required for a device tree to fully implement?
@@ -121,6 +126,8 @@ impl Description {
 /// A cache node may be represented under a CPU node or any other appropriate location in the devicetree.
 #[derive(Debug)]
 pub struct HigherLevel<'node> {
+    /// The unit name of this node
+    name: NameRef<'node>,
     /// The description of the cache itself
     cache: Description,
     /// Specifies the level in the cache hierarchy. For example, a level 2 cache has a value of 2.
@@ -143,16 +150,50 @@ pub enum HigherLevelError {
     Level,
     /// Error parsing the cells of this node, if present
     Cells,
-    /// Error parsing a child node
-    Child(device::Error),
+    /// Error parsing the child node at the given offset in the structure block
+    Child(usize, device::Error),
+}
+
+impl Display for HigherLevelError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::BadType => f.write_str("not `\"cache\"`"),
+            Self::PHandle | Self::Level => f.write_str("missing or not a single cell"),
+            Self::Cells => f.write_str("`#address-cells` or `#size-cells` is not a single cell"),
+            Self::Child(_, ref child) => Display::fmt(child, f),
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for HigherLevelError {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        match *self {
+            Self::Child(offset, ref child) => Some(child.node_offset().unwrap_or(offset)),
+            Self::BadType | Self::PHandle | Self::Level | Self::Cells => None,
+        }
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::BadType => Some(PropertyKeys::COMPATIBLE),
+            Self::PHandle => Some(PropertyKeys::PHANDLE),
+            Self::Level => Some(PropertyKeys::CACHE_LEVEL),
+            Self::Cells => None,
+            Self::Child(_, ref child) => child.property(),
+        }
+    }
 }
 
 impl<'node> HigherLevel<'node> {
     /// Creates a new higher-level cache from the given device tree node
     pub(super) fn new(
         mut value: RawNode<'node>,
+        name: NameRef<'node>,
         phandles: &mut Map<u32, Rc<device::Node<'node>>>,
-    ) -> Result<(u32, Self), HigherLevelError> {
+    ) -> Result<(u32, Rc<Self>), HigherLevelError> {
         if !value
             .properties
             .remove(&PropertyKeys::COMPATIBLE)
@@ -176,21 +217,54 @@ impl<'node> HigherLevel<'node> {
 
         let cache = cache_description!(&mut value.properties, b"");
 
-        let (properties, children) = value.into_components(phandles, None);
-        let children = match children {
-            Ok(children) => children,
-            Err(RawNodeError::Cells) => return Err(HigherLevelError::Cells),
-            Err(RawNodeError::Child(child)) => return Err(HigherLevelError::Child(child)),
-        };
-        Ok((
-            phandle,
+        let mut error = None;
+        let node = Rc::new_cyclic(|me| {
+            let (properties, children) =
+                value.into_components(phandles, &ParentLink::Cache(Weak::clone(me)));
             Self {
+                name,
                 cache,
                 level,
-                children,
+                children: children.unwrap_or_else(|err| {
+                    error = Some(err);
+                    Map::new()
+                }),
                 properties,
-            },
-        ))
+            }
+        });
+        match error {
+            None => Ok((phandle, node)),
+            Some(RawNodeError::Cells) => Err(HigherLevelError::Cells),
+            Some(RawNodeError::Child(offset, child)) => Err(HigherLevelError::Child(offset, child)),
+        }
+    }
+
+    /// Returns the unit name of this node
+    #[must_use]
+    #[inline]
+    pub const fn name(&self) -> NameRef<'node> {
+        self.name
+    }
+
+    /// Returns the parent of this node, which is always the `/cpus` node
+    #[must_use]
+    #[inline]
+    pub const fn parent(&self) -> Option<Parent<'node>> {
+        Some(Parent::Cpus)
+    }
+
+    /// Returns an iterator over the ancestors of this node, from its parent up to the root node
+    #[must_use]
+    #[inline]
+    pub const fn ancestors(&self) -> Ancestors<'node> {
+        Ancestors::new(self.parent())
+    }
+
+    /// Returns the full path of this node, e.g. `/cpus/l2-cache0`
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> String {
+        super::path(self.name, self.ancestors())
     }
 
     #[inline]
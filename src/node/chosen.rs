@@ -1,10 +1,11 @@
 use core::ffi::CStr;
+use core::fmt::{self, Display};
 
-use alloc::rc::Rc;
+use crate::rc::Rc;
 
 use crate::parse::U32ByteSlice;
 
-use super::{device, root, Node, PropertyKeys, PropertyMap, RawNode};
+use super::{device, root, Locate, Node, PropertyKeys, PropertyMap, RawNode};
 
 /// The `Chosen` node does not represent a real device in the system but describes parameters chosen or specified by the system firmware at run time.
 #[derive(Debug)]
@@ -29,17 +30,70 @@ pub struct Chosen<'data> {
     rpi_boardrev_ext: Option<u32>,
 }
 
+/// Errors from parsing the `/chosen` node
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error<'data> {
+    /// The `bootargs` property is not a string
     BootArg(U32ByteSlice<'data>),
+    /// The `stdout-path` property is not a string
     StdoutPathInvalid(U32ByteSlice<'data>),
+    /// The `stdout-path` property refers to a node that does not exist
     StdoutDanglingPath(&'data CStr),
+    /// The `stdin-path` property is not a string
     StdinPathInvalid(U32ByteSlice<'data>),
+    /// The `stdin-path` property refers to a node that does not exist
     StdinDanglingPath(&'data CStr),
+    /// The `overlay_prefix` property is not a string
     OverlayPrefix(U32ByteSlice<'data>),
+    /// The `os_prefix` property is not a string
     OsPrefix(U32ByteSlice<'data>),
+    /// The `rpi-boardrev-ext` property is not a single cell
     RpiBoardrevExt(U32ByteSlice<'data>),
+    /// The chosen node has children, which are not currently supported
+    Children,
+}
+
+impl Display for Error<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::BootArg(_)
+            | Self::StdoutPathInvalid(_)
+            | Self::StdinPathInvalid(_)
+            | Self::OverlayPrefix(_)
+            | Self::OsPrefix(_) => f.write_str("not a string"),
+            Self::StdoutDanglingPath(path) | Self::StdinDanglingPath(path) => {
+                write!(f, "`{}` does not exist", path.to_string_lossy())
+            }
+            Self::RpiBoardrevExt(_) => f.write_str("not a single cell"),
+            Self::Children => f.write_str("children of the chosen node are not supported"),
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for Error<'_> {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::BootArg(_) => Some(PropertyKeys::BOOTARGS),
+            Self::StdoutPathInvalid(_) | Self::StdoutDanglingPath(_) => {
+                Some(PropertyKeys::STDOUT_PATH)
+            }
+            Self::StdinPathInvalid(_) | Self::StdinDanglingPath(_) => {
+                Some(PropertyKeys::STDIN_PATH)
+            }
+            Self::OverlayPrefix(_) => Some(PropertyKeys::OVERLAY_PREFIX),
+            Self::OsPrefix(_) => Some(PropertyKeys::OS_PREFIX),
+            Self::RpiBoardrevExt(_) => Some(PropertyKeys::RPI_BOARDREV_EXT),
+            Self::Children => None,
+        }
+    }
 }
 
 impl<'data> Chosen<'data> {
@@ -48,25 +102,26 @@ impl<'data> Chosen<'data> {
         mut chosen: RawNode<'data>,
         root: &'root root::Node<'data>,
     ) -> Result<Chosen<'data>, Error<'data>> {
-        /// Extracts an `Rc` to the specified node from the given property
+        /// Extracts an `Rc` to the specified node from the given property, failing with the given errors if the path is invalid or dangling
         fn rc_from_node<'data>(
             properties: &mut PropertyMap<'data>,
             property_key: &CStr,
             root: &root::Node<'data>,
+            invalid: fn(U32ByteSlice<'data>) -> Error<'data>,
+            dangling: fn(&'data CStr) -> Error<'data>,
         ) -> Result<Option<Rc<device::Node<'data>>>, Error<'data>> {
             properties
                 .remove(property_key)
                 .map(|bytes| {
-                    let c_string =
-                        <&CStr>::try_from(bytes).map_err(|_err| Error::StdoutPathInvalid(bytes))?;
+                    let c_string = <&CStr>::try_from(bytes).map_err(|_err| invalid(bytes))?;
                     root.find_str(c_string.to_bytes())
-                        .ok_or(Error::StdoutDanglingPath(c_string))
+                        .ok_or_else(|| dangling(c_string))
                 })
                 .transpose()
         }
 
         if !chosen.children.is_empty() {
-            unimplemented!("Children of the chosen node are currently not supported");
+            return Err(Error::Children);
         }
 
         let boot_args = chosen
@@ -74,10 +129,22 @@ impl<'data> Chosen<'data> {
             .remove(PropertyKeys::BOOTARGS)
             .map(|bytes| <&CStr>::try_from(bytes).map_err(|_err| Error::BootArg(bytes)))
             .transpose()?;
-        let stdout = rc_from_node(&mut chosen.properties, PropertyKeys::STDOUT_PATH, root)?;
+        let stdout = rc_from_node(
+            &mut chosen.properties,
+            PropertyKeys::STDOUT_PATH,
+            root,
+            Error::StdoutPathInvalid,
+            Error::StdoutDanglingPath,
+        )?;
         // If the stdin-path property is not specified, stdout-path should be assumed to define the input device.
-        let stdin = rc_from_node(&mut chosen.properties, PropertyKeys::STDIN_PATH, root)?
-            .or_else(|| stdout.as_ref().map(Rc::clone));
+        let stdin = rc_from_node(
+            &mut chosen.properties,
+            PropertyKeys::STDIN_PATH,
+            root,
+            Error::StdinPathInvalid,
+            Error::StdinDanglingPath,
+        )?
+        .or_else(|| stdout.as_ref().map(Rc::clone));
 
         #[cfg(feature = "rpi")]
         let overlay_prefix = chosen
@@ -1,19 +1,22 @@
 //! A CPU node. CPU nodes describe the physical cores that are present.
 
+use crate::rc::Rc;
 use crate::{
     map::Map,
     parse::U32ByteSlice,
     property::{EnableMethod, EnableMethodError},
 };
-use alloc::rc::Rc;
+use alloc::string::String;
+use core::fmt::{self, Display};
 use core::{ffi::CStr, num::NonZeroU8};
 
 use super::{
     cache::{HigherLevel, HigherLevelError, L1},
     device,
     root::NodeNames,
-    PropertyKeys, RawNode,
+    Ancestors, Locate, Parent, PropertyKeys, RawNode, Warnings,
 };
+use crate::node_name::NameRef;
 
 /// Status of a CPU as indicated by the node
 #[derive(Debug)]
@@ -42,6 +45,8 @@ pub enum Status {
 /// A node representing a physical CPU
 #[derive(Debug)]
 pub struct Node<'node> {
+    /// The unit name of this node
+    name: NameRef<'node>,
     /// The mechanism for enabling a CPU. Required if `status` is `Fail`
     enable_method: Option<EnableMethod<'node>>,
     /// A unique identifier for this CPU
@@ -70,6 +75,8 @@ pub enum NodeError {
     Status,
     /// Error parsing the `reg` field of the node
     Reg,
+    /// The CPU ID in the `reg` field does not fit into 32 bits, which is not currently supported
+    RegWidth(u64),
     /// Next-level cache is a dangling phandle
     NextLevelCache,
 }
@@ -78,16 +85,89 @@ pub enum NodeError {
 #[non_exhaustive]
 #[derive(Debug)]
 pub enum RootError {
-    /// Error parsing a child CPU
-    Cpu(NodeError),
-    /// Error parsing a child cache
-    Cache(HigherLevelError),
+    /// Error parsing the child CPU at the given offset in the structure block
+    Cpu(usize, NodeError),
+    /// Error parsing the child cache at the given offset in the structure block
+    Cache(usize, HigherLevelError),
     /// Missing a field for address/size cells
     Reg,
     /// Mismatch between a child CPU's specified reg and its unit-address
     RegMismatch(Option<u64>, u32),
 }
 
+impl Display for NodeError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::DeviceType => f.write_str("missing or not `\"cpu\"`"),
+            Self::EnableMethod => f.write_str("missing or not a known method"),
+            Self::ReleaseAddr => f.write_str("missing or not a single address for a spin table"),
+            Self::Status => f.write_str("not a known status"),
+            Self::Reg => f.write_str("missing or not a single CPU ID of `#address-cells`"),
+            Self::RegWidth(reg) => write!(f, "CPU ID {reg:#X} does not fit into 32 bits"),
+            Self::NextLevelCache => f.write_str("not the phandle of a cache"),
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for NodeError {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::DeviceType => Some(PropertyKeys::DEVICE_TYPE),
+            Self::EnableMethod => Some(PropertyKeys::ENABLE_METHOD),
+            Self::ReleaseAddr => Some(PropertyKeys::CPU_RELEASE_ADDR),
+            Self::Status => Some(PropertyKeys::STATUS),
+            Self::Reg | Self::RegWidth(_) => Some(PropertyKeys::REG),
+            Self::NextLevelCache => Some(PropertyKeys::NEXT_LEVEL_CACHE),
+        }
+    }
+}
+
+impl Display for RootError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Cpu(_, ref cpu) => Display::fmt(cpu, f),
+            Self::Cache(_, ref cache) => Display::fmt(cache, f),
+            Self::Reg => f.write_str(
+                "`#address-cells` must be a nonzero single cell, and `#size-cells` must be 0",
+            ),
+            Self::RegMismatch(Some(address), reg) => {
+                write!(f, "a CPU with unit address {address:#X} has ID {reg:#X}")
+            }
+            Self::RegMismatch(None, reg) => {
+                write!(f, "a CPU without a unit address has ID {reg:#X}")
+            }
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for RootError {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        match *self {
+            Self::Cpu(offset, ref cpu) => Some(cpu.node_offset().unwrap_or(offset)),
+            Self::Cache(offset, ref cache) => Some(cache.node_offset().unwrap_or(offset)),
+            Self::Reg | Self::RegMismatch(..) => None,
+        }
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::Cpu(_, ref cpu) => cpu.property(),
+            Self::Cache(_, ref cache) => cache.property(),
+            Self::Reg | Self::RegMismatch(..) => None,
+        }
+    }
+}
+
 /// A map of CPU IDs to CPU nodes
 type CpuMap<'node> = Map<u32, Rc<Node<'node>>>;
 /// A map of cache IDs to cache Nodes
@@ -97,9 +177,11 @@ impl<'node> Node<'node> {
     /// Parses and creates a CPU node from the provided informaiton
     fn new<'parsing>(
         mut value: RawNode<'node>,
+        name: NameRef<'node>,
         base: &'parsing Map<&'node CStr, U32ByteSlice<'node>>,
         cache_handles: &'parsing Map<u32, Rc<HigherLevel<'node>>>,
         address_cells: NonZeroU8,
+        warnings: &mut Warnings<'_, NodeError>,
     ) -> Result<Self, NodeError> {
         value.properties.extend_preserve(base);
 
@@ -110,7 +192,7 @@ impl<'node> Node<'node> {
                 <&CStr>::try_from(device_type).is_ok_and(|x| x.to_bytes() == b"cpu")
             })
         {
-            return Err(NodeError::DeviceType);
+            warnings.tolerate(NodeError::DeviceType)?;
         }
 
         let enable_method = match EnableMethod::extract_from_properties(&mut value.properties) {
@@ -155,15 +237,12 @@ impl<'node> Node<'node> {
         let reg = value
             .properties
             .remove(PropertyKeys::REG)
-            .and_then(|bytes| {
-                if address_cells.get() != 1 {
-                    unimplemented!("Only 32-bit CPU IDs are currently supported");
-                }
-                u32::try_from(bytes).ok()
-            })
+            .and_then(|bytes| bytes.into_cells(address_cells.get()))
             .ok_or(NodeError::Reg)?;
+        let reg = u32::try_from(reg).map_err(|_err| NodeError::RegWidth(reg))?;
 
         Ok(Self {
+            name,
             reg,
             enable_method,
             l1_cache: cache,
@@ -177,6 +256,7 @@ impl<'node> Node<'node> {
     pub(super) fn parse_parent(
         mut parent: RawNode<'node>,
         phandles: &mut Map<u32, Rc<device::Node<'node>>>,
+        warnings: &mut Warnings<'_, RootError>,
     ) -> Result<(CpuMap<'node>, CacheMap<'node>), RootError> {
         let (Ok(cpu_addr_cells), Ok(0)) = parent.extract_cell_counts() else {
             return Err(RootError::Reg);
@@ -186,10 +266,9 @@ impl<'node> Node<'node> {
         let caches = parent
             .children
             .extract_if(|name, _| !name.node_name().starts_with(NodeNames::cpu_prefix()))
-            .map(|(_, node)| {
-                HigherLevel::new(node, phandles)
-                    .map(|(phandle, cache)| (phandle, Rc::new(cache)))
-                    .map_err(RootError::Cache)
+            .map(|(name, node)| {
+                let offset = node.offset;
+                HigherLevel::new(node, name, phandles).map_err(|err| RootError::Cache(offset, err))
             })
             .try_collect()?;
 
@@ -197,9 +276,23 @@ impl<'node> Node<'node> {
             .children
             .into_iter()
             .map(|(name, node)| {
+                let offset = node.offset;
                 let node = Rc::new(
-                    Self::new(node, &parent.properties, &caches, cpu_addr_cells)
-                        .map_err(RootError::Cpu)?,
+                    warnings
+                        .nest(
+                            |err| RootError::Cpu(offset, err),
+                            |warnings| {
+                                Self::new(
+                                    node,
+                                    name,
+                                    &parent.properties,
+                                    &caches,
+                                    cpu_addr_cells,
+                                    warnings,
+                                )
+                            },
+                        )
+                        .map_err(|err| RootError::Cpu(offset, err))?,
                 );
 
                 if name
@@ -214,6 +307,34 @@ impl<'node> Node<'node> {
             .map(|cpus| (cpus, caches))
     }
 
+    /// Returns the unit name of this node
+    #[must_use]
+    #[inline]
+    pub const fn name(&self) -> NameRef<'node> {
+        self.name
+    }
+
+    /// Returns the parent of this node, which is always the `/cpus` node
+    #[must_use]
+    #[inline]
+    pub const fn parent(&self) -> Option<Parent<'node>> {
+        Some(Parent::Cpus)
+    }
+
+    /// Returns an iterator over the ancestors of this node, from its parent up to the root node
+    #[must_use]
+    #[inline]
+    pub const fn ancestors(&self) -> Ancestors<'node> {
+        Ancestors::new(self.parent())
+    }
+
+    /// Returns the full path of this node, e.g. `/cpus/cpu@0`
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> String {
+        super::path(self.name, self.ancestors())
+    }
+
     #[must_use]
     #[inline]
     pub const fn enable_method(&self) -> Option<&EnableMethod<'_>> {
@@ -1,22 +1,28 @@
 use core::ffi::CStr;
+use core::fmt::{self, Display};
 
-use alloc::{
-    boxed::Box,
-    rc::{Rc, Weak},
-};
+use alloc::boxed::Box;
 
 use crate::{
     map::Map,
-    property::{Model, Range, Status},
+    property::{Address, Model, Range, Status},
+    rc::{Rc, Weak},
 };
 
 use super::{
-    interrupt::PartialInterruptDevice, ChildMap, PropertyKeys, PropertyMap, RawNode, RawNodeError,
+    interrupt::{self, Interrupt, PartialInterruptDevice},
+    root, Ancestors, ChildMap, Locate, Parent, ParentLink, PropertyKeys, PropertyMap, RawNode,
+    RawNodeError,
 };
+use crate::node_name::NameRef;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// A Device Tree Node
 #[derive(Debug)]
 pub struct Node<'data> {
+    /// The unit name of this node
+    name: NameRef<'data>,
     /// Children of this node
     children: ChildMap<'data>,
     /// The compatible property value consists of one or more strings that define the specific programming model for the device.
@@ -42,15 +48,19 @@ pub struct Node<'data> {
     /// The `reg` property describes the address of the device’s resources within the address space defined by its parent bus.
     /// Most commonly this means the offsets and lengths of memory-mapped IO register blocks, but may have a different meaning on some bus types.
     /// Addresses in the address space defined by the root node are CPU real addresses.
-    reg: Option<Box<[[u64; 2]]>>,
+    reg: Option<Box<[(Address, u64)]>>,
     /// The `ranges`` property provides a means of defining a mapping or translation between the address space of the bus (the child address space) and the address space of the bus node’s parent (the parent address space).
     ranges: Option<Box<[Range]>>,
+    /// The `dma-ranges` property describes the mapping of memory addresses as seen by DMA masters on the bus (the child address space) into the address space of the bus node's parent, in the same format as `ranges`
+    dma_ranges: Option<Box<[Range]>>,
     /// The status property indicates the operational status of a device.
     status: Status<'data>,
     /// Miscellaneous extra properties regarding this node
     pub(super) properties: PropertyMap<'data>,
     /// Interrupt information about the device
     interrupts: Rc<PartialInterruptDevice<'data>>,
+    /// The parent of this node
+    parent: ParentLink<'data>,
 }
 
 #[derive(Debug)]
@@ -60,23 +70,95 @@ pub enum Error {
     Compatible,
     Model,
     Ranges,
+    /// The `dma-ranges` property is not a whole number of entries
+    DmaRanges,
     Status,
     Cells,
     BadPHandle,
     DuplicatePHandle,
-    Child(Box<Error>),
+    /// The `#interrupt-cells` property is not a single, reasonably sized cell
+    InterruptCells,
+    /// The `interrupt-parent` property is not a single phandle
+    InterruptParent,
+    /// Error parsing the child node at the given offset in the structure block
+    Child(usize, Box<Error>),
+}
+
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Reg => f.write_str("not a whole number of entries of the parent's `#address-cells` and `#size-cells`"),
+            Self::Compatible => f.write_str("not a list of strings"),
+            Self::Model => f.write_str("not a string"),
+            Self::Ranges | Self::DmaRanges => f.write_str("not a whole number of entries of `#address-cells`, the parent's `#address-cells`, and `#size-cells`"),
+            Self::Status => f.write_str("not a known status"),
+            Self::Cells => f.write_str("`#address-cells` or `#size-cells` is not a single cell"),
+            Self::BadPHandle => f.write_str("not a single cell"),
+            Self::DuplicatePHandle => f.write_str("already used by another node"),
+            Self::InterruptCells => f.write_str("not a single, reasonably sized cell"),
+            Self::InterruptParent => f.write_str("not a single phandle"),
+            Self::Child(_, ref child) => Display::fmt(child, f),
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for Error {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        match *self {
+            Self::Child(offset, ref child) => Some(child.node_offset().unwrap_or(offset)),
+            Self::Reg
+            | Self::Compatible
+            | Self::Model
+            | Self::Ranges
+            | Self::DmaRanges
+            | Self::Status
+            | Self::Cells
+            | Self::BadPHandle
+            | Self::DuplicatePHandle
+            | Self::InterruptCells
+            | Self::InterruptParent => None,
+        }
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::Reg => Some(PropertyKeys::REG),
+            Self::Compatible => Some(PropertyKeys::COMPATIBLE),
+            Self::Model => Some(PropertyKeys::MODEL),
+            Self::Ranges => Some(PropertyKeys::RANGES),
+            Self::DmaRanges => Some(PropertyKeys::DMA_RANGES),
+            Self::Status => Some(PropertyKeys::STATUS),
+            Self::BadPHandle | Self::DuplicatePHandle => Some(PropertyKeys::PHANDLE),
+            Self::InterruptCells => Some(PropertyKeys::INTERRUPT_CELLS),
+            Self::InterruptParent => Some(PropertyKeys::INTERRUPT_PARENT),
+            Self::Cells => None,
+            Self::Child(_, ref child) => child.property(),
+        }
+    }
 }
 
 impl<'node> Node<'node> {
     /// Constructs a new `DeviceNode` from a given `RawNode` and additional properties
+    #[expect(clippy::too_many_lines, reason = "Each property is parsed in sequence")]
     pub(super) fn new<'phandles>(
         mut value: RawNode<'node>,
+        name: NameRef<'node>,
         address_cells: Option<u8>,
         size_cells: Option<u8>,
         phandles: &'phandles mut Map<u32, Rc<Node<'node>>>,
-        parent: Option<&Weak<Node<'node>>>,
+        parent: &ParentLink<'node>,
     ) -> Result<Rc<Node<'node>>, Error> {
+        let has_address_cells = value.properties.get(&PropertyKeys::ADDRESS_CELLS).is_some();
+        let unit_address = value.properties.get(&PropertyKeys::REG).copied();
         let (child_address_cells, child_size_cells) = value.extract_cell_counts();
+        let interrupt_address_cells = child_address_cells
+            .as_ref()
+            .ok()
+            .copied()
+            .filter(|_| has_address_cells);
 
         let reg = value
             .properties
@@ -84,7 +166,9 @@ impl<'node> Node<'node> {
             .map(|bytes| {
                 address_cells
                     .zip(size_cells)
-                    .and_then(|cells| bytes.into_cells_slice(&cells.into()))
+                    .and_then(|(address_cells, size_cells)| {
+                        bytes.into_regs(address_cells, size_cells)
+                    })
                     .ok_or(Error::Reg)
             })
             .transpose()?;
@@ -103,27 +187,22 @@ impl<'node> Node<'node> {
             })
             .transpose()?;
 
+        let parse_ranges = |bytes| {
+            let ((child_address_cells, address_cells), child_size_cells) = child_address_cells
+                .ok()
+                .zip(address_cells)
+                .zip(child_size_cells.ok())?;
+            Range::parse_list(bytes, child_address_cells, address_cells, child_size_cells)
+        };
         let ranges = value
             .properties
             .remove(&PropertyKeys::RANGES)
-            .map(|bytes| {
-                child_address_cells
-                    .ok()
-                    .zip(address_cells)
-                    .zip(child_size_cells.ok())
-                    .and_then(|((child_address_cells, address_cells), child_size_cells)| {
-                        bytes
-                            .into_cells_slice(&[
-                                child_address_cells,
-                                address_cells,
-                                child_size_cells,
-                            ])
-                            .map(|entries| {
-                                entries.iter().map(|&range| Range::from(range)).collect()
-                            })
-                    })
-                    .ok_or(Error::Ranges)
-            })
+            .map(|bytes| parse_ranges(bytes).ok_or(Error::Ranges))
+            .transpose()?;
+        let dma_ranges = value
+            .properties
+            .remove(&PropertyKeys::DMA_RANGES)
+            .map(|bytes| parse_ranges(bytes).ok_or(Error::DmaRanges))
             .transpose()?;
         let status = value
             .properties
@@ -139,34 +218,50 @@ impl<'node> Node<'node> {
             .transpose()
             .map_err(|_err| Error::BadPHandle)?;
 
+        // The node must be constructed even if its children or interrupts are invalid, so the first such error is recorded to be returned afterwards
+        let mut error = None;
         let node = Rc::new_cyclic(|device| {
             let (mut properties, children) = value.into_components_from_cells(
                 child_address_cells.ok(),
                 child_size_cells.ok(),
                 phandles,
-                Some(device),
+                &ParentLink::Device(Weak::clone(device)),
+            );
+            let children = children.unwrap_or_else(|err| {
+                error = Some(match err {
+                    RawNodeError::Cells => Error::Cells,
+                    RawNodeError::Child(offset, child) => Error::Child(offset, Box::new(child)),
+                });
+                Map::new()
+            });
+            let interrupts = PartialInterruptDevice::extract_from_properties(
+                &mut properties,
+                Weak::clone(device),
+                parent.device(),
+                interrupt_address_cells,
+                unit_address,
             );
 
             Self {
-                children: children
-                    .map_err(|err| match err {
-                        RawNodeError::Cells => Error::Cells,
-                        RawNodeError::Child(child) => Error::Child(Box::new(child)),
-                    })
-                    .unwrap(),
+                name,
+                children,
                 compatible,
                 model,
                 reg,
                 ranges,
+                dma_ranges,
                 status,
-                interrupts: Rc::new(PartialInterruptDevice::extract_from_properties(
-                    &mut properties,
-                    Weak::clone(device),
-                    parent,
-                )),
+                interrupts: Rc::new(interrupts.unwrap_or_else(|err| {
+                    error.get_or_insert(err);
+                    PartialInterruptDevice::default()
+                })),
                 properties,
+                parent: parent.clone(),
             }
         });
+        if let Some(err) = error {
+            return Err(err);
+        }
 
         if let Some(phandle) = phandle {
             if phandles.insert(phandle, Rc::clone(&node)).is_some() {
@@ -176,6 +271,34 @@ impl<'node> Node<'node> {
         Ok(node)
     }
 
+    /// Returns the unit name of this node
+    #[must_use]
+    #[inline]
+    pub const fn name(&self) -> NameRef<'node> {
+        self.name
+    }
+
+    /// Returns the parent of this node, or `None` if the tree containing this node no longer exists
+    #[must_use]
+    #[inline]
+    pub fn parent(&self) -> Option<Parent<'node>> {
+        self.parent.upgrade()
+    }
+
+    /// Returns an iterator over the ancestors of this node, from its parent up to the root node
+    #[must_use]
+    #[inline]
+    pub fn ancestors(&self) -> Ancestors<'node> {
+        Ancestors::new(self.parent())
+    }
+
+    /// Returns the full path of this node, e.g. `/soc/serial@7e201000`
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> String {
+        super::path(self.name, self.ancestors())
+    }
+
     #[must_use]
     #[inline]
     pub fn compatible(&self) -> Option<&[Model<'_>]> {
@@ -190,7 +313,7 @@ impl<'node> Node<'node> {
 
     #[must_use]
     #[inline]
-    pub fn reg(&self) -> Option<&[[u64; 2]]> {
+    pub fn reg(&self) -> Option<&[(Address, u64)]> {
         self.reg.as_deref()
     }
 
@@ -200,17 +323,152 @@ impl<'node> Node<'node> {
         self.ranges.as_deref()
     }
 
+    /// Returns the `dma-ranges` of this node, which map the addresses used by DMA masters on this bus into the address space of this node's parent
+    #[must_use]
+    #[inline]
+    pub fn dma_ranges(&self) -> Option<&[Range]> {
+        self.dma_ranges.as_deref()
+    }
+
     #[must_use]
     #[inline]
     pub const fn status(&self) -> &Status<'node> {
         &self.status
     }
 
+    /// Returns whether or not any string of this node's `compatible` property is the given string, such as `b"arm,gic-400"`
     #[must_use]
     #[inline]
-    pub fn interrupts(&self) -> &PartialInterruptDevice<'_> {
+    pub fn is_compatible(&self, compatible: &[u8]) -> bool {
+        self.compatible
+            .iter()
+            .flatten()
+            .any(|model| model.matches(compatible))
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn interrupts(&self) -> &PartialInterruptDevice<'node> {
         &self.interrupts
     }
+
+    /// Resolves each interrupt of this node to the interrupt controller that receives it, along with the specifier in the controller's format.
+    ///
+    /// Follows `interrupt-parent`s, or devicetree parents where absent, and translates specifiers through the `interrupt-map` of any interrupt nexus on the way
+    ///
+    /// # Errors
+    /// Returns an error if some interrupt cannot be resolved to a controller
+    #[inline]
+    pub fn resolve_interrupts(
+        &self,
+        root: &root::Node<'node>,
+    ) -> Result<Box<[Interrupt<'node>]>, interrupt::Error> {
+        self.interrupts.resolve(root)
+    }
+
+    /// Resolves the interrupt of this node named by `interrupt-names`, such as `b"tx"`, to the interrupt controller that receives it.
+    /// Returns `None` if this node has no interrupt with the given name
+    ///
+    /// # Errors
+    /// Returns an error if the named interrupt cannot be resolved to a controller
+    #[inline]
+    pub fn interrupt_by_name(
+        &self,
+        root: &root::Node<'node>,
+        name: &[u8],
+    ) -> Result<Option<Interrupt<'node>>, interrupt::Error> {
+        self.interrupts.resolve_by_name(root, name)
+    }
+
+    /// Translates the region of the given size at the given address, in the address space of this node's parent bus, into a CPU physical address.
+    ///
+    /// Each ancestor bus maps the region into its own parent's address space through its `ranges`: an empty `ranges` is an identity mapping,
+    /// while a missing `ranges` means that the bus' addresses are not translatable.
+    /// Returns `None` if some ancestor cannot translate the region, or if the region does not lie entirely within one of an ancestor's ranges
+    #[must_use]
+    #[inline]
+    pub fn translate_address(&self, mut address: Address, size: u64) -> Option<Address> {
+        let mut bus = self.parent()?;
+        loop {
+            bus = match bus {
+                Parent::Root => return Some(address),
+                // The `/reserved-memory` node is required to have an empty `ranges`
+                Parent::ReservedMemory => Parent::Root,
+                Parent::Device(node) => {
+                    let ranges = node.ranges()?;
+                    if !ranges.is_empty() {
+                        address = ranges
+                            .iter()
+                            .find_map(|range| range.translate(&address, size))?;
+                    }
+                    node.parent()?
+                }
+                Parent::Cpus | Parent::Cache(_) | Parent::ReservedRegion(_) => return None,
+            };
+        }
+    }
+
+    /// Returns the buses that this node's DMA accesses pass through on their way to the root node, nearest first.
+    /// Returns `None` if this node is not beneath the root node through device nodes alone
+    fn dma_buses(&self) -> Option<Vec<Rc<Self>>> {
+        let mut buses = Vec::new();
+        for ancestor in self.ancestors() {
+            match ancestor {
+                Parent::Device(node) => buses.push(node),
+                // The `/reserved-memory` node is required to have an empty `ranges`
+                Parent::Root | Parent::ReservedMemory => return Some(buses),
+                Parent::Cpus | Parent::Cache(_) | Parent::ReservedRegion(_) => return None,
+            }
+        }
+        None
+    }
+
+    /// Translates the region of the given size at the given address, as seen by this node when acting as a DMA master, into a CPU physical address.
+    ///
+    /// Each ancestor bus maps the region into its own parent's address space through its `dma-ranges`:
+    /// a missing or empty `dma-ranges` is an identity mapping.
+    /// Returns `None` if this node is not beneath the root node, or if the region does not lie entirely within one of an ancestor's `dma-ranges`
+    #[must_use]
+    #[inline]
+    pub fn dma_to_cpu(&self, address: Address, size: u64) -> Option<Address> {
+        self.dma_buses()?
+            .iter()
+            .try_fold(address, |address, bus| match bus.dma_ranges() {
+                Some(ranges) if !ranges.is_empty() => ranges
+                    .iter()
+                    .find_map(|range| range.translate(&address, size)),
+                Some(_) | None => Some(address),
+            })
+    }
+
+    /// Translates the region of the given size at the given CPU physical address into the address that this node must use to reach it when acting as a DMA master.
+    ///
+    /// This is the inverse of `dma_to_cpu`, walking the `dma-ranges` of each ancestor bus from the root node down to this node's parent.
+    /// Returns `None` if this node is not beneath the root node, or if the region is not visible to this node in its entirety
+    #[must_use]
+    #[inline]
+    pub fn cpu_to_dma(&self, address: Address, size: u64) -> Option<Address> {
+        self.dma_buses()?
+            .iter()
+            .rev()
+            .try_fold(address, |address, bus| match bus.dma_ranges() {
+                Some(ranges) if !ranges.is_empty() => ranges
+                    .iter()
+                    .find_map(|range| range.translate_from_parent(&address, size)),
+                Some(_) | None => Some(address),
+            })
+    }
+
+    /// Returns the regions of this node's `reg`, translated into CPU physical addresses through the `ranges` of each ancestor.
+    /// Returns `None` if this node has no `reg`, or if any region is not translatable
+    #[must_use]
+    #[inline]
+    pub fn translate_reg(&self) -> Option<Box<[(Address, u64)]>> {
+        self.reg()?
+            .iter()
+            .map(|&(address, size)| Some((self.translate_address(address, size)?, size)))
+            .collect()
+    }
 }
 
 impl<'node> super::Node<'node> for Node<'node> {
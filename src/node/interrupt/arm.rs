@@ -0,0 +1,294 @@
+//! Typed decoding of the interrupt specifiers of ARM Generic Interrupt Controllers, and of the interrupts of ARM generic timers
+//!
+//! A GIC specifier consists of the interrupt type, the interrupt number relative to the type, and a flags cell
+//! holding the trigger type and, for PPIs on a GIC version 2, the mask of CPUs that the interrupt is wired to
+
+use super::{Error as ResolveError, Interrupt};
+use crate::node::{device, root};
+
+/// The `compatible` strings of the Generic Interrupt Controllers whose specifiers can be decoded
+const GIC_COMPATIBLE: [&[u8]; 8] = [
+    b"arm,gic-400",
+    b"arm,gic-v3",
+    b"arm,cortex-a15-gic",
+    b"arm,cortex-a9-gic",
+    b"arm,cortex-a7-gic",
+    b"arm,arm11mp-gic",
+    b"arm,arm1176jzf-devchip-gic",
+    b"arm,cortex-a5-gic",
+];
+
+/// The `compatible` strings of the ARM generic timers whose interrupts can be decoded
+const TIMER_COMPATIBLE: [&[u8]; 2] = [b"arm,armv7-timer", b"arm,armv8-timer"];
+
+/// Errors from decoding a GIC specifier or the interrupts of a timer
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The interrupt controller is not a known Generic Interrupt Controller
+    Controller,
+    /// The specifier has too few cells
+    Cells,
+    /// The interrupt type is not a known type
+    Kind(u32),
+    /// The interrupt number is out of range for its type
+    Number(u32),
+    /// The trigger type is not a single known trigger
+    Trigger(u32),
+    /// The node is not a known ARM generic timer
+    Timer,
+    /// The timer does not have the required interrupts, each of which must be a PPI
+    TimerInterrupts,
+    /// The interrupts of the timer could not be resolved to a controller
+    Resolve(ResolveError),
+}
+
+/// The type of a GIC interrupt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Kind {
+    /// Shared Peripheral Interrupt, which may be routed to any CPU
+    Spi,
+    /// Private Peripheral Interrupt, which is specific to a single CPU
+    Ppi,
+    /// Extended Shared Peripheral Interrupt, on a GIC version 3.1 or later
+    ExtendedSpi,
+    /// Extended Private Peripheral Interrupt, on a GIC version 3.1 or later
+    ExtendedPpi,
+}
+
+impl Kind {
+    /// Returns the first interrupt ID of this type, and the number of interrupts of this type
+    const fn range(self) -> (u32, u32) {
+        match self {
+            Self::Spi => (32, 988),
+            Self::Ppi => (16, 16),
+            Self::ExtendedSpi => (4096, 1024),
+            Self::ExtendedPpi => (1056, 64),
+        }
+    }
+}
+
+impl TryFrom<u32> for Kind {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Spi),
+            1 => Ok(Self::Ppi),
+            2 => Ok(Self::ExtendedSpi),
+            3 => Ok(Self::ExtendedPpi),
+            _ => Err(Error::Kind(value)),
+        }
+    }
+}
+
+/// The trigger type of an interrupt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Trigger {
+    /// Triggered on a low-to-high transition
+    EdgeRising,
+    /// Triggered on a high-to-low transition
+    EdgeFalling,
+    /// Asserted while the line is high
+    LevelHigh,
+    /// Asserted while the line is low
+    LevelLow,
+}
+
+impl Trigger {
+    /// Decodes the trigger type from the low bits of a flags cell, where zero means that the trigger type is unspecified
+    const fn from_flags(flags: u32) -> Result<Option<Self>, Error> {
+        match flags & 0xF {
+            0 => Ok(None),
+            1 => Ok(Some(Self::EdgeRising)),
+            2 => Ok(Some(Self::EdgeFalling)),
+            4 => Ok(Some(Self::LevelHigh)),
+            8 => Ok(Some(Self::LevelLow)),
+            trigger => Err(Error::Trigger(trigger)),
+        }
+    }
+}
+
+/// A decoded Generic Interrupt Controller interrupt specifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GicSpecifier {
+    /// The type of the interrupt
+    kind: Kind,
+    /// The interrupt number, relative to the first interrupt of its type
+    number: u32,
+    /// The trigger type of the interrupt, if specified
+    trigger: Option<Trigger>,
+    /// The mask of CPUs that a PPI is wired to, on a GIC version 2
+    cpu_mask: u8,
+    /// The phandle of the partition of CPUs that a PPI is wired to, on a GIC version 3 with 4 interrupt cells
+    partition: Option<u32>,
+}
+
+impl GicSpecifier {
+    /// Returns the type of the interrupt
+    #[must_use]
+    #[inline]
+    pub const fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Returns the interrupt number, relative to the first interrupt of its type
+    #[must_use]
+    #[inline]
+    pub const fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Returns the interrupt ID (INTID) that the GIC uses for this interrupt
+    #[must_use]
+    #[inline]
+    pub const fn intid(&self) -> u32 {
+        // The number has already been checked against the range of its type
+        self.kind.range().0.wrapping_add(self.number)
+    }
+
+    /// Returns the trigger type of the interrupt, if specified
+    #[must_use]
+    #[inline]
+    pub const fn trigger(&self) -> Option<Trigger> {
+        self.trigger
+    }
+
+    /// Returns the mask of CPUs that a PPI is wired to, on a GIC version 2. Zero if unspecified
+    #[must_use]
+    #[inline]
+    pub const fn cpu_mask(&self) -> u8 {
+        self.cpu_mask
+    }
+
+    /// Returns the phandle of the partition of CPUs that a PPI is wired to, on a GIC version 3 with 4 interrupt cells
+    #[must_use]
+    #[inline]
+    pub const fn partition(&self) -> Option<u32> {
+        self.partition
+    }
+}
+
+impl TryFrom<&[u32]> for GicSpecifier {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &[u32]) -> Result<Self, Self::Error> {
+        let (&[kind, number, flags], rest) = value.split_first_chunk().ok_or(Error::Cells)?;
+        let kind = Kind::try_from(kind)?;
+        if number >= kind.range().1 {
+            return Err(Error::Number(number));
+        }
+        let [_, _, cpu_mask, _] = flags.to_be_bytes();
+        Ok(Self {
+            kind,
+            number,
+            trigger: Trigger::from_flags(flags)?,
+            cpu_mask,
+            partition: rest.first().copied().filter(|&phandle| phandle != 0),
+        })
+    }
+}
+
+impl TryFrom<&Interrupt<'_>> for GicSpecifier {
+    type Error = Error;
+
+    /// Decodes the specifier of an interrupt, if its controller is a Generic Interrupt Controller
+    #[inline]
+    fn try_from(value: &Interrupt<'_>) -> Result<Self, Self::Error> {
+        if !GIC_COMPATIBLE
+            .iter()
+            .any(|compatible| value.controller().is_compatible(compatible))
+        {
+            return Err(Error::Controller);
+        }
+        Self::try_from(value.specifier())
+    }
+}
+
+/// The interrupts of an ARM generic timer, each of which is a PPI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timer {
+    /// The secure EL1 physical timer interrupt
+    secure_physical: GicSpecifier,
+    /// The non-secure EL1 physical timer interrupt
+    non_secure_physical: GicSpecifier,
+    /// The virtual timer interrupt
+    virt: GicSpecifier,
+    /// The EL2 physical (hypervisor) timer interrupt
+    hypervisor: GicSpecifier,
+    /// The EL2 virtual timer interrupt, if present
+    hypervisor_virtual: Option<GicSpecifier>,
+}
+
+impl Timer {
+    /// Decodes the interrupts of the given ARM generic timer node
+    ///
+    /// # Errors
+    /// Returns an error if the node is not an ARM generic timer,
+    /// or if it does not have the four PPIs required of one
+    #[inline]
+    pub fn new<'node>(node: &device::Node<'node>, root: &root::Node<'node>) -> Result<Self, Error> {
+        if !TIMER_COMPATIBLE
+            .iter()
+            .any(|compatible| node.is_compatible(compatible))
+        {
+            return Err(Error::Timer);
+        }
+        let interrupts = node.resolve_interrupts(root).map_err(Error::Resolve)?;
+        let mut interrupts = interrupts.iter().map(|interrupt| {
+            let specifier = GicSpecifier::try_from(interrupt)?;
+            if specifier.kind == Kind::Ppi {
+                Ok(specifier)
+            } else {
+                Err(Error::TimerInterrupts)
+            }
+        });
+        let mut next = || interrupts.next().ok_or(Error::TimerInterrupts)?;
+        Ok(Self {
+            secure_physical: next()?,
+            non_secure_physical: next()?,
+            virt: next()?,
+            hypervisor: next()?,
+            hypervisor_virtual: interrupts.next().transpose()?,
+        })
+    }
+
+    /// Returns the secure EL1 physical timer interrupt
+    #[must_use]
+    #[inline]
+    pub const fn secure_physical(&self) -> GicSpecifier {
+        self.secure_physical
+    }
+
+    /// Returns the non-secure EL1 physical timer interrupt
+    #[must_use]
+    #[inline]
+    pub const fn non_secure_physical(&self) -> GicSpecifier {
+        self.non_secure_physical
+    }
+
+    /// Returns the virtual timer interrupt
+    #[must_use]
+    #[inline]
+    pub const fn virt(&self) -> GicSpecifier {
+        self.virt
+    }
+
+    /// Returns the EL2 physical (hypervisor) timer interrupt
+    #[must_use]
+    #[inline]
+    pub const fn hypervisor(&self) -> GicSpecifier {
+        self.hypervisor
+    }
+
+    /// Returns the EL2 virtual timer interrupt, if present
+    #[must_use]
+    #[inline]
+    pub const fn hypervisor_virtual(&self) -> Option<GicSpecifier> {
+        self.hypervisor_virtual
+    }
+}
@@ -0,0 +1,586 @@
+//! Interrupt information of device nodes, and the resolution of interrupts through the interrupt tree
+//!
+//! Each interrupt of a device is described by a specifier in the format of its interrupt parent,
+//! given either by `interrupts` along with a single parent, or by `interrupts-extended` with a parent per interrupt.
+//! Resolving an interrupt follows `interrupt-parent`s (or the devicetree parent, if absent) up to an `interrupt-controller`,
+//! translating the specifier through the `interrupt-map` of any interrupt nexus along the way.
+
+pub mod arm;
+
+use super::{
+    device::{self, Node},
+    root, PropertyKeys, PropertyMap,
+};
+use crate::parse::U32ByteSlice;
+use crate::rc::{Rc, Weak};
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{ffi::CStr, iter};
+
+#[derive(Debug)]
+/// The two representations for a parent of an interrupt node
+enum InterruptParent<'node> {
+    /// A direct phandle to some other node is provided
+    PHandle(u32),
+    /// The direct parent is implicitly the device-tree parent
+    DirectParent(Weak<device::Node<'node>>),
+}
+
+#[derive(Debug, Default)]
+pub struct PartialInterruptDevice<'node> {
+    /// The device that this interrupt device belongs to
+    device: Weak<device::Node<'node>>,
+    /// The interrupt parent of this device
+    interrupt_parent: Option<InterruptParent<'node>>,
+    /// Whether or not this is an interrupt controller
+    is_controller: bool,
+    /// Interrupt cell count
+    cells: Option<u8>,
+    /// The `#address-cells` of this node, if explicitly present
+    address_cells: Option<u8>,
+    /// The `reg` property of this node, whose first address is used as the unit address when matching against an `interrupt-map`
+    unit_address: Option<U32ByteSlice<'node>>,
+    /// The interrupts property of this node
+    interrupts: Option<U32ByteSlice<'node>>,
+    /// The interrupts-extended property of this node, where each interrupt specifies its own interrupt parent
+    interrupts_extended: Option<U32ByteSlice<'node>>,
+    /// The interrupt-names property of this node
+    interrupt_names: Option<U32ByteSlice<'node>>,
+    /// The interrupts map property of this node
+    interrupt_map: Option<U32ByteSlice<'node>>,
+    /// The interrupts mask property of this node
+    interrupt_map_mask: Option<U32ByteSlice<'node>>,
+}
+
+/// An interrupt of a device, resolved to the interrupt controller that receives it
+#[derive(Debug, Clone)]
+pub struct Interrupt<'node> {
+    /// The interrupt controller that receives the interrupt
+    controller: Rc<device::Node<'node>>,
+    /// The interrupt specifier, in the format defined by the binding of the controller
+    specifier: Box<[u32]>,
+    /// The name of the interrupt, from the `interrupt-names` of the device
+    name: Option<&'node CStr>,
+}
+
+impl<'node> Interrupt<'node> {
+    /// Returns the interrupt controller that receives this interrupt
+    #[must_use]
+    #[inline]
+    pub const fn controller(&self) -> &Rc<device::Node<'node>> {
+        &self.controller
+    }
+
+    /// Returns the interrupt specifier, whose meaning is defined by the binding of the controller
+    #[must_use]
+    #[inline]
+    pub fn specifier(&self) -> &[u32] {
+        &self.specifier
+    }
+
+    /// Returns the name of this interrupt, if the device names its interrupts with `interrupt-names`
+    #[must_use]
+    #[inline]
+    pub const fn name(&self) -> Option<&'node CStr> {
+        self.name
+    }
+}
+
+/// An entry of an `interrupt-map`, which maps the interrupts of children of a nexus that match a unit address and specifier to an interrupt of another parent
+#[derive(Debug, Clone)]
+pub struct MapEntry<'node> {
+    /// The unit address of the child, in the format of the nexus
+    child_address: Box<[u32]>,
+    /// The interrupt specifier of the child, in the format of the nexus
+    child_specifier: Box<[u32]>,
+    /// The interrupt parent that matching interrupts are mapped to
+    parent: Rc<device::Node<'node>>,
+    /// The unit address in the format of the parent
+    parent_address: Box<[u32]>,
+    /// The interrupt specifier in the format of the parent
+    parent_specifier: Box<[u32]>,
+}
+
+impl<'node> MapEntry<'node> {
+    /// Returns the unit address of the child, in the format of the nexus
+    #[must_use]
+    #[inline]
+    pub fn child_address(&self) -> &[u32] {
+        &self.child_address
+    }
+
+    /// Returns the interrupt specifier of the child, in the format of the nexus
+    #[must_use]
+    #[inline]
+    pub fn child_specifier(&self) -> &[u32] {
+        &self.child_specifier
+    }
+
+    /// Returns the interrupt parent that matching interrupts are mapped to
+    #[must_use]
+    #[inline]
+    pub const fn parent(&self) -> &Rc<device::Node<'node>> {
+        &self.parent
+    }
+
+    /// Returns the unit address in the format of the parent
+    #[must_use]
+    #[inline]
+    pub fn parent_address(&self) -> &[u32] {
+        &self.parent_address
+    }
+
+    /// Returns the interrupt specifier in the format of the parent
+    #[must_use]
+    #[inline]
+    pub fn parent_specifier(&self) -> &[u32] {
+        &self.parent_specifier
+    }
+}
+
+/// Errors from resolving the interrupts of a device
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Some node on the path to the interrupt controller has no interrupt parent
+    NoParent,
+    /// An `interrupt-parent` or `interrupt-map` refers to a phandle that no node has
+    BadPHandle(u32),
+    /// An interrupt parent or nexus is missing `#interrupt-cells`
+    Cells,
+    /// The `interrupts` or `interrupts-extended` property is not a whole number of specifiers
+    Interrupts,
+    /// The `interrupt-names` property is not a list of strings
+    Names,
+    /// An `interrupt-map` or `interrupt-map-mask` is malformed
+    Map,
+    /// No entry of an `interrupt-map` matches the interrupt
+    Unmapped,
+    /// The interrupt tree is too deep, likely because it contains a cycle
+    Depth,
+    /// A node on the path to the interrupt controller no longer exists
+    Dangling,
+}
+
+/// The maximum number of interrupt parents or nexuses followed while resolving an interrupt
+const MAX_DEPTH: usize = 64;
+/// The `#address-cells` assumed for an interrupt nexus if neither it nor any of its ancestors specifies one
+const DEFAULT_ADDRESS_CELLS: u8 = 2;
+
+/// An interrupt translated through an `interrupt-map`: the new interrupt parent, along with the unit address and specifier in its format
+type Mapped<'node> = (Rc<device::Node<'node>>, Vec<u32>, Vec<u32>);
+
+/// An entry of an `interrupt-map` as stored in the property: the child unit address and specifier, the interrupt parent, and the parent unit address and specifier
+type RawMapEntry<'node, 'map> = (
+    &'map [u32],
+    Rc<device::Node<'node>>,
+    &'map [u32],
+    &'map [u32],
+);
+
+/// An interrupt as sent by a device: its interrupt parent, along with the specifier in the format of that parent
+type Specifier<'node> = (Rc<device::Node<'node>>, Vec<u32>);
+
+/// Converts a property value into a list of cells
+fn cells(bytes: U32ByteSlice<'_>) -> Option<Vec<u32>> {
+    <&[u32]>::try_from(bytes)
+        .ok()
+        .map(|cells| cells.iter().map(|&cell| u32::from_be(cell)).collect())
+}
+
+/// Looks up the device node with the given phandle
+fn from_phandle<'node>(
+    root: &root::Node<'node>,
+    phandle: u32,
+) -> Result<Rc<device::Node<'node>>, Error> {
+    root.phandles()
+        .get(&phandle)
+        .cloned()
+        .ok_or(Error::BadPHandle(phandle))
+}
+
+impl<'node> PartialInterruptDevice<'node> {
+    /// Extracts a partial interrupt device from the properties of a node.
+    ///
+    /// # Errors
+    /// Returns an error if `#interrupt-cells` or `interrupt-parent` is not a single cell
+    pub(super) fn extract_from_properties(
+        properties: &mut PropertyMap<'node>,
+        device: Weak<Node<'node>>,
+        device_parent: Option<&Weak<Node<'node>>>,
+        address_cells: Option<u8>,
+        unit_address: Option<U32ByteSlice<'node>>,
+    ) -> Result<Self, device::Error> {
+        let is_controller = properties
+            .remove(PropertyKeys::INTERRUPT_CONTROLLER)
+            .is_some();
+        let cells = properties
+            .remove(PropertyKeys::INTERRUPT_CELLS)
+            .map(|bytes| {
+                u32::try_from(bytes)
+                    .ok()
+                    .and_then(|cells| u8::try_from(cells).ok())
+                    .ok_or(device::Error::InterruptCells)
+            })
+            .transpose()?;
+        let interrupts = properties.remove(PropertyKeys::INTERRUPTS);
+        let interrupts_extended = properties.remove(PropertyKeys::INTERRUPTS_EXTENDED);
+        let interrupt_names = properties.remove(PropertyKeys::INTERRUPT_NAMES);
+        let interrupt_parent = properties
+            .remove(PropertyKeys::INTERRUPT_PARENT)
+            .map(|x| {
+                u32::try_from(x)
+                    .map(InterruptParent::PHandle)
+                    .map_err(|_err| device::Error::InterruptParent)
+            })
+            .transpose()?
+            .or_else(|| device_parent.map(|x| InterruptParent::DirectParent(Weak::clone(x))));
+        let interrupt_map = properties.remove(PropertyKeys::INTERRUPT_MAP);
+        let interrupt_map_mask = properties.remove(PropertyKeys::INTERRUPT_MAP_MASK);
+        Ok(Self {
+            device,
+            interrupt_parent,
+            is_controller,
+            cells,
+            address_cells,
+            unit_address,
+            interrupts,
+            interrupts_extended,
+            interrupt_names,
+            interrupt_map,
+            interrupt_map_mask,
+        })
+    }
+
+    /// Returns whether or not this node is an interrupt controller
+    #[must_use]
+    #[inline]
+    pub const fn is_controller(&self) -> bool {
+        self.is_controller
+    }
+
+    /// Returns the number of cells in an interrupt specifier for this node, if it is an interrupt parent
+    #[must_use]
+    #[inline]
+    pub const fn interrupt_cells(&self) -> Option<u8> {
+        self.cells
+    }
+
+    /// Returns the node that this node's interrupts are directly sent to, i.e. its `interrupt-parent`,
+    /// or its devicetree parent if not specified
+    fn direct_parent(&self, root: &root::Node<'node>) -> Result<Rc<device::Node<'node>>, Error> {
+        match self.interrupt_parent {
+            Some(InterruptParent::PHandle(phandle)) => from_phandle(root, phandle),
+            Some(InterruptParent::DirectParent(ref parent)) => {
+                parent.upgrade().ok_or(Error::Dangling)
+            }
+            // The devicetree parent is the root node, which can only redirect to another interrupt parent
+            None => {
+                use super::Node as _;
+                let phandle = root
+                    .properties()
+                    .get(PropertyKeys::INTERRUPT_PARENT)
+                    .and_then(|&phandle| u32::try_from(phandle).ok())
+                    .ok_or(Error::NoParent)?;
+                from_phandle(root, phandle)
+            }
+        }
+    }
+
+    /// Returns the interrupt parent of this node, i.e. the first node with `#interrupt-cells`,
+    /// following the interrupt parents of any nodes in between
+    fn parent(&self, root: &root::Node<'node>) -> Result<Rc<device::Node<'node>>, Error> {
+        let mut parent = self.direct_parent(root)?;
+        for _ in 0..MAX_DEPTH {
+            if parent.interrupts().cells.is_some() {
+                return Ok(parent);
+            }
+            parent = parent.interrupts().direct_parent(root)?;
+        }
+        Err(Error::Depth)
+    }
+
+    /// Returns the `#address-cells` of this node or its nearest ancestor that specifies one, for matching unit addresses against an `interrupt-map`
+    fn nexus_address_cells(&self) -> u8 {
+        self.address_cells
+            .or_else(|| {
+                let device = self.device.upgrade()?;
+                device.ancestors().find_map(|ancestor| match ancestor {
+                    super::Parent::Device(node) => node.interrupts().address_cells,
+                    super::Parent::Root
+                    | super::Parent::Cpus
+                    | super::Parent::ReservedMemory
+                    | super::Parent::Cache(_)
+                    | super::Parent::ReservedRegion(_) => None,
+                })
+            })
+            .unwrap_or(DEFAULT_ADDRESS_CELLS)
+    }
+
+    /// Removes the first entry from the given cells of an `interrupt-map`, whose child unit address and specifier together take up `child_cells` cells
+    fn split_map_entry<'map>(
+        root: &root::Node<'node>,
+        entries: &mut &'map [u32],
+        child_cells: usize,
+    ) -> Result<RawMapEntry<'node, 'map>, Error> {
+        let (child, rest) = entries.split_at_checked(child_cells).ok_or(Error::Map)?;
+        let (&phandle, rest) = rest.split_first().ok_or(Error::Map)?;
+        let parent = from_phandle(root, phandle)?;
+        let parent_address_cells = usize::from(parent.interrupts().address_cells.unwrap_or(0));
+        let parent_interrupt_cells = parent.interrupts().cells.ok_or(Error::Cells)?;
+        let (parent_address, rest) = rest
+            .split_at_checked(parent_address_cells)
+            .ok_or(Error::Map)?;
+        let (parent_specifier, rest) = rest
+            .split_at_checked(usize::from(parent_interrupt_cells))
+            .ok_or(Error::Map)?;
+        *entries = rest;
+        Ok((child, parent, parent_address, parent_specifier))
+    }
+
+    /// Iterates over the entries of the given cells of an `interrupt-map`, whose child unit address and specifier together take up `child_cells` cells.
+    ///
+    /// Entries are split lazily, so that a malformed entry only produces an error once it is reached, after which iteration ends
+    fn map_entries<'map>(
+        root: &'map root::Node<'node>,
+        mut entries: &'map [u32],
+        child_cells: usize,
+    ) -> impl Iterator<Item = Result<RawMapEntry<'node, 'map>, Error>> + 'map
+    where
+        'node: 'map,
+    {
+        iter::from_fn(move || {
+            (!entries.is_empty()).then(|| {
+                let entry = Self::split_map_entry(root, &mut entries, child_cells);
+                if entry.is_err() {
+                    entries = &[];
+                }
+                entry
+            })
+        })
+    }
+
+    /// Decodes the `interrupt-map` of this node into its entries, if this node is an interrupt nexus
+    ///
+    /// # Errors
+    /// Returns an error if this node has no `#interrupt-cells`, if the `interrupt-map` is not a whole number of entries,
+    /// or if some entry refers to an interrupt parent that does not exist or has no `#interrupt-cells`
+    #[inline]
+    pub fn interrupt_map(
+        &self,
+        root: &root::Node<'node>,
+    ) -> Result<Option<Box<[MapEntry<'node>]>>, Error> {
+        let Some(interrupt_map) = self.interrupt_map else {
+            return Ok(None);
+        };
+        let address_cells = usize::from(self.nexus_address_cells());
+        let child_cells = address_cells
+            .checked_add(usize::from(self.cells.ok_or(Error::Cells)?))
+            .ok_or(Error::Map)?;
+        let map = cells(interrupt_map).ok_or(Error::Map)?;
+        Self::map_entries(root, &map, child_cells)
+            .map(|entry| {
+                let (child, parent, parent_address, parent_specifier) = entry?;
+                let (child_address, child_specifier) =
+                    child.split_at_checked(address_cells).ok_or(Error::Map)?;
+                Ok(MapEntry {
+                    child_address: child_address.into(),
+                    child_specifier: child_specifier.into(),
+                    parent,
+                    parent_address: parent_address.into(),
+                    parent_specifier: parent_specifier.into(),
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Some)
+    }
+
+    /// Returns the `interrupt-map-mask` of this node, which is applied to the unit address and specifier of an interrupt before matching it against the `interrupt-map`
+    ///
+    /// # Errors
+    /// Returns an error if the `interrupt-map-mask` is not a list of cells
+    #[inline]
+    pub fn interrupt_map_mask(&self) -> Result<Option<Box<[u32]>>, Error> {
+        self.interrupt_map_mask
+            .map(|mask| cells(mask).map(Vec::into_boxed_slice).ok_or(Error::Map))
+            .transpose()
+    }
+
+    /// Translates the given unit address and interrupt specifier through the `interrupt-map` of this nexus,
+    /// returning the new interrupt parent along with the unit address and specifier in its format
+    fn map(
+        &self,
+        root: &root::Node<'node>,
+        interrupt_map: U32ByteSlice<'node>,
+        address: &[u32],
+        specifier: &[u32],
+    ) -> Result<Mapped<'node>, Error> {
+        let key: Vec<u32> = address.iter().chain(specifier).copied().collect();
+        let mask = self
+            .interrupt_map_mask
+            .map(|mask| cells(mask).ok_or(Error::Map))
+            .transpose()?
+            .unwrap_or_else(|| vec![u32::MAX; key.len()]);
+        if mask.len() != key.len() {
+            return Err(Error::Map);
+        }
+
+        let map = cells(interrupt_map).ok_or(Error::Map)?;
+        for raw_entry in Self::map_entries(root, &map, key.len()) {
+            let (child, parent, parent_address, parent_specifier) = raw_entry?;
+            if child
+                .iter()
+                .zip(&key)
+                .zip(&mask)
+                .all(|((&entry, &cell), &mask)| (entry ^ cell) & mask == 0)
+            {
+                return Ok((
+                    parent,
+                    Vec::from(parent_address),
+                    Vec::from(parent_specifier),
+                ));
+            }
+        }
+        Err(Error::Unmapped)
+    }
+
+    /// Resolves the interrupt with the given specifier and name, sent to the given interrupt parent by the device with the given unit address
+    fn resolve_one(
+        root: &root::Node<'node>,
+        mut parent: Rc<device::Node<'node>>,
+        mut address: Vec<u32>,
+        mut specifier: Vec<u32>,
+        name: Option<&'node CStr>,
+    ) -> Result<Interrupt<'node>, Error> {
+        for _ in 0..MAX_DEPTH {
+            let interrupts = parent.interrupts();
+            // An `interrupt-map` takes precedence over the node being a controller
+            if let Some(interrupt_map) = interrupts.interrupt_map {
+                let (next_parent, next_address, next_specifier) =
+                    interrupts.map(root, interrupt_map, &address, &specifier)?;
+                (address, specifier) = (next_address, next_specifier);
+                parent = next_parent;
+            } else if interrupts.is_controller {
+                return Ok(Interrupt {
+                    controller: parent,
+                    specifier: specifier.into_boxed_slice(),
+                    name,
+                });
+            } else {
+                parent = interrupts.parent(root)?;
+            }
+        }
+        Err(Error::Depth)
+    }
+
+    /// Returns the unit address of this device, padded or truncated to the given number of cells, for matching against an `interrupt-map`
+    fn unit_address(&self, parent: &device::Node<'node>) -> Vec<u32> {
+        let mut address = self.unit_address.and_then(cells).unwrap_or_default();
+        address.resize(usize::from(parent.interrupts().nexus_address_cells()), 0);
+        address
+    }
+
+    /// Returns the names of the interrupts of this device, from `interrupt-names`, in the same order as the interrupts
+    fn names(&self) -> Result<Vec<&'node CStr>, Error> {
+        let mut names = Vec::new();
+        let mut bytes = self.interrupt_names.map(<&[u8]>::from).unwrap_or_default();
+        while !bytes.is_empty() {
+            let name = CStr::from_bytes_until_nul(bytes).map_err(|_err| Error::Names)?;
+            bytes = name
+                .count_bytes()
+                .checked_add(1) // NUL byte
+                .and_then(|length| bytes.get(length..))
+                .expect("CStr should not go past the end of the slice");
+            names.push(name);
+        }
+        Ok(names)
+    }
+
+    /// Returns the interrupt parent and specifier of each interrupt of this device, from either `interrupts-extended` or `interrupts`
+    fn specifiers(&self, root: &root::Node<'node>) -> Result<Vec<Specifier<'node>>, Error> {
+        if let Some(interrupts_extended) = self.interrupts_extended {
+            let interrupts = cells(interrupts_extended).ok_or(Error::Interrupts)?;
+            let mut interrupts = interrupts.as_slice();
+            let mut specifiers = Vec::new();
+            // Each entry is a phandle to its interrupt parent, followed by a specifier in the format of that parent
+            while let Some((&phandle, rest)) = interrupts.split_first() {
+                let parent = from_phandle(root, phandle)?;
+                let interrupt_cells = parent.interrupts().cells.ok_or(Error::Cells)?;
+                let (specifier, rest) = rest
+                    .split_at_checked(usize::from(interrupt_cells))
+                    .ok_or(Error::Interrupts)?;
+                specifiers.push((parent, Vec::from(specifier)));
+                interrupts = rest;
+            }
+            return Ok(specifiers);
+        }
+
+        let Some(interrupts) = self.interrupts else {
+            return Ok(Vec::new());
+        };
+        let parent = self.parent(root)?;
+        let interrupt_cells = usize::from(parent.interrupts().cells.ok_or(Error::Cells)?);
+        let interrupts = cells(interrupts).ok_or(Error::Interrupts)?;
+        if interrupts.len().checked_rem(interrupt_cells) != Some(0) {
+            return Err(Error::Interrupts);
+        }
+        Ok(interrupts
+            .chunks(interrupt_cells)
+            .map(|specifier| (Rc::clone(&parent), Vec::from(specifier)))
+            .collect())
+    }
+
+    /// Resolves each interrupt of this device to the controller that receives it, in order.
+    ///
+    /// `interrupts-extended` is used in preference to `interrupts` if both are present,
+    /// and each interrupt is named by the corresponding entry of `interrupt-names`, if any
+    ///
+    /// # Errors
+    /// Returns an error if the interrupt parent of this device cannot be found,
+    /// or if some interrupt cannot be translated to a controller
+    #[inline]
+    pub fn resolve(&self, root: &root::Node<'node>) -> Result<Box<[Interrupt<'node>]>, Error> {
+        let mut names = self.names()?.into_iter();
+        self.specifiers(root)?
+            .into_iter()
+            .map(|(parent, specifier)| {
+                let address = self.unit_address(&parent);
+                Self::resolve_one(root, parent, address, specifier, names.next())
+            })
+            .collect()
+    }
+
+    /// Resolves the interrupt with the given name in `interrupt-names` to the controller that receives it.
+    /// Returns `None` if this device has no interrupt with the given name
+    ///
+    /// # Errors
+    /// Returns an error if the interrupt parent of this device cannot be found,
+    /// or if the named interrupt cannot be translated to a controller
+    #[inline]
+    pub fn resolve_by_name(
+        &self,
+        root: &root::Node<'node>,
+        name: &[u8],
+    ) -> Result<Option<Interrupt<'node>>, Error> {
+        let Some((index, name)) = self
+            .names()?
+            .into_iter()
+            .enumerate()
+            .find(|&(_, candidate)| candidate.to_bytes() == name)
+        else {
+            return Ok(None);
+        };
+        let Some((parent, specifier)) = self.specifiers(root)?.into_iter().nth(index) else {
+            return Ok(None);
+        };
+        let address = self.unit_address(&parent);
+        Self::resolve_one(root, parent, address, specifier, Some(name)).map(Some)
+    }
+}
+
+// #[derive(Debug)]
+// pub struct Generator<'node> {
+//     device: Weak<device::DeviceNode<'node>>,
+//     /// Because the hierarchy of the nodes in the interrupt tree might not match the devicetree, the interrupt-parent property is available to make the definition of an interrupt parent explicit. The value is the phandle to the interrupt parent. If this property is missing from a device, its interrupt parent is assumed to be its devicetree parent.
+//     parent: Option<Weak<InterruptDevice<'node>>>,
+//     interrupts: Box<()>,
+// }
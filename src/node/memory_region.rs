@@ -1,8 +1,9 @@
 //! Types to describe the physical memory present on a device, as specified under the root node of the Device Tree
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, string::String, vec::Vec};
 
-use super::{PropertyKeys, RawNode};
+use super::{Ancestors, Locate, Parent, PropertyKeys, RawNode};
 use crate::{map::Map, node_name::NameRef, parse::U32ByteSlice};
+use core::fmt::{self, Display};
 use core::{ffi::CStr, num::NonZeroU32};
 
 /// An initially mapped area of memory provided by the bootloader.
@@ -21,6 +22,19 @@ pub struct InitialMappedArea {
     pub size: NonZeroU32,
 }
 
+impl InitialMappedArea {
+    /// The number of bytes in each entry of an `initial-mapped-area`: a 64-bit effective address, a 64-bit physical address, and a 32-bit size
+    const ENTRY_BYTES: usize = 20;
+
+    /// Returns whether or not the physical memory of this mapping lies entirely within the region of the given size at the given address
+    fn is_within(&self, address: u64, size: u64) -> bool {
+        self.physical_address
+            .checked_sub(address)
+            .and_then(|offset| offset.checked_add(self.size.get().into()))
+            .is_some_and(|end| end <= size)
+    }
+}
+
 impl TryFrom<U32ByteSlice<'_>> for InitialMappedArea {
     type Error = ();
 
@@ -37,12 +51,14 @@ impl TryFrom<U32ByteSlice<'_>> for InitialMappedArea {
 /// A physical memory region
 #[derive(Debug)]
 pub struct MemoryRegion<'node> {
+    /// The unit name of this node
+    name: NameRef<'node>,
     /// The various regions of physical memory
     regions: Box<[(u64, u64)]>,
     /// Specifies an explicit hint to the operating system that this memory may potentially be removed later.
     hotpluggable: bool,
-    /// Specifies the address and size of the Initial Mapped Area
-    initial_mapped_area: Option<InitialMappedArea>,
+    /// Specifies the addresses and sizes of the Initial Mapped Areas, each of which lies within one of the regions
+    initial_mapped_areas: Box<[InitialMappedArea]>,
     /// Miscellaneous other properties
     properties: Map<&'node CStr, U32ByteSlice<'node>>,
 }
@@ -57,6 +73,40 @@ pub enum Error {
     Reg,
     /// Unexpected children of this node
     Children,
+    /// The `initial-mapped-area` is not a whole number of entries of an effective address, a physical address, and a nonzero size
+    InitialMappedArea,
+    /// Some initial mapped area does not lie entirely within one of the regions of the `reg`
+    UnmappedArea,
+}
+
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Type => f.write_str("not `\"memory\"`"),
+            Self::Reg => f.write_str("missing, not a whole number of entries of `#address-cells` and `#size-cells`, or not matching the unit address"),
+            Self::Children => f.write_str("memory nodes cannot have children"),
+            Self::InitialMappedArea => f.write_str("not a whole number of entries of a 64-bit effective address, a 64-bit physical address, and a nonzero 32-bit size"),
+            Self::UnmappedArea => f.write_str("maps physical memory outside of the regions of `reg`"),
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for Error {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::Type => Some(PropertyKeys::DEVICE_TYPE),
+            Self::Reg => Some(PropertyKeys::REG),
+            Self::InitialMappedArea | Self::UnmappedArea => Some(PropertyKeys::INITIAL_MAPPED_AREA),
+            Self::Children => None,
+        }
+    }
 }
 
 impl<'node> MemoryRegion<'node> {
@@ -86,6 +136,10 @@ impl<'node> MemoryRegion<'node> {
             .remove(PropertyKeys::REG)
             .ok_or(Error::Reg)?;
 
+        if address_cells == 0 && size_cells == 0 && !bytes.is_empty() {
+            return Err(Error::Reg);
+        }
+
         let mut memory = Vec::new();
 
         while !bytes.is_empty() {
@@ -97,14 +151,60 @@ impl<'node> MemoryRegion<'node> {
             }
             memory.push((start, size));
         }
+
+        let mut initial_mapped_areas = Vec::new();
+        if let Some(mut entries) = node.properties.remove(PropertyKeys::INITIAL_MAPPED_AREA) {
+            while !entries.is_empty() {
+                let area = entries
+                    .take(InitialMappedArea::ENTRY_BYTES)
+                    .and_then(|entry| InitialMappedArea::try_from(entry).ok())
+                    .ok_or(Error::InitialMappedArea)?;
+                if !memory
+                    .iter()
+                    .any(|&(start, size)| area.is_within(start, size))
+                {
+                    return Err(Error::UnmappedArea);
+                }
+                initial_mapped_areas.push(area);
+            }
+        }
         Ok(MemoryRegion {
+            name: *name,
             regions: memory.into_boxed_slice(),
             hotpluggable,
-            initial_mapped_area: None,
+            initial_mapped_areas: initial_mapped_areas.into_boxed_slice(),
             properties: node.properties,
         })
     }
 
+    /// Returns the unit name of this node
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> NameRef<'node> {
+        self.name
+    }
+
+    /// Returns the parent of this node, which is always the root node
+    #[inline]
+    #[must_use]
+    pub const fn parent(&self) -> Option<Parent<'node>> {
+        Some(Parent::Root)
+    }
+
+    /// Returns an iterator over the ancestors of this node, i.e. only the root node
+    #[inline]
+    #[must_use]
+    pub const fn ancestors(&self) -> Ancestors<'node> {
+        Ancestors::new(self.parent())
+    }
+
+    /// Returns the full path of this node, e.g. `/memory@0`
+    #[inline]
+    #[must_use]
+    pub fn path(&self) -> String {
+        super::path(self.name, self.ancestors())
+    }
+
     #[inline]
     #[must_use]
     pub fn regions(&self) -> &[(u64, u64)] {
@@ -117,10 +217,22 @@ impl<'node> MemoryRegion<'node> {
         self.hotpluggable
     }
 
+    /// Returns the areas of this memory that the boot program has already mapped, from `initial-mapped-area`.
+    /// Each lies entirely within one of the `regions`
+    #[inline]
+    #[must_use]
+    pub fn initial_mapped_areas(&self) -> &[InitialMappedArea] {
+        &self.initial_mapped_areas
+    }
+
+    /// Returns the first area of this memory that the boot program has already mapped, if any
     #[inline]
     #[must_use]
-    pub const fn initial_mapped_area(&self) -> Option<&InitialMappedArea> {
-        self.initial_mapped_area.as_ref()
+    #[deprecated(
+        note = "memory may have several initial mapped areas; use `initial_mapped_areas` instead"
+    )]
+    pub fn initial_mapped_area(&self) -> Option<&InitialMappedArea> {
+        self.initial_mapped_areas.first()
     }
 
     #[inline]
@@ -5,9 +5,13 @@ use crate::node_name::NameRef;
 
 use crate::parse::to_c_str;
 use crate::parse::U32ByteSlice;
-use alloc::rc::Rc;
-use alloc::rc::Weak;
+use crate::rc::{Rc, Weak};
+use alloc::string::String;
+use alloc::vec::Vec;
+use cache::HigherLevel;
 use core::ffi::CStr;
+use core::fmt::{self, Display, Write};
+use root::NodeNames;
 
 pub mod cache;
 pub mod chosen;
@@ -15,6 +19,7 @@ pub mod cpu;
 pub mod device;
 pub mod interrupt;
 pub mod memory_region;
+pub mod pci;
 pub mod reserved_memory;
 pub mod root;
 
@@ -32,6 +37,7 @@ impl PropertyKeys {
     pub const SIZE_CELLS: &'static CStr = to_c_str(b"#size-cells\0");
     pub const REG: &'static CStr = to_c_str(b"reg\0");
     pub const RANGES: &'static CStr = to_c_str(b"ranges\0");
+    pub const DMA_RANGES: &'static CStr = to_c_str(b"dma-ranges\0");
     pub const COMPATIBLE: &'static CStr = to_c_str(b"compatible\0");
     pub const CHASSIS: &'static CStr = to_c_str(b"chassis-type\0");
     pub const MODEL: &'static CStr = to_c_str(b"model\0");
@@ -45,6 +51,7 @@ impl PropertyKeys {
     pub const ALLOC_RANGES: &'static CStr = to_c_str(b"alloc-ranges\0");
     pub const MEMORY: &'static CStr = to_c_str(b"memory\0");
     pub const HOTPLUGGABLE: &'static CStr = to_c_str(b"hotpluggable\0");
+    pub const INITIAL_MAPPED_AREA: &'static CStr = to_c_str(b"initial-mapped-area\0");
     pub const RESERVED_MEMORY: &'static CStr = to_c_str(b"reserved-memory\0");
     pub const PHANDLE: &'static CStr = to_c_str(b"phandle\0");
     pub const CACHE_LEVEL: &'static CStr = to_c_str(b"cache-level\0");
@@ -61,18 +68,155 @@ impl PropertyKeys {
     pub const INTERRUPT_CONTROLLER: &'static CStr = to_c_str(b"interrupt-controller\0");
     pub const INTERRUPT_CELLS: &'static CStr = to_c_str(b"#interrupt-cells\0");
     pub const INTERRUPTS: &'static CStr = to_c_str(b"interrupts\0");
+    pub const INTERRUPTS_EXTENDED: &'static CStr = to_c_str(b"interrupts-extended\0");
+    pub const INTERRUPT_NAMES: &'static CStr = to_c_str(b"interrupt-names\0");
     pub const INTERRUPT_PARENT: &'static CStr = to_c_str(b"interrupt-parent\0");
     pub const INTERRUPT_MAP: &'static CStr = to_c_str(b"interrupt-map\0");
     pub const INTERRUPT_MAP_MASK: &'static CStr = to_c_str(b"interrupt-map-mask\0");
 }
 
+/// The parent of a node
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Parent<'node> {
+    /// The root node
+    Root,
+    /// The `/cpus` node, which is represented only through the CPUs and caches it contains
+    Cpus,
+    /// The `/reserved-memory` node, which is represented only through the regions it contains
+    ReservedMemory,
+    /// A generic device node
+    Device(Rc<device::Node<'node>>),
+    /// A higher-level cache node
+    Cache(Rc<HigherLevel<'node>>),
+    /// A reserved memory region node
+    ReservedRegion(Rc<reserved_memory::Node<'node>>),
+}
+
+impl<'node> Parent<'node> {
+    /// Returns the unit name of this node, or `None` for the root node
+    #[must_use]
+    #[inline]
+    pub fn name(&self) -> Option<NameRef<'node>> {
+        match *self {
+            Self::Root => None,
+            Self::Cpus => Some(NodeNames::cpus()),
+            Self::ReservedMemory => Some(NodeNames::reserved_memory()),
+            Self::Device(ref node) => Some(node.name()),
+            Self::Cache(ref node) => Some(node.name()),
+            Self::ReservedRegion(ref node) => Some(node.name()),
+        }
+    }
+
+    /// Returns the parent of this node, or `None` for the root node
+    #[must_use]
+    #[inline]
+    pub fn parent(&self) -> Option<Self> {
+        match *self {
+            Self::Root => None,
+            Self::Cpus | Self::ReservedMemory => Some(Self::Root),
+            Self::Device(ref node) => node.parent(),
+            Self::Cache(ref node) => node.parent(),
+            Self::ReservedRegion(ref node) => node.parent(),
+        }
+    }
+
+    /// Returns an iterator over the ancestors of this node, from its parent up to the root node
+    #[must_use]
+    #[inline]
+    pub fn ancestors(&self) -> Ancestors<'node> {
+        Ancestors::new(self.parent())
+    }
+
+    /// Returns the full path of this node, e.g. `/soc`
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> String {
+        self.name()
+            .map_or_else(|| String::from("/"), |name| path(name, self.ancestors()))
+    }
+}
+
+/// A non-owning link from a device node to its parent
+#[derive(Debug, Clone)]
+pub(crate) enum ParentLink<'node> {
+    /// The parent is the root node
+    Root,
+    /// The parent is a generic device node
+    Device(Weak<device::Node<'node>>),
+    /// The parent is a higher-level cache node
+    Cache(Weak<HigherLevel<'node>>),
+    /// The parent is a reserved memory region node
+    ReservedRegion(Weak<reserved_memory::Node<'node>>),
+}
+
+impl<'node> ParentLink<'node> {
+    /// Returns the parent that this link refers to, or `None` if it no longer exists
+    pub(crate) fn upgrade(&self) -> Option<Parent<'node>> {
+        match *self {
+            Self::Root => Some(Parent::Root),
+            Self::Device(ref node) => node.upgrade().map(Parent::Device),
+            Self::Cache(ref node) => node.upgrade().map(Parent::Cache),
+            Self::ReservedRegion(ref node) => node.upgrade().map(Parent::ReservedRegion),
+        }
+    }
+
+    /// Returns the parent that this link refers to, if it is a generic device node
+    pub(crate) const fn device(&self) -> Option<&Weak<device::Node<'node>>> {
+        if let Self::Device(ref node) = *self {
+            Some(node)
+        } else {
+            None
+        }
+    }
+}
+
+/// An iterator over the ancestors of a node, from its parent up to the root node
+#[derive(Debug, Clone)]
+pub struct Ancestors<'node> {
+    /// The next ancestor to yield
+    next: Option<Parent<'node>>,
+}
+
+impl<'node> Ancestors<'node> {
+    /// Creates an iterator over the given parent and its ancestors
+    pub(crate) const fn new(parent: Option<Parent<'node>>) -> Self {
+        Self { next: parent }
+    }
+}
+
+impl<'node> Iterator for Ancestors<'node> {
+    type Item = Parent<'node>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        self.next = current.parent();
+        Some(current)
+    }
+}
+
+/// Builds the full path of a node from its unit name and its ancestors
+pub(crate) fn path(name: NameRef<'_>, ancestors: Ancestors<'_>) -> String {
+    let mut names: Vec<_> = ancestors.filter_map(|ancestor| ancestor.name()).collect();
+    names.reverse();
+    names.push(name);
+    let mut path = String::new();
+    for component in names {
+        write!(path, "/{component}").expect("Writing to a `String` should never fail");
+    }
+    path
+}
+
 /// A Device Tree Node
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct RawNode<'node> {
     /// Unparsed children, mapped from name to raw node
     pub(crate) children: Map<NameRef<'node>, RawNode<'node>>,
     /// Unparsed properties
     pub(crate) properties: PropertyMap<'node>,
+    /// The byte offset of this node's `BeginNode` token from the start of the structure block
+    pub(crate) offset: usize,
 }
 
 /// Errors from parsing the address and size cell count properties of a node
@@ -85,24 +229,96 @@ pub enum CellError {
     Invalid,
 }
 
+impl Display for CellError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NotPresent => f.write_str("`#address-cells` or `#size-cells` is missing"),
+            Self::Invalid => f.write_str("`#address-cells` or `#size-cells` is not a single cell"),
+        }
+    }
+}
+
 /// Errors from attempting to convert a raw node's children into the appropriate device nodes
 #[non_exhaustive]
 pub enum RawNodeError {
     /// Either the `address-cells` or `size-cells` field was invalid or missing when required
     Cells,
-    /// Error from parsing some child node
-    Child(device::Error),
+    /// Error from parsing the child node at the given offset in the structure block
+    Child(usize, device::Error),
+}
+
+/// Errors from parsing nodes, which can be traced back to the node and property responsible
+pub(crate) trait Locate<'dtb> {
+    /// Returns the byte offset, from the start of the structure block, of the descendant node responsible for this error.
+    /// Returns `None` if the node that produced this error is itself responsible
+    fn node_offset(&self) -> Option<usize>;
+
+    /// Returns the name of the property responsible for this error, if any single property is
+    fn property(&self) -> Option<&'dtb CStr>;
+}
+
+/// Collects the errors tolerated while parsing leniently, which are reported as warnings instead of failing the parse
+pub(crate) struct Warnings<'sink, E> {
+    /// Where tolerated errors are recorded, or `None` if parsing strictly
+    sink: Option<&'sink mut Vec<E>>,
+}
+
+impl<'sink, E> Warnings<'sink, E> {
+    /// Creates a collector that tolerates no errors
+    pub(crate) const fn strict() -> Self {
+        Self { sink: None }
+    }
+
+    /// Creates a collector that tolerates errors by recording them into the given list
+    pub(crate) const fn lenient(sink: &'sink mut Vec<E>) -> Self {
+        Self { sink: Some(sink) }
+    }
+
+    /// Returns whether or not errors are tolerated
+    pub(crate) const fn is_lenient(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Records the given error as a warning if parsing leniently, or returns it to fail the parse otherwise
+    pub(crate) fn tolerate(&mut self, error: E) -> Result<(), E> {
+        match self.sink {
+            Some(ref mut sink) => {
+                sink.push(error);
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Runs the given parser with a collector for a nested type of error, recording any warnings it produces as converted by `wrap`
+    pub(crate) fn nest<F, R>(
+        &mut self,
+        wrap: impl Fn(F) -> E,
+        parse: impl FnOnce(&mut Warnings<'_, F>) -> R,
+    ) -> R {
+        let mut nested = Vec::new();
+        let result = parse(&mut Warnings {
+            sink: self.is_lenient().then_some(&mut nested),
+        });
+        if let Some(ref mut sink) = self.sink {
+            sink.extend(nested.into_iter().map(wrap));
+        }
+        result
+    }
 }
 
 impl<'node> RawNode<'node> {
-    /// Creates a node with the given name, children, and properties
+    /// Creates a node with the given name, children, and properties, whose `BeginNode` token is at the given offset
     pub(crate) fn new(
         children: impl IntoIterator<Item = (NameRef<'node>, Self)>,
         properties: Map<&'node CStr, U32ByteSlice<'node>>,
+        offset: usize,
     ) -> Self {
         Self {
             children: children.into_iter().collect(),
             properties,
+            offset,
         }
     }
 
@@ -131,7 +347,7 @@ impl<'node> RawNode<'node> {
     fn into_components(
         mut self,
         phandles: &mut Map<u32, Rc<device::Node<'node>>>,
-        me: Option<&Weak<device::Node<'node>>>,
+        me: &ParentLink<'node>,
     ) -> (PropertyMap<'node>, Result<ChildMap<'node>, RawNodeError>) {
         let (child_addr_cells, child_size_cells) = self.extract_cell_counts();
         (
@@ -144,17 +360,19 @@ impl<'node> RawNode<'node> {
                 self.children
                     .into_iter()
                     .map(|(name, raw_node)| {
+                        let offset = raw_node.offset;
                         device::Node::new(
                             raw_node,
+                            name,
                             child_addr_cells.ok(),
                             child_size_cells.ok(),
                             phandles,
                             me,
                         )
                         .map(|device_node| (name, device_node))
+                        .map_err(|err| RawNodeError::Child(offset, err))
                     })
                     .try_collect()
-                    .map_err(RawNodeError::Child)
             },
         )
     }
@@ -167,18 +385,19 @@ impl<'node> RawNode<'node> {
         address_cells: Option<u8>,
         size_cells: Option<u8>,
         phandles: &mut Map<u32, Rc<device::Node<'node>>>,
-        me: Option<&Weak<device::Node<'node>>>,
+        me: &ParentLink<'node>,
     ) -> (PropertyMap<'node>, Result<ChildMap<'node>, RawNodeError>) {
         (
             self.properties,
             self.children
                 .into_iter()
                 .map(|(name, raw_node)| {
-                    device::Node::new(raw_node, address_cells, size_cells, phandles, me)
+                    let offset = raw_node.offset;
+                    device::Node::new(raw_node, name, address_cells, size_cells, phandles, me)
                         .map(|device_node| (name, device_node))
+                        .map_err(|err| RawNodeError::Child(offset, err))
                 })
-                .try_collect()
-                .map_err(RawNodeError::Child),
+                .try_collect(),
         )
     }
 }
@@ -209,11 +428,13 @@ pub trait Node<'data> {
     where
         'path: 'data,
     {
-        let mut names = path
+        let names: Vec<NameRef<'path>> = path
             .split(|&char| char == b'/')
             .filter(|x| !x.is_empty())
-            .map(|x| NameRef::try_from(x).unwrap());
+            .map(|x| NameRef::try_from(x).ok())
+            .collect::<Option<_>>()?;
 
+        let mut names = names.into_iter();
         let direct_child_name = names.next()?;
         self.find(direct_child_name, names)
     }
@@ -0,0 +1,478 @@
+//! Typed decoding of the addresses of PCI buses, as used in the `reg`, `ranges`, `dma-ranges`, and `interrupt-map` of PCI host bridges and their children
+//!
+//! A PCI address is made up of three cells: `phys.hi`, followed by the 64-bit `phys.mid` and `phys.lo`.
+//! The `phys.hi` cell is laid out as `npt000ss bbbbbbbb dddddfff rrrrrrrr`, where
+//! `n` is set if the address is not relocatable, `p` is set if the region is prefetchable, `t` is set if the address is aliased,
+//! `ss` is the space code, `bbbbbbbb` is the bus number, `ddddd` is the device number, `fff` is the function number,
+//! and `rrrrrrrr` is the register number
+
+use super::interrupt::{Error as ResolveError, MapEntry};
+use super::{device, root, Node as _, PropertyKeys};
+use crate::property;
+use crate::rc::Rc;
+use alloc::boxed::Box;
+
+/// The number of cells in a PCI address
+const ADDRESS_CELLS: u8 = 3;
+
+/// The `device_type` of PCI buses
+const DEVICE_TYPE: &[u8] = b"pci\0";
+
+/// The entries of the `reg` of a child of a PCI bus, as (PCI address, size) pairs
+type Reg = Box<[(Address, u64)]>;
+
+/// Errors from decoding PCI addresses or the properties of a PCI host bridge
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The node is not a PCI bus, i.e. its `device_type` is not `"pci"`
+    Bridge,
+    /// An address does not have the three cells of a PCI address, but instead the given number of cells
+    Cells(u8),
+    /// An interrupt pin is not one of `INTA` to `INTD`
+    Pin(u32),
+    /// An entry of the `interrupt-map` does not have a PCI address followed by a single interrupt pin cell,
+    /// or the `interrupt-map-mask` is not four cells
+    InterruptMap,
+    /// The `interrupt-map` could not be decoded
+    Resolve(ResolveError),
+}
+
+/// The address space that a PCI address refers to, from the `ss` bits of `phys.hi`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Space {
+    /// Configuration space
+    Configuration,
+    /// I/O space
+    Io,
+    /// 32-bit memory space
+    Memory32,
+    /// 64-bit memory space
+    Memory64,
+}
+
+/// A decoded PCI address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address {
+    /// The `phys.hi` cell, holding the space code, flags, and bus, device, function, and register numbers
+    hi: u32,
+    /// The `phys.mid` and `phys.lo` cells, holding the address within the space
+    address: u64,
+}
+
+impl Address {
+    /// Bit of `phys.hi` that is set if the address is not relocatable
+    const NON_RELOCATABLE: u32 = 1 << 31;
+    /// Bit of `phys.hi` that is set if the region is prefetchable
+    const PREFETCHABLE: u32 = 1 << 30;
+    /// Bit of `phys.hi` that is set if the address is aliased
+    const ALIASED: u32 = 1 << 29;
+
+    /// Creates a PCI address from its `phys.hi` cell and the 64-bit address formed by its `phys.mid` and `phys.lo` cells
+    #[must_use]
+    #[inline]
+    pub const fn new(hi: u32, address: u64) -> Self {
+        Self { hi, address }
+    }
+
+    /// Returns the raw `phys.hi` cell
+    #[must_use]
+    #[inline]
+    pub const fn hi(&self) -> u32 {
+        self.hi
+    }
+
+    /// Returns the address within the address space, from the `phys.mid` and `phys.lo` cells
+    #[must_use]
+    #[inline]
+    pub const fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Returns the address space that this address refers to
+    #[must_use]
+    #[inline]
+    pub const fn space(&self) -> Space {
+        match (self.hi >> 24) & 0b11 {
+            0b00 => Space::Configuration,
+            0b01 => Space::Io,
+            0b10 => Space::Memory32,
+            _ => Space::Memory64,
+        }
+    }
+
+    /// Returns whether or not the region is prefetchable
+    #[must_use]
+    #[inline]
+    pub const fn prefetchable(&self) -> bool {
+        self.hi & Self::PREFETCHABLE != 0
+    }
+
+    /// Returns whether or not the address is relocatable, i.e. may be assigned by the operating system
+    #[must_use]
+    #[inline]
+    pub const fn relocatable(&self) -> bool {
+        self.hi & Self::NON_RELOCATABLE == 0
+    }
+
+    /// Returns whether or not the address is aliased, i.e. is a 10-bit I/O address or a memory address below 1 MiB that is aliased
+    #[must_use]
+    #[inline]
+    pub const fn aliased(&self) -> bool {
+        self.hi & Self::ALIASED != 0
+    }
+
+    /// Returns the bus number
+    #[must_use]
+    #[inline]
+    pub const fn bus(&self) -> u8 {
+        self.hi.to_be_bytes()[1]
+    }
+
+    /// Returns the device number
+    #[must_use]
+    #[inline]
+    pub const fn device(&self) -> u8 {
+        self.hi.to_be_bytes()[2] >> 3
+    }
+
+    /// Returns the function number
+    #[must_use]
+    #[inline]
+    pub const fn function(&self) -> u8 {
+        self.hi.to_be_bytes()[2] & 0b111
+    }
+
+    /// Returns the register number, i.e. the offset of a base address register in the configuration space of the function
+    #[must_use]
+    #[inline]
+    pub const fn register(&self) -> u8 {
+        self.hi.to_be_bytes()[3]
+    }
+
+    /// Returns the three cells of this address, as they appear in the devicetree
+    #[must_use]
+    #[inline]
+    #[expect(clippy::missing_panics_doc, reason = "Checks should never fail")]
+    pub fn to_cells(self) -> [u32; 3] {
+        let [mid, lo] = [
+            self.address >> u32::BITS,
+            self.address & u64::from(u32::MAX),
+        ]
+        .map(|cell| u32::try_from(cell).expect("Each half of a `u64` should fit into a `u32`"));
+        [self.hi, mid, lo]
+    }
+
+    /// Decodes the `reg` of a child of a PCI bus, whose addresses identify the function and the base address registers it uses
+    ///
+    /// # Errors
+    /// Returns an error if the addresses of the `reg` are not PCI addresses
+    #[inline]
+    pub fn parse_reg(node: &device::Node<'_>) -> Result<Option<Reg>, Error> {
+        node.reg()
+            .map(|reg| {
+                reg.iter()
+                    .map(|&(address, size)| Ok((Self::try_from(address)?, size)))
+                    .collect()
+            })
+            .transpose()
+    }
+}
+
+impl TryFrom<property::Address> for Address {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: property::Address) -> Result<Self, Self::Error> {
+        match (value.cell(0), value.cell(1), value.cell(2)) {
+            (Some(hi), Some(mid), Some(lo)) if value.cells() == ADDRESS_CELLS => {
+                Self::try_from([hi, mid, lo].as_slice())
+            }
+            _ => Err(Error::Cells(value.cells())),
+        }
+    }
+}
+
+impl TryFrom<&[u32]> for Address {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &[u32]) -> Result<Self, Self::Error> {
+        let &[hi, mid, lo] = value else {
+            return Err(Error::Cells(u8::try_from(value.len()).unwrap_or(u8::MAX)));
+        };
+        Ok(Self {
+            hi,
+            address: (u64::from(mid) << u32::BITS) | u64::from(lo),
+        })
+    }
+}
+
+/// A decoded entry of the `ranges` or `dma-ranges` of a PCI bus, which maps a region of a PCI address space to the address space of the bus' parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    /// The PCI address of the start of the region
+    pci_address: Address,
+    /// The address of the start of the region in the address space of the bus' parent
+    parent_address: property::Address,
+    /// The size of the region
+    length: u64,
+}
+
+impl Range {
+    /// Returns the PCI address of the start of the region, which also gives its address space
+    #[must_use]
+    #[inline]
+    pub const fn pci_address(&self) -> Address {
+        self.pci_address
+    }
+
+    /// Returns the address of the start of the region in the address space of the bus' parent
+    #[must_use]
+    #[inline]
+    pub const fn parent_address(&self) -> property::Address {
+        self.parent_address
+    }
+
+    /// Returns the size of the region
+    #[must_use]
+    #[inline]
+    pub const fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Translates the region of the given size at the given PCI address into the address space of the bus' parent,
+    /// if the region is in the same address space and lies entirely within this range
+    #[must_use]
+    #[inline]
+    pub fn translate(&self, address: &Address, size: u64) -> Option<property::Address> {
+        if address.space() != self.pci_address.space() {
+            return None;
+        }
+        let offset = address.address.checked_sub(self.pci_address.address)?;
+        (offset.checked_add(size)? <= self.length)
+            .then(|| self.parent_address.checked_add(offset.into()))
+            .flatten()
+    }
+}
+
+impl TryFrom<&property::Range> for Range {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &property::Range) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pci_address: Address::try_from(value.child_address)?,
+            parent_address: value.parent_address,
+            length: value.length,
+        })
+    }
+}
+
+/// A PCI interrupt pin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Pin {
+    /// `INTA`
+    IntA,
+    /// `INTB`
+    IntB,
+    /// `INTC`
+    IntC,
+    /// `INTD`
+    IntD,
+}
+
+impl Pin {
+    /// Returns the number of this pin, as used in interrupt specifiers, from 1 for `INTA` to 4 for `INTD`
+    #[must_use]
+    #[inline]
+    pub const fn number(self) -> u32 {
+        match self {
+            Self::IntA => 1,
+            Self::IntB => 2,
+            Self::IntC => 3,
+            Self::IntD => 4,
+        }
+    }
+}
+
+impl TryFrom<u32> for Pin {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::IntA),
+            2 => Ok(Self::IntB),
+            3 => Ok(Self::IntC),
+            4 => Ok(Self::IntD),
+            _ => Err(Error::Pin(value)),
+        }
+    }
+}
+
+/// A decoded entry of the `interrupt-map` of a PCI bus, which maps an interrupt pin of the functions matching a PCI address to an interrupt of another parent
+#[derive(Debug, Clone)]
+pub struct InterruptMapEntry<'node> {
+    /// The PCI address of the function, of which usually only the bus, device, and function numbers are significant
+    address: Address,
+    /// The interrupt pin of the function
+    pin: Pin,
+    /// The interrupt parent that the interrupt is mapped to
+    parent: Rc<device::Node<'node>>,
+    /// The interrupt specifier in the format of the parent
+    parent_specifier: Box<[u32]>,
+}
+
+impl<'node> InterruptMapEntry<'node> {
+    /// Returns the PCI address of the function, of which usually only the bus, device, and function numbers are significant
+    #[must_use]
+    #[inline]
+    pub const fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns the interrupt pin of the function
+    #[must_use]
+    #[inline]
+    pub const fn pin(&self) -> Pin {
+        self.pin
+    }
+
+    /// Returns the interrupt parent that the interrupt is mapped to
+    #[must_use]
+    #[inline]
+    pub const fn parent(&self) -> &Rc<device::Node<'node>> {
+        &self.parent
+    }
+
+    /// Returns the interrupt specifier in the format of the parent
+    #[must_use]
+    #[inline]
+    pub fn parent_specifier(&self) -> &[u32] {
+        &self.parent_specifier
+    }
+}
+
+impl<'node> TryFrom<&MapEntry<'node>> for InterruptMapEntry<'node> {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &MapEntry<'node>) -> Result<Self, Self::Error> {
+        let &[pin] = value.child_specifier() else {
+            return Err(Error::InterruptMap);
+        };
+        Ok(Self {
+            address: Address::try_from(value.child_address())?,
+            pin: Pin::try_from(pin)?,
+            parent: Rc::clone(value.parent()),
+            parent_specifier: value.parent_specifier().into(),
+        })
+    }
+}
+
+/// The decoded `ranges`, `dma-ranges`, and `interrupt-map` of a PCI host bridge
+#[derive(Debug, Clone)]
+pub struct HostBridge<'node> {
+    /// The regions of PCI address spaces that are accessible from the parent bus
+    ranges: Box<[Range]>,
+    /// The regions of the parent bus that are accessible to DMA masters on the PCI bus
+    dma_ranges: Box<[Range]>,
+    /// The mapping of the interrupt pins of functions to interrupts of other parents
+    interrupt_map: Box<[InterruptMapEntry<'node>]>,
+    /// The mask applied to the PCI address and pin of an interrupt before matching it against the `interrupt-map`
+    interrupt_map_mask: [u32; 4],
+}
+
+impl<'node> HostBridge<'node> {
+    /// Decodes the properties of the given PCI host bridge node
+    ///
+    /// # Errors
+    /// Returns an error if the node is not a PCI bus, if its `ranges` or `dma-ranges` do not have PCI child addresses,
+    /// or if its `interrupt-map` cannot be decoded
+    #[inline]
+    pub fn new(node: &device::Node<'node>, root: &root::Node<'node>) -> Result<Self, Error> {
+        let is_pci = node
+            .properties()
+            .get(PropertyKeys::DEVICE_TYPE)
+            .is_some_and(|&device_type| <&[u8]>::from(device_type) == DEVICE_TYPE);
+        if !is_pci {
+            return Err(Error::Bridge);
+        }
+        let decode_ranges = |ranges: Option<&[property::Range]>| {
+            ranges
+                .unwrap_or_default()
+                .iter()
+                .map(Range::try_from)
+                .collect::<Result<Box<[_]>, _>>()
+        };
+        let interrupts = node.interrupts();
+        let interrupt_map = interrupts
+            .interrupt_map(root)
+            .map_err(Error::Resolve)?
+            .unwrap_or_default()
+            .iter()
+            .map(InterruptMapEntry::try_from)
+            .collect::<Result<_, _>>()?;
+        let interrupt_map_mask = interrupts
+            .interrupt_map_mask()
+            .map_err(Error::Resolve)?
+            .map(|mask| <[u32; 4]>::try_from(&*mask).map_err(|_err| Error::InterruptMap))
+            .transpose()?
+            .unwrap_or([u32::MAX; 4]);
+        Ok(Self {
+            ranges: decode_ranges(node.ranges())?,
+            dma_ranges: decode_ranges(node.dma_ranges())?,
+            interrupt_map,
+            interrupt_map_mask,
+        })
+    }
+
+    /// Returns the regions of PCI address spaces that are accessible from the parent bus, from `ranges`
+    #[must_use]
+    #[inline]
+    pub fn ranges(&self) -> &[Range] {
+        &self.ranges
+    }
+
+    /// Returns the regions of the parent bus that are accessible to DMA masters on the PCI bus, from `dma-ranges`
+    #[must_use]
+    #[inline]
+    pub fn dma_ranges(&self) -> &[Range] {
+        &self.dma_ranges
+    }
+
+    /// Returns the entries of the `interrupt-map`
+    #[must_use]
+    #[inline]
+    pub fn interrupt_map(&self) -> &[InterruptMapEntry<'node>] {
+        &self.interrupt_map
+    }
+
+    /// Translates the region of the given size at the given PCI address into the address space of the bridge's parent, through `ranges`
+    #[must_use]
+    #[inline]
+    pub fn translate(&self, address: &Address, size: u64) -> Option<property::Address> {
+        self.ranges
+            .iter()
+            .find_map(|range| range.translate(address, size))
+    }
+
+    /// Finds the `interrupt-map` entry for the given interrupt pin of the function at the given PCI address, after applying the `interrupt-map-mask`
+    #[must_use]
+    #[inline]
+    pub fn map_interrupt(&self, address: &Address, pin: Pin) -> Option<&InterruptMapEntry<'node>> {
+        let [hi, mid, lo] = address.to_cells();
+        let key = [hi, mid, lo, pin.number()];
+        self.interrupt_map.iter().find(|entry| {
+            let [entry_hi, entry_mid, entry_lo] = entry.address.to_cells();
+            [entry_hi, entry_mid, entry_lo, entry.pin.number()]
+                .iter()
+                .zip(&key)
+                .zip(&self.interrupt_map_mask)
+                .all(|((&entry, &cell), &mask)| (entry ^ cell) & mask == 0)
+        })
+    }
+}
@@ -2,13 +2,16 @@
 //!
 //! This is different from the memory reservations described in the DTB that are not part of the device tree directly
 
+use crate::rc::{Rc, Weak};
 use alloc::boxed::Box;
-use alloc::rc::Rc;
-use alloc::vec::Vec;
+use alloc::string::String;
 
-use super::{device, ChildMap, PropertyMap, RawNode, RawNodeError};
+use super::{
+    device, Ancestors, ChildMap, Locate, Parent, ParentLink, PropertyMap, RawNode, RawNodeError,
+};
 use crate::map::Map;
 use crate::node_name::NameRef;
+use crate::property::Address;
 use crate::{node::PropertyKeys, split_at_first};
 use core::{ffi::CStr, num::NonZeroU8};
 use core::{fmt, str};
@@ -99,9 +102,9 @@ pub enum Usage {
 )]
 pub enum Range {
     /// Consists of an arbitrary number of address and size pairs that specify the physical address and size of the memory ranges.
-    Static(Box<[(u64, u64)]>),
+    Static(Box<[(Address, u64)]>),
     /// Dynamic allocations may use `alignment` and `alloc-ranges` properties to constrain where the memory is allocated from.
-    Dynamic(u64, Option<u64>, Option<Box<[(u64, u64)]>>),
+    Dynamic(u64, Option<u64>, Option<Box<[(Address, u64)]>>),
 }
 
 /// Each child of the reserved-memory node specifies one or more regions of reserved memory.
@@ -110,6 +113,8 @@ pub enum Range {
 /// Following the generic-names recommended practice, node names should reflect the purpose of the node (ie. “framebuffer” or “dma-pool”). Unit address (`@<address>`) should be appended to the name if the node is a static allocation.
 #[derive(Debug)]
 pub struct Node<'node> {
+    /// The unit name of this node
+    name: NameRef<'node>,
     /// The range of memory that this reservation describes
     memory: Range,
     /// The usage permitted for this range of memory
@@ -134,25 +139,92 @@ pub enum Error {
     Cells,
     /// Error parsing the compatibility field
     Compatible,
-    /// Error parsing a child
-    Child(device::Error),
+    /// Error parsing the child node at the given offset in the structure block
+    Child(usize, device::Error),
 }
 
+/// Errors that can occur when parsing the parent `/reserved-memory` node
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum RootError {
-    Child(Error),
+    /// Error parsing the reserved memory node at the given offset in the structure block
+    Child(usize, Error),
+    /// The cells of the node do not match those of the root node, or its `ranges` is not empty
     CellsMismatch,
 }
 
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidMemory => f.write_str("neither `reg` nor `size` is present"),
+            Self::Usage => f.write_str("both `no-map` and `reusable` are present"),
+            Self::Cells => {
+                f.write_str("not a whole number of entries of `#address-cells` and `#size-cells`")
+            }
+            Self::Compatible => f.write_str("not a known string"),
+            Self::Child(_, ref child) => fmt::Display::fmt(child, f),
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for Error {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        match *self {
+            Self::Child(offset, ref child) => Some(child.node_offset().unwrap_or(offset)),
+            Self::InvalidMemory | Self::Usage | Self::Cells | Self::Compatible => None,
+        }
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::Compatible => Some(PropertyKeys::COMPATIBLE),
+            Self::Usage => Some(PropertyKeys::REUSABLE),
+            Self::InvalidMemory | Self::Cells => None,
+            Self::Child(_, ref child) => child.property(),
+        }
+    }
+}
+
+impl fmt::Display for RootError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Child(_, ref child) => fmt::Display::fmt(child, f),
+            Self::CellsMismatch => f.write_str("`#address-cells` and `#size-cells` must match the root node, and `ranges` must be empty"),
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for RootError {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        match *self {
+            Self::Child(offset, ref child) => Some(child.node_offset().unwrap_or(offset)),
+            Self::CellsMismatch => None,
+        }
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::Child(_, ref child) => child.property(),
+            Self::CellsMismatch => None,
+        }
+    }
+}
+
 impl<'node> Node<'node> {
     /// Parses the given raw node into a reserved memory node
     pub(crate) fn new(
         mut value: RawNode<'node>,
+        name: NameRef<'node>,
         address_cells: u8,
         size_cells: NonZeroU8,
         phandles: &mut Map<u32, Rc<device::Node<'node>>>,
-    ) -> Result<Self, Error> {
+    ) -> Result<Rc<Self>, Error> {
         let size = value
             .properties
             .remove(PropertyKeys::SIZE)
@@ -167,15 +239,9 @@ impl<'node> Node<'node> {
         let regs = value
             .properties
             .remove(PropertyKeys::REG)
-            .map(|mut reg| {
-                let mut regs = Vec::new();
-                while !reg.is_empty() {
-                    regs.push((
-                        reg.consume_cells(address_cells).ok_or(Error::Cells)?,
-                        reg.consume_cells(size_cells.get()).ok_or(Error::Cells)?,
-                    ));
-                }
-                Ok(regs.into_boxed_slice())
+            .map(|reg| {
+                reg.into_regs(address_cells, size_cells.get())
+                    .ok_or(Error::Cells)
             })
             .transpose()?;
 
@@ -189,16 +255,12 @@ impl<'node> Node<'node> {
         let alloc_ranges = value
             .properties
             .remove(PropertyKeys::ALLOC_RANGES)
-            .map(|mut ranges| {
-                let mut reg = Vec::new();
-                while !ranges.is_empty() {
-                    reg.push((
-                        ranges.consume_cells(address_cells).ok_or(Error::Cells)?,
-                        ranges.consume_cells(size_cells.get()).ok_or(Error::Cells)?,
-                    ));
-                }
-                reg.sort_unstable_by_key(|&(start, _)| start);
-                Ok(reg.into_boxed_slice())
+            .map(|ranges| {
+                let mut ranges = ranges
+                    .into_regs(address_cells, size_cells.get())
+                    .ok_or(Error::Cells)?;
+                ranges.sort_unstable_by_key(|&(start, _)| start);
+                Ok(ranges)
             })
             .transpose()?;
 
@@ -213,32 +275,41 @@ impl<'node> Node<'node> {
             })
             .transpose()?;
 
-        let (properties, children) = value.into_components(phandles, None);
-        let children = match children {
-            Ok(children) => children,
-            Err(RawNodeError::Cells) => return Err(Error::Cells),
-            Err(RawNodeError::Child(child)) => return Err(Error::Child(child)),
-        };
-
-        Ok(Self {
-            memory: regs.map_or_else(
-                || {
-                    size.map(|x| Range::Dynamic(x, alignment, alloc_ranges))
-                        .ok_or(Error::InvalidMemory)
-                },
-                |static_regs| Ok(Range::Static(static_regs)),
-            )?,
-            compatible,
-            usage: if no_map {
-                Usage::NoMap
-            } else if reusable {
-                Usage::Reusable
-            } else {
-                Usage::Other
+        let memory = regs.map_or_else(
+            || {
+                size.map(|x| Range::Dynamic(x, alignment, alloc_ranges))
+                    .ok_or(Error::InvalidMemory)
             },
-            properties,
-            children,
-        })
+            |static_regs| Ok(Range::Static(static_regs)),
+        )?;
+
+        let mut error = None;
+        let node = Rc::new_cyclic(|me| {
+            let (properties, children) =
+                value.into_components(phandles, &ParentLink::ReservedRegion(Weak::clone(me)));
+            Self {
+                name,
+                memory,
+                compatible,
+                usage: if no_map {
+                    Usage::NoMap
+                } else if reusable {
+                    Usage::Reusable
+                } else {
+                    Usage::Other
+                },
+                properties,
+                children: children.unwrap_or_else(|err| {
+                    error = Some(err);
+                    Map::new()
+                }),
+            }
+        });
+        match error {
+            None => Ok(node),
+            Some(RawNodeError::Cells) => Err(Error::Cells),
+            Some(RawNodeError::Child(offset, child)) => Err(Error::Child(offset, child)),
+        }
     }
 
     /// Parses the parent `/reserved-memory` node and returns all the associated reserved memory
@@ -247,7 +318,7 @@ impl<'node> Node<'node> {
         address_cells: u8,
         size_cells: NonZeroU8,
         phandles: &mut Map<u32, Rc<device::Node<'node>>>,
-    ) -> Result<Map<NameRef<'node>, Self>, RootError> {
+    ) -> Result<Map<NameRef<'node>, Rc<Self>>, RootError> {
         // #address-cells and #size-cells should use the same values as for the root node, and ranges should be empty so that address translation logic works correctly.
         let (reserved_memory_addr_cells, reserved_memory_size_cells) = parent.extract_cell_counts();
 
@@ -265,11 +336,40 @@ impl<'node> Node<'node> {
             .children
             .into_iter()
             .map(|(name, node)| {
-                Node::new(node, address_cells, size_cells, phandles)
+                let offset = node.offset;
+                Node::new(node, name, address_cells, size_cells, phandles)
                     .map(|reserved_node| (name, reserved_node))
+                    .map_err(|err| RootError::Child(offset, err))
             })
             .try_collect()
-            .map_err(RootError::Child)
+    }
+
+    /// Returns the unit name of this node
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> NameRef<'node> {
+        self.name
+    }
+
+    /// Returns the parent of this node, which is always the `/reserved-memory` node
+    #[inline]
+    #[must_use]
+    pub const fn parent(&self) -> Option<Parent<'node>> {
+        Some(Parent::ReservedMemory)
+    }
+
+    /// Returns an iterator over the ancestors of this node, from its parent up to the root node
+    #[inline]
+    #[must_use]
+    pub const fn ancestors(&self) -> Ancestors<'node> {
+        Ancestors::new(self.parent())
+    }
+
+    /// Returns the full path of this node, e.g. `/reserved-memory/framebuffer@78000000`
+    #[inline]
+    #[must_use]
+    pub fn path(&self) -> String {
+        super::path(self.name, self.ancestors())
     }
 
     #[inline]
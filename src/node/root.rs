@@ -1,9 +1,12 @@
 //! The root node of the device tree. All nodes are descendants of this.
 
 use super::chosen::{Chosen, Error};
-use super::{cache::HigherLevel, cpu, memory_region, reserved_memory, RawNode, RawNodeError};
-use super::{device, ChildMap, PropertyMap};
+use super::{
+    cache::HigherLevel, cpu, memory_region, reserved_memory, ParentLink, RawNode, RawNodeError,
+};
+use super::{device, ChildMap, Locate, PropertyMap, Warnings};
 use crate::property::{ChassisError, ChassisType};
+use crate::rc::Rc;
 use crate::{
     map::Map,
     node::{memory_region::MemoryRegion, CellError, PropertyKeys},
@@ -11,8 +14,9 @@ use crate::{
     property::Model,
 };
 use alloc::boxed::Box;
-use alloc::rc::Rc;
+use alloc::vec::Vec;
 use core::ffi::CStr;
+use core::fmt::{self, Display};
 use core::num::NonZeroU8;
 
 /// The base of the device tree that all nodes are children of
@@ -35,7 +39,7 @@ pub struct Node<'node> {
     /// The operating system shall exclude reserved memory from normal usage.
     /// One can create child nodes describing particular reserved (excluded from normal use) memory regions.
     /// Such memory regions are usually designed for the special usage by various device drivers.
-    reserved_memory: Option<Map<NameRef<'node>, reserved_memory::Node<'node>>>,
+    reserved_memory: Option<Map<NameRef<'node>, Rc<reserved_memory::Node<'node>>>>,
     /// A memory device node is required for all devicetrees and describes the physical memory layout for the system.
     /// If a system has multiple ranges of memory, multiple memory nodes can be created, or the ranges can be specified in the reg property of a single memory node.
     ///
@@ -109,6 +113,15 @@ impl<'node> Node<'node> {
         &self.memory
     }
 
+    /// Returns the children of the `/reserved-memory` node, if present
+    #[must_use]
+    #[inline]
+    pub const fn reserved_memory(
+        &self,
+    ) -> Option<&Map<NameRef<'node>, Rc<reserved_memory::Node<'node>>>> {
+        self.reserved_memory.as_ref()
+    }
+
     #[must_use]
     #[inline]
     pub const fn phandles(&self) -> &Map<u32, Rc<device::Node<'node>>> {
@@ -120,6 +133,206 @@ impl<'node> Node<'node> {
     pub const fn chosen(&self) -> Option<&Chosen<'node>> {
         self.chosen.as_ref()
     }
+
+    /// Parses the root node of the device tree, along with all of its descendants.
+    ///
+    /// Errors tolerated by `warnings` are recorded instead of failing the parse:
+    /// invalid properties of the root node are ignored, CPU nodes may lack a `device_type`, dangling aliases are skipped,
+    /// and any memory, reserved memory, or chosen node that fails to parse is instead parsed as a generic device node
+    #[expect(
+        clippy::too_many_lines,
+        reason = "Each special child is parsed in sequence"
+    )]
+    pub(crate) fn parse(
+        mut value: RawNode<'node>,
+        warnings: &mut Warnings<'_, NodeError<'node>>,
+    ) -> Result<Self, NodeError<'node>> {
+        let mut phandles = Map::new();
+        let model = value
+            .properties
+            .remove(PropertyKeys::MODEL)
+            .and_then(|bytes| <&CStr>::try_from(bytes).ok())
+            .map(Model::from);
+        if model.is_none() {
+            warnings.tolerate(NodeError::Model)?;
+        }
+
+        let compatible = value
+            .properties
+            .remove(&PropertyKeys::COMPATIBLE)
+            .and_then(|compatible| compatible.try_into().ok());
+        if compatible.is_none() {
+            warnings.tolerate(NodeError::Compatible)?;
+        }
+        let compatible: Box<[Model<'node>]> = compatible.unwrap_or_default();
+        // Without a model, the most specific compatible platform is the best description of the board
+        let model = model
+            .or_else(|| compatible.first().copied())
+            .unwrap_or(Model::Other(&[]));
+
+        let serial_number = match value
+            .properties
+            .remove(&PropertyKeys::SERIAL_NUMBER)
+            .map(<&CStr>::try_from)
+        {
+            Some(Ok(serial_number)) => Some(serial_number),
+            Some(Err(_)) => {
+                warnings.tolerate(NodeError::SerialNumber)?;
+                None
+            }
+            None => None,
+        };
+
+        let (address_cells, size_cells) = value.extract_cell_counts();
+        let (address_cells, size_cells) = (
+            address_cells.map_err(NodeError::Cells)?,
+            size_cells.map_err(NodeError::Cells)?,
+        );
+        let size_cells = NonZeroU8::new(size_cells).ok_or(NodeError::Cells(CellError::Invalid))?;
+
+        let cpus_node = value
+            .children
+            .remove(&NodeNames::cpus())
+            .ok_or(NodeError::CpuRoot)?;
+        let cpus_offset = cpus_node.offset;
+        let (cpus, caches) = warnings
+            .nest(
+                |err| NodeError::Cpu(cpus_offset, err),
+                |warnings| cpu::Node::parse_parent(cpus_node, &mut phandles, warnings),
+            )
+            .map_err(|err| NodeError::Cpu(cpus_offset, err))?;
+
+        let reserved_memory = match value
+            .children
+            .remove(&NodeNames::reserved_memory())
+            .map(|reserved_root| {
+                parse_or_fall_back(
+                    reserved_root,
+                    &mut phandles,
+                    warnings,
+                    NodeError::ReservedMemory,
+                    |reserved_root, phandles| {
+                        reserved_memory::Node::parse_parent(
+                            reserved_root,
+                            address_cells,
+                            size_cells,
+                            phandles,
+                        )
+                    },
+                )
+            })
+            .transpose()?
+        {
+            Some(Ok(reserved_memory)) => Some(reserved_memory),
+            Some(Err(reserved_root)) => {
+                value
+                    .children
+                    .insert(NodeNames::reserved_memory(), reserved_root);
+                None
+            }
+            None => None,
+        };
+
+        let mut generic_memory = Vec::new();
+        let memory = value
+            .children
+            .extract_if(|name, _| name.node_name() == NodeNames::memory())
+            .filter_map(|(name, memory_node)| {
+                match parse_or_fall_back(
+                    memory_node,
+                    &mut phandles,
+                    warnings,
+                    NodeError::Memory,
+                    |memory_node, _| {
+                        MemoryRegion::new(memory_node, &name, address_cells, size_cells.get())
+                    },
+                ) {
+                    Ok(Ok(region)) => Some(Ok(region)),
+                    Ok(Err(memory_node)) => {
+                        generic_memory.push((name, memory_node));
+                        None
+                    }
+                    Err(err) => Some(Err(err)),
+                }
+            })
+            .try_collect()?;
+        value.children.extend(generic_memory);
+
+        let chassis = match value
+            .properties
+            .remove(PropertyKeys::CHASSIS)
+            .map(ChassisType::try_from)
+        {
+            Some(Ok(chassis)) => Some(chassis),
+            Some(Err(err)) => {
+                warnings.tolerate(NodeError::Chassis(err))?;
+                None
+            }
+            None => None,
+        };
+
+        let aliases_node = value.children.remove(&NodeNames::aliases());
+        #[cfg(feature = "rpi")]
+        let symbols_node = value.children.remove(&NodeNames::symbols());
+
+        let chosen_node = value.children.remove(&NodeNames::chosen());
+
+        let (properties, children) = value.into_components_from_cells(
+            Some(address_cells),
+            Some(size_cells.get()),
+            &mut phandles,
+            &ParentLink::Root,
+        );
+        let children: Map<NameRef<'node>, Rc<device::Node<'node>>> = match children {
+            Ok(children) => children,
+            Err(RawNodeError::Cells) => return Err(NodeError::Cells(CellError::Invalid)),
+            Err(RawNodeError::Child(offset, child)) => return Err(NodeError::Child(offset, child)),
+        };
+
+        let mut root = Self {
+            phandles,
+            aliases: Map::default(),
+            model,
+            compatible,
+            serial_number,
+            chassis,
+            cpus,
+            memory,
+            reserved_memory,
+            higher_caches: caches,
+            properties,
+            children,
+            chosen: Option::default(),
+        };
+
+        root.aliases = parse_aliases(aliases_node, &root, warnings)?;
+        #[cfg(feature = "rpi")]
+        root.aliases
+            .extend(parse_aliases(symbols_node, &root, warnings)?);
+
+        if let Some(chosen) = chosen_node {
+            let offset = chosen.offset;
+            let fallback = warnings.is_lenient().then(|| chosen.clone());
+            match Chosen::from_node(chosen, &root) {
+                Ok(parsed) => root.chosen = Some(parsed),
+                Err(err) => {
+                    warnings.tolerate(NodeError::Chosen(offset, err))?;
+                    let generic = device::Node::new(
+                        fallback.expect("A fallback should be kept whenever errors are tolerated"),
+                        NodeNames::chosen(),
+                        Some(address_cells),
+                        Some(size_cells.get()),
+                        &mut root.phandles,
+                        &ParentLink::Root,
+                    )
+                    .map_err(|child_err| NodeError::Child(offset, child_err))?;
+                    root.children.insert(NodeNames::chosen(), generic);
+                }
+            }
+        }
+
+        Ok(root)
+    }
 }
 
 /// Errors from parsing a root node
@@ -134,8 +347,8 @@ pub enum NodeError<'node> {
     SerialNumber,
     /// The parent node for CPU nodes is missing
     CpuRoot,
-    /// Parsing CPUs failed
-    Cpu(cpu::RootError),
+    /// Parsing the parent node for CPU nodes, at the given offset in the structure block, failed
+    Cpu(usize, cpu::RootError),
     /// Matching a reg field to the unit name failed
     RegMismatch(Option<u64>, u64),
     /// A cache node is invalid
@@ -144,16 +357,97 @@ pub enum NodeError<'node> {
     Cells(CellError),
     /// The parent node for reserved memory is invalid
     ReservedMemoryRoot,
-    /// A reserved memory node is invalid
-    ReservedMemory(reserved_memory::RootError),
-    /// A memory region is invalid
-    Memory(memory_region::Error),
+    /// The parent node for reserved memory, at the given offset in the structure block, is invalid
+    ReservedMemory(usize, reserved_memory::RootError),
+    /// The memory region at the given offset in the structure block is invalid
+    Memory(usize, memory_region::Error),
     /// The type of a node was invalid
     Type,
-    Child(device::Error),
+    /// The child node at the given offset in the structure block is invalid
+    Child(usize, device::Error),
+    /// The chassis type is invalid
     Chassis(ChassisError<'node>),
-    Alias,
-    Chosen(Error<'node>),
+    /// An alias of the aliases node at the given offset in the structure block refers to a path that does not exist, as the name of the alias
+    Alias(usize, &'node CStr),
+    /// The chosen node at the given offset in the structure block is invalid
+    Chosen(usize, Error<'node>),
+}
+
+impl Display for NodeError<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Model => f.write_str("missing or not a string"),
+            Self::Compatible => f.write_str("missing or not a list of strings"),
+            Self::SerialNumber => f.write_str("not a string"),
+            Self::CpuRoot => f.write_str("the `/cpus` node is missing"),
+            Self::Cpu(_, ref error) => Display::fmt(error, f),
+            Self::RegMismatch(Some(address), reg) => {
+                write!(f, "unit address {address:#X} does not match `reg` {reg:#X}")
+            }
+            Self::RegMismatch(None, reg) => write!(f, "missing unit address for `reg` {reg:#X}"),
+            Self::Cache => f.write_str("invalid cache node"),
+            Self::Cells(ref error) => Display::fmt(error, f),
+            Self::ReservedMemoryRoot => f.write_str("invalid `/reserved-memory` node"),
+            Self::ReservedMemory(_, ref error) => Display::fmt(error, f),
+            Self::Memory(_, ref error) => Display::fmt(error, f),
+            Self::Type => f.write_str("invalid node type"),
+            Self::Child(_, ref error) => Display::fmt(error, f),
+            Self::Chassis(ref error) => Display::fmt(error, f),
+            Self::Alias(_, alias) => write!(
+                f,
+                "alias `{}` refers to a node that does not exist",
+                alias.to_string_lossy()
+            ),
+            Self::Chosen(_, ref error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl<'dtb> Locate<'dtb> for NodeError<'dtb> {
+    #[inline]
+    fn node_offset(&self) -> Option<usize> {
+        match *self {
+            Self::Cpu(offset, ref error) => Some(error.node_offset().unwrap_or(offset)),
+            Self::ReservedMemory(offset, ref error) => Some(error.node_offset().unwrap_or(offset)),
+            Self::Memory(offset, ref error) => Some(error.node_offset().unwrap_or(offset)),
+            Self::Child(offset, ref error) => Some(error.node_offset().unwrap_or(offset)),
+            Self::Chosen(offset, ref error) => Some(error.node_offset().unwrap_or(offset)),
+            Self::Alias(offset, _) => Some(offset),
+            Self::Model
+            | Self::Compatible
+            | Self::SerialNumber
+            | Self::CpuRoot
+            | Self::RegMismatch(..)
+            | Self::Cache
+            | Self::Cells(_)
+            | Self::ReservedMemoryRoot
+            | Self::Type
+            | Self::Chassis(_) => None,
+        }
+    }
+
+    #[inline]
+    fn property(&self) -> Option<&'dtb CStr> {
+        match *self {
+            Self::Model => Some(PropertyKeys::MODEL),
+            Self::Compatible => Some(PropertyKeys::COMPATIBLE),
+            Self::SerialNumber => Some(PropertyKeys::SERIAL_NUMBER),
+            Self::Chassis(_) => Some(PropertyKeys::CHASSIS),
+            Self::Alias(_, alias) => Some(alias),
+            Self::Cpu(_, ref error) => error.property(),
+            Self::ReservedMemory(_, ref error) => error.property(),
+            Self::Memory(_, ref error) => error.property(),
+            Self::Child(_, ref error) => error.property(),
+            Self::Chosen(_, ref error) => error.property(),
+            Self::CpuRoot
+            | Self::RegMismatch(..)
+            | Self::Cache
+            | Self::Cells(_)
+            | Self::ReservedMemoryRoot
+            | Self::Type => None,
+        }
+    }
 }
 
 /// "Constants" for various node names
@@ -161,7 +455,7 @@ pub(super) struct NodeNames;
 
 impl NodeNames {
     /// The node name for the CPUs parent node
-    fn cpus() -> NameRef<'static> {
+    pub(super) fn cpus() -> NameRef<'static> {
         NameRef::try_from(b"cpus".as_slice()).expect("Should be a valid name")
     }
 
@@ -205,39 +499,101 @@ impl NodeNames {
     }
 }
 
-/// Parses the root `/aliases` node and returns a map that converts a name into a reference to the resolved node
+/// Parses the root `/aliases` node and returns a map that converts a name into a reference to the resolved node.
+/// Aliases to nodes that are not device nodes, such as CPUs or reserved memory regions, are left out of the map
+///
+/// # Errors
+/// Returns an error if an alias refers to a path that does not exist, unless such aliases are tolerated by `warnings`
 fn parse_aliases<'data, 'root>(
     aliases_node: Option<RawNode<'data>>,
     root: &'root Node<'data>,
-) -> Map<NameRef<'data>, Rc<device::Node<'data>>>
+    warnings: &mut Warnings<'_, NodeError<'data>>,
+) -> Result<Map<NameRef<'data>, Rc<device::Node<'data>>>, NodeError<'data>>
 where
     'data: 'root,
 {
-    aliases_node.map_or_else(Map::new, |aliases| {
-        aliases
-            .properties
-            .into_iter()
-            .filter_map(|(name, path)| {
-                NameRef::try_from(name.to_bytes()).ok().zip(
-                    CStr::from_bytes_until_nul(path.into())
-                        .ok()
-                        .and_then(|c_path| {
-                            use super::Node;
-                            let entry: Option<Rc<device::Node<'data>>> =
-                                root.find_str(c_path.to_bytes());
-                            if entry.is_none() {
-                                panic!(
-                                    "WARNING: Could not match {} to {}",
-                                    name.to_string_lossy(),
-                                    c_path.to_string_lossy()
-                                );
-                            }
-                            entry
-                        }),
-                )
-            })
-            .collect()
-    })
+    use super::Node as _;
+    aliases_node.map_or_else(
+        || Ok(Map::new()),
+        |aliases| {
+            aliases
+                .properties
+                .into_iter()
+                .filter_map(|(name, path)| {
+                    NameRef::try_from(name.to_bytes()).ok().zip(
+                        CStr::from_bytes_until_nul(path.into())
+                            .ok()
+                            // Nodes such as CPUs and reserved memory regions are not device nodes, so aliases to them are skipped rather than resolved
+                            .filter(|c_path| !is_special_node(root, c_path.to_bytes()))
+                            .map(|c_path| {
+                                root.find_str(c_path.to_bytes())
+                                    .ok_or(NodeError::Alias(aliases.offset, name))
+                            }),
+                    )
+                })
+                .filter_map(|(alias, entry)| match entry {
+                    Ok(node) => Some(Ok((alias, node))),
+                    Err(err) => warnings.tolerate(err).err().map(Err),
+                })
+                .try_collect()
+        },
+    )
+}
+
+/// Returns whether the given path refers to a node that the root parses specially rather than as a device node,
+/// such as a CPU, a memory node, or a reserved memory region
+fn is_special_node(root: &Node<'_>, path: &[u8]) -> bool {
+    let Some(names) = path
+        .split(|&char| char == b'/')
+        .filter(|name| !name.is_empty())
+        .map(|name| NameRef::try_from(name).ok())
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+    match *names.as_slice() {
+        [first, ..] if first == NodeNames::cpus() || first == NodeNames::chosen() => true,
+        [first, ..] if first.node_name() == NodeNames::memory() => {
+            root.children.get(&first).is_none()
+        }
+        [first] if first == NodeNames::reserved_memory() => root.reserved_memory.is_some(),
+        // Nodes beneath a reserved memory region are device nodes, and are resolved as usual
+        [first, region] if first == NodeNames::reserved_memory() => root
+            .reserved_memory
+            .as_ref()
+            .is_some_and(|regions| regions.get(&region).is_some()),
+        _ => false,
+    }
+}
+
+/// A map of phandles to the nodes they refer to
+type PHandleMap<'node> = Map<u32, Rc<device::Node<'node>>>;
+
+/// Parses a specialized child of the root node with the given parser.
+///
+/// If the node fails to parse but errors are tolerated by `warnings`, the node is returned unparsed so that it can instead be parsed as a generic device node,
+/// and any phandles registered by the failed parse are discarded
+fn parse_or_fall_back<'node, T, E>(
+    node: RawNode<'node>,
+    phandles: &mut PHandleMap<'node>,
+    warnings: &mut Warnings<'_, NodeError<'node>>,
+    wrap: impl FnOnce(usize, E) -> NodeError<'node>,
+    parse: impl FnOnce(RawNode<'node>, &mut PHandleMap<'node>) -> Result<T, E>,
+) -> Result<Result<T, RawNode<'node>>, NodeError<'node>> {
+    let offset = node.offset;
+    let fallback = warnings
+        .is_lenient()
+        .then(|| (node.clone(), phandles.clone()));
+    match parse(node, phandles) {
+        Ok(parsed) => Ok(Ok(parsed)),
+        Err(err) => {
+            warnings.tolerate(wrap(offset, err))?;
+            let (unparsed, saved_phandles) =
+                fallback.expect("A fallback should be kept whenever errors are tolerated");
+            *phandles = saved_phandles;
+            Ok(Err(unparsed))
+        }
+    }
 }
 
 impl<'data> super::Node<'data> for Node<'data> {
@@ -266,19 +622,16 @@ impl<'data> super::Node<'data> for Node<'data> {
                 node.find(grandchild_name, rest_path)
             });
         }
-        let entry = if direct_child_name == NodeNames::reserved_memory() {
-            let reserved_memory = self.reserved_memory.as_ref()?;
-            grandchild_name_opt
-                .and_then(|grandchild_name| {
-                    reserved_memory.get(&grandchild_name).and_then(|grandchild| {
-                        rest_path.next().map_or_else(|| {
-                            unimplemented!("WARNING: References to non-plain device nodes are not currently supported: /{direct_child_name}/{grandchild_name}");
-                            None
-                        }, |great_grandchild_name| {
-                            grandchild.find(great_grandchild_name, rest_path)
-                        })
-                    })
-                })
+        // If the reserved memory failed to parse, it is instead a generic child node
+        let entry = if let Some(reserved_memory) = self
+            .reserved_memory
+            .as_ref()
+            .filter(|_| direct_child_name == NodeNames::reserved_memory())
+        {
+            let grandchild = reserved_memory.get(&grandchild_name_opt?)?;
+            // References to the reserved memory nodes themselves are not currently supported, as they are not plain device nodes
+            let great_grandchild_name = rest_path.next()?;
+            grandchild.find(great_grandchild_name, rest_path)
         } else {
             self.children
                 .get(&direct_child_name)
@@ -296,113 +649,7 @@ impl<'node> TryFrom<RawNode<'node>> for Node<'node> {
     type Error = NodeError<'node>;
 
     #[inline]
-    #[expect(clippy::too_many_lines)]
-    fn try_from(mut value: RawNode<'node>) -> Result<Self, Self::Error> {
-        let mut phandles = Map::new();
-        let model = value
-            .properties
-            .remove(PropertyKeys::MODEL)
-            .and_then(|bytes| <&CStr>::try_from(bytes).ok())
-            .map(Model::from)
-            .ok_or(NodeError::Model)?;
-
-        let compatible = value
-            .properties
-            .remove(&PropertyKeys::COMPATIBLE)
-            .and_then(|compatible| compatible.try_into().ok())
-            .ok_or(NodeError::Model)?;
-
-        let serial_number = value
-            .properties
-            .remove(&PropertyKeys::SERIAL_NUMBER)
-            .map(|serial_number| {
-                <&CStr>::try_from(serial_number).map_err(|_err| NodeError::SerialNumber)
-            })
-            .transpose()?;
-
-        let (address_cells, size_cells) = value.extract_cell_counts();
-        let (address_cells, size_cells) = (
-            address_cells.map_err(NodeError::Cells)?,
-            size_cells.map_err(NodeError::Cells)?,
-        );
-        let size_cells = NonZeroU8::new(size_cells).ok_or(NodeError::Cells(CellError::Invalid))?;
-
-        let (cpus, caches) = cpu::Node::parse_parent(
-            value
-                .children
-                .remove(&NodeNames::cpus())
-                .ok_or(NodeError::CpuRoot)?,
-            &mut phandles,
-        )
-        .map_err(NodeError::Cpu)?;
-
-        let reserved_memory = value
-            .children
-            .remove(&NodeNames::reserved_memory())
-            .map(|reserved_root| {
-                reserved_memory::Node::parse_parent(
-                    reserved_root,
-                    address_cells,
-                    size_cells,
-                    &mut phandles,
-                )
-                .map_err(NodeError::ReservedMemory)
-            })
-            .transpose()?;
-
-        let memory = value
-            .children
-            .extract_if(|name, _| name.node_name() == NodeNames::memory())
-            .map(|(name, memory_node)| {
-                MemoryRegion::new(memory_node, &name, address_cells, size_cells.get())
-                    .map_err(NodeError::Memory)
-            })
-            .try_collect()?;
-
-        let chassis = value
-            .properties
-            .remove(PropertyKeys::CHASSIS)
-            .map(ChassisType::try_from)
-            .transpose()
-            .map_err(NodeError::Chassis)?;
-
-        let aliases_node = value.children.remove(&NodeNames::aliases());
-        #[cfg(feature = "rpi")]
-        let symbols_node = value.children.remove(&NodeNames::symbols());
-
-        let chosen_node = value.children.remove(&NodeNames::chosen());
-
-        let (properties, children) = value.into_components(&mut phandles, None);
-        let children: Map<NameRef<'node>, Rc<device::Node<'node>>> = match children {
-            Ok(children) => children,
-            Err(RawNodeError::Cells) => return Err(NodeError::Cells(CellError::Invalid)),
-            Err(RawNodeError::Child(child)) => return Err(NodeError::Child(child)),
-        };
-
-        let mut root = Self {
-            phandles,
-            aliases: Map::default(),
-            model,
-            compatible,
-            serial_number,
-            chassis,
-            cpus,
-            memory,
-            reserved_memory,
-            higher_caches: caches,
-            properties,
-            children,
-            chosen: Option::default(),
-        };
-
-        root.aliases = parse_aliases(aliases_node, &root);
-        #[cfg(feature = "rpi")]
-        root.aliases.extend(parse_aliases(symbols_node, &root));
-
-        root.chosen = chosen_node
-            .map(|chosen| Chosen::from_node(chosen, &root).map_err(NodeError::Chosen))
-            .transpose()?;
-
-        Ok(root)
+    fn try_from(value: RawNode<'node>) -> Result<Self, Self::Error> {
+        Self::parse(value, &mut Warnings::strict())
     }
 }
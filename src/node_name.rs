@@ -13,7 +13,6 @@ use core::str;
 
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
-use alloc::string::String;
 
 /// A valid character for a node name.
 ///
@@ -92,7 +91,7 @@ pub enum Char {
 
 impl Char {
     /// Returns whether or not a given `u8` byte is a valid `Char`
-    const fn is_valid(byte: u8) -> bool {
+    pub(crate) const fn is_valid(byte: u8) -> bool {
         matches!(byte, b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z' | b',' | b'.' | b'_' | b'+' | b'-')
     }
 }
@@ -198,12 +197,14 @@ impl ToOwned for NameSlice {
 }
 
 /// Represents a node's name via borrowing
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct NameRef<'bytes> {
     /// The node-name component of the name
     node_name: &'bytes NameSlice,
-    /// The unit-address component of the name
+    /// The unit-address component of the name, or its first part if it has several comma-separated parts
     unit_address: Option<u64>,
+    /// The parts of the unit-address component after the first comma, such as `0` in `pci@1,0`, if there are any
+    unit_address_rest: Option<&'bytes NameSlice>,
 }
 
 impl NameRef<'_> {
@@ -212,15 +213,21 @@ impl NameRef<'_> {
         self.node_name
     }
 
-    /// Returns the unit-address component of this name, if it exists
+    /// Returns the unit-address component of this name, if it exists.
+    /// If the unit-address has several comma-separated parts, such as `1,0` for PCI devices, this is the first part
     pub const fn unit_address(&self) -> Option<u64> {
         self.unit_address
     }
+
+    /// Returns the parts of the unit-address component after the first comma, such as `0` in `pci@1,0`, if there are any
+    pub const fn unit_address_rest(&self) -> Option<&NameSlice> {
+        self.unit_address_rest
+    }
 }
 
 impl NameRef<'_> {
     /// The node-name component of a name must be 1-31 characters long
-    const MAX_NODE_NAME_LENGTH: usize = 31;
+    pub(crate) const MAX_NODE_NAME_LENGTH: usize = 31;
 }
 
 /// Errors that can occur while parsing a slice of bytes into a `NameRef`
@@ -232,6 +239,20 @@ pub enum NameRefError {
     TooLong,
 }
 
+impl Display for NameRefError {
+    #[inline]
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidCharacters => formatter.write_str("node name contains invalid characters"),
+            Self::TooLong => write!(
+                formatter,
+                "node name is longer than {} characters",
+                NameRef::MAX_NODE_NAME_LENGTH
+            ),
+        }
+    }
+}
+
 impl<'bytes> TryFrom<&'bytes [u8]> for NameRef<'bytes> {
     type Error = NameRefError;
 
@@ -247,37 +268,34 @@ impl<'bytes> TryFrom<&'bytes [u8]> for NameRef<'bytes> {
                             .map(|node_name| Self {
                                 node_name,
                                 unit_address: None,
+                                unit_address_rest: None,
                             })
                     })
                     .unwrap_or(Err(NameRefError::TooLong))
             },
             |(node_name, unit_address)| {
-                let mut address_parts = unit_address.split(|&char| char == b',');
-                let address = address_parts
-                    .next()
-                    .expect("Split iterator should always have at least one entry");
-                if address_parts.next().is_some() {
-                    panic!(
-                        "WARNING: unhandled comma in unit address: {}@{}",
-                        String::from_utf8_lossy(node_name),
-                        String::from_utf8_lossy(unit_address)
-                    );
-                }
+                let (unit_address, rest) = unit_address
+                    .split_once(|&char| char == b',')
+                    .map_or((unit_address, None), |(first, rest)| (first, Some(rest)));
                 (node_name.len() <= Self::MAX_NODE_NAME_LENGTH)
                     .then(|| {
                         node_name
                             .try_into()
                             .ok()
                             .zip(
-                                address
+                                unit_address
                                     .as_ascii()
                                     .and_then(|x| u64::from_str_radix(x.as_str(), 16).ok()),
                             )
+                            .zip(rest.map(<&NameSlice>::try_from).transpose().ok())
                             .ok_or(NameRefError::InvalidCharacters)
-                            .map(|(parsed_node_name, parsed_unit_address)| Self {
-                                node_name: parsed_node_name,
-                                unit_address: Some(parsed_unit_address),
-                            })
+                            .map(
+                                |((parsed_node_name, parsed_unit_address), parsed_rest)| Self {
+                                    node_name: parsed_node_name,
+                                    unit_address: Some(parsed_unit_address),
+                                    unit_address_rest: parsed_rest,
+                                },
+                            )
                     })
                     .unwrap_or(Err(NameRefError::TooLong))
             },
@@ -321,7 +339,11 @@ impl Debug for NameRef<'_> {
 impl Display for NameRef<'_> {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
         if let Some(unit_address) = self.unit_address {
-            write!(formatter, "{}@{:x}", self.node_name, unit_address)
+            write!(formatter, "{}@{:x}", self.node_name, unit_address)?;
+            if let Some(rest) = self.unit_address_rest {
+                write!(formatter, ",{rest}")?;
+            }
+            Ok(())
         } else {
             write!(formatter, "{}", self.node_name)
         }
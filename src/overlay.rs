@@ -0,0 +1,327 @@
+//! Application of device tree overlays (DTBOs) onto a base tree
+//!
+//! An overlay is a device tree whose top-level `fragment@N` nodes each name a `target` (by phandle) or `target-path` in the base tree,
+//! and whose `__overlay__` child holds the properties and nodes to merge into that target.
+//! References from the overlay to labels in the base tree are listed in `__fixups__` and resolved through the base tree's `__symbols__`,
+//! while references within the overlay itself are listed in `__local_fixups__` and renumbered so that the overlay's phandles do not collide with the base tree's.
+
+use crate::edit::{self, Node, Tree};
+use crate::node::PropertyKeys;
+use crate::parse::to_c_str;
+use alloc::{boxed::Box, ffi::CString, vec::Vec};
+use core::ffi::CStr;
+use core::{mem, str};
+
+/// Errors from applying an overlay
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The overlay references labels, but the base tree has no `__symbols__` node
+    NoSymbols,
+    /// A label referenced by the overlay is not present in the base tree's `__symbols__`
+    UnresolvedLabel(CString),
+    /// A symbol in the base tree refers to a path that does not exist
+    DanglingSymbol(CString),
+    /// An entry in `__fixups__` is not of the form `path:property:offset`, or refers to a nonexistent location
+    Fixup(CString),
+    /// An entry in `__local_fixups__` refers to a nonexistent location, as the name of the missing property or node
+    LocalFixup(CString),
+    /// A fragment has neither a valid `target` nor `target-path`
+    Target(Box<[u8]>),
+    /// Renumbering the overlay's phandles ran out of phandle values
+    PHandleOverflow,
+    /// Merging the overlay into the base tree failed
+    Edit(edit::Error),
+}
+
+/// Names of the special nodes and properties of overlays
+struct OverlayKeys;
+
+impl OverlayKeys {
+    /// The child of a fragment holding the contents to merge
+    const OVERLAY: &'static [u8] = b"__overlay__";
+    /// The node listing references to labels in the base tree
+    const FIXUPS: &'static [u8] = b"__fixups__";
+    /// The node listing references to phandles within the overlay
+    const LOCAL_FIXUPS: &'static [u8] = b"__local_fixups__";
+    /// The node mapping labels to paths
+    const SYMBOLS: &'static [u8] = b"__symbols__";
+    /// The phandle of the target node of a fragment
+    const TARGET: &'static CStr = to_c_str(b"target\0");
+    /// The path of the target node of a fragment
+    const TARGET_PATH: &'static CStr = to_c_str(b"target-path\0");
+    /// The legacy name of the phandle property
+    const LINUX_PHANDLE: &'static CStr = to_c_str(b"linux,phandle\0");
+    /// The node mapping aliases to paths
+    const ALIASES: &'static [u8] = b"aliases";
+}
+
+/// Returns the largest phandle used by the given node or any of its descendants
+fn max_phandle(node: &Node) -> u32 {
+    node.children().iter().map(max_phandle).fold(
+        node.property_u32(PropertyKeys::PHANDLE)
+            .max(node.property_u32(OverlayKeys::LINUX_PHANDLE))
+            .unwrap_or(0),
+        u32::max,
+    )
+}
+
+/// Returns the phandle of the given node, from either its `phandle` or its legacy `linux,phandle` property
+fn phandle(node: &Node) -> Option<u32> {
+    node.property_u32(PropertyKeys::PHANDLE)
+        .or_else(|| node.property_u32(OverlayKeys::LINUX_PHANDLE))
+}
+
+/// Returns the path of the node with the given phandle, relative to the given node
+fn find_phandle(node: &Node, phandle: u32) -> Option<Vec<u8>> {
+    if node.property_u32(PropertyKeys::PHANDLE) == Some(phandle)
+        || node.property_u32(OverlayKeys::LINUX_PHANDLE) == Some(phandle)
+    {
+        return Some(Vec::new());
+    }
+    node.children().iter().find_map(|child| {
+        find_phandle(child, phandle).map(|rest| {
+            let mut path = Vec::with_capacity(
+                rest.len()
+                    .saturating_add(child.name().len())
+                    .saturating_add(1),
+            );
+            path.push(b'/');
+            path.extend_from_slice(child.name());
+            path.extend_from_slice(&rest);
+            path
+        })
+    })
+}
+
+/// Adds `delta` to the big-endian `u32` at the given byte offset of a property, returning `None` if the offset is out of bounds
+fn add_to_cell(node: &mut Node, property: &CStr, offset: usize, delta: u32) -> Option<()> {
+    let mut value = Box::<[u8]>::from(node.property(property)?);
+    let cell = value.get_mut(offset..offset.checked_add(mem::size_of::<u32>())?)?;
+    let adjusted = u32::from_be_bytes(<[u8; 4]>::try_from(&*cell).ok()?).wrapping_add(delta);
+    cell.copy_from_slice(&adjusted.to_be_bytes());
+    node.set_property(property, value);
+    Some(())
+}
+
+/// Writes a big-endian `u32` at the given byte offset of a property, returning `None` if the offset is out of bounds
+fn write_cell(node: &mut Node, property: &CStr, offset: usize, cell: u32) -> Option<()> {
+    let mut value = Box::<[u8]>::from(node.property(property)?);
+    value
+        .get_mut(offset..offset.checked_add(mem::size_of::<u32>())?)?
+        .copy_from_slice(&cell.to_be_bytes());
+    node.set_property(property, value);
+    Some(())
+}
+
+/// Adds `delta` to every phandle defined in the given node and its descendants
+fn adjust_phandles(node: &mut Node, delta: u32) -> Result<(), Error> {
+    for key in [PropertyKeys::PHANDLE, OverlayKeys::LINUX_PHANDLE] {
+        if let Some(phandle) = node.property_u32(key) {
+            node.set_property_u32(
+                key,
+                phandle.checked_add(delta).ok_or(Error::PHandleOverflow)?,
+            );
+        }
+    }
+    node.children_mut()
+        .iter_mut()
+        .try_for_each(|child| adjust_phandles(child, delta))
+}
+
+/// Adds `delta` to every phandle reference listed in the local fixups node, which mirrors the structure of the overlay node
+fn adjust_local_references(node: &mut Node, fixups: &Node, delta: u32) -> Result<(), Error> {
+    for (property, offsets) in fixups.properties() {
+        for offset in offsets.array_chunks::<4>() {
+            let offset = usize::try_from(u32::from_be_bytes(*offset))
+                .map_err(|_err| Error::LocalFixup(CString::from(property)))?;
+            add_to_cell(node, property, offset, delta)
+                .ok_or_else(|| Error::LocalFixup(CString::from(property)))?;
+        }
+    }
+    for fixup_child in fixups.children() {
+        let child = node.child_mut(fixup_child.name()).ok_or_else(|| {
+            Error::LocalFixup(
+                CString::new(fixup_child.name()).expect("Node names should not contain nul bytes"),
+            )
+        })?;
+        adjust_local_references(child, fixup_child, delta)?;
+    }
+    Ok(())
+}
+
+/// Resolves the references to labels of the base tree listed in the overlay's `__fixups__` node
+fn resolve_fixups(
+    base: &mut Tree,
+    overlay: &mut Tree,
+    next_phandle: &mut u32,
+) -> Result<(), Error> {
+    let Some(fixups) = overlay.root_mut().remove_child(OverlayKeys::FIXUPS) else {
+        return Ok(());
+    };
+    for (label, locations) in fixups.properties() {
+        let target_path = base
+            .root()
+            .child(OverlayKeys::SYMBOLS)
+            .ok_or(Error::NoSymbols)?
+            .property(label)
+            .and_then(|path| CStr::from_bytes_until_nul(path).ok())
+            .map(CString::from)
+            .ok_or_else(|| Error::UnresolvedLabel(CString::from(label)))?;
+        let target = base
+            .find_mut(target_path.to_bytes())
+            .ok_or(Error::DanglingSymbol(target_path))?;
+
+        let phandle = if let Some(phandle) = phandle(target) {
+            phandle
+        } else {
+            let phandle = *next_phandle;
+            *next_phandle = phandle.checked_add(1).ok_or(Error::PHandleOverflow)?;
+            target.set_property_u32(PropertyKeys::PHANDLE, phandle);
+            phandle
+        };
+
+        for location in locations
+            .split(|&byte| byte == 0)
+            .filter(|location| !location.is_empty())
+        {
+            let fixup_error = || Error::Fixup(CString::from(label));
+            let mut parts = location.rsplitn(3, |&byte| byte == b':');
+            let (Some(offset), Some(property), Some(path)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                return Err(fixup_error());
+            };
+            let offset = str::from_utf8(offset)
+                .ok()
+                .and_then(|offset| offset.parse::<usize>().ok())
+                .ok_or_else(fixup_error)?;
+            let property = CString::new(property).map_err(|_err| fixup_error())?;
+            let node = overlay.find_mut(path).ok_or_else(fixup_error)?;
+            write_cell(node, &property, offset, phandle).ok_or_else(fixup_error)?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the target of a fragment into a path in the base tree
+fn fragment_target(base: &Tree, fragment: &Node) -> Option<Vec<u8>> {
+    if let Some(phandle) = fragment.property_u32(OverlayKeys::TARGET) {
+        return find_phandle(base.root(), phandle).map(|path| {
+            if path.is_empty() {
+                Vec::from(b"/".as_slice())
+            } else {
+                path
+            }
+        });
+    }
+    let path = CStr::from_bytes_until_nul(fragment.property(OverlayKeys::TARGET_PATH)?)
+        .ok()?
+        .to_bytes();
+    if path.starts_with(b"/") {
+        return Some(Vec::from(path));
+    }
+    // A path not starting with `/` begins with an alias
+    let (alias, rest) = path
+        .split_once(|&char| char == b'/')
+        .map_or((path, None), |(alias, rest)| (alias, Some(rest)));
+    let aliased = base
+        .root()
+        .child(OverlayKeys::ALIASES)?
+        .property(&CString::new(alias).ok()?)
+        .and_then(|aliased| CStr::from_bytes_until_nul(aliased).ok())?
+        .to_bytes();
+    let mut resolved = Vec::from(aliased);
+    if let Some(rest) = rest {
+        resolved.push(b'/');
+        resolved.extend_from_slice(rest);
+    }
+    Some(resolved)
+}
+
+/// Merges the properties and children of an overlay node into a node of the base tree
+fn merge(target: &mut Node, overlay: &Node) -> Result<(), Error> {
+    for (name, value) in overlay.properties() {
+        target.set_property(name, value);
+    }
+    for child in overlay.children() {
+        merge(
+            target.child_or_insert(child.name()).map_err(Error::Edit)?,
+            child,
+        )?;
+    }
+    Ok(())
+}
+
+/// Rewrites the path of a symbol defined within a fragment, i.e. `/fragment@N/__overlay__/rest`, into its path in the base tree after merging
+fn rewrite_symbol(path: &[u8], targets: &[(&[u8], Vec<u8>)]) -> Option<CString> {
+    let (fragment, rest) = path.strip_prefix(b"/")?.split_once(|&char| char == b'/')?;
+    let rest = rest.strip_prefix(OverlayKeys::OVERLAY)?;
+    let &(_, ref target) = targets.iter().find(|&&(name, _)| name == fragment)?;
+    let mut rewritten = if target.as_slice() == b"/" && !rest.is_empty() {
+        Vec::new()
+    } else {
+        target.clone()
+    };
+    rewritten.extend_from_slice(rest);
+    CString::new(rewritten).ok()
+}
+
+/// Applies an overlay to a base tree.
+///
+/// The phandles of the overlay are renumbered to follow those of the base tree, references to labels of the base tree are resolved through its `__symbols__` node,
+/// and the contents of each fragment are merged into its target.
+/// Any symbols defined by the overlay are added to the base tree's `__symbols__` node, rewritten to point at their location in the merged tree.
+///
+/// # Errors
+/// Returns an error if some reference or target of the overlay cannot be resolved.
+/// The base tree may be partially modified if an error occurs while merging fragments
+#[inline]
+pub fn apply(base: &mut Tree, overlay: &Tree) -> Result<(), Error> {
+    let mut overlay = overlay.clone();
+
+    let delta = max_phandle(base.root());
+    adjust_phandles(overlay.root_mut(), delta)?;
+    if let Some(local_fixups) = overlay.root_mut().remove_child(OverlayKeys::LOCAL_FIXUPS) {
+        adjust_local_references(overlay.root_mut(), &local_fixups, delta)?;
+    }
+
+    let mut next_phandle = max_phandle(overlay.root())
+        .max(delta)
+        .checked_add(1)
+        .ok_or(Error::PHandleOverflow)?;
+    resolve_fixups(base, &mut overlay, &mut next_phandle)?;
+
+    let symbols = overlay.root_mut().remove_child(OverlayKeys::SYMBOLS);
+
+    let mut targets = Vec::new();
+    for fragment in overlay.root().children() {
+        let Some(contents) = fragment.child(OverlayKeys::OVERLAY) else {
+            continue;
+        };
+        let target_path = fragment_target(base, fragment)
+            .ok_or_else(|| Error::Target(Box::from(fragment.name())))?;
+        let target = base
+            .find_mut(&target_path)
+            .ok_or_else(|| Error::Target(Box::from(fragment.name())))?;
+        merge(target, contents)?;
+        targets.push((fragment.name(), target_path));
+    }
+
+    if let Some(symbols) = symbols {
+        for (label, path) in symbols.properties() {
+            let Some(rewritten) = CStr::from_bytes_until_nul(path)
+                .ok()
+                .and_then(|path| rewrite_symbol(path.to_bytes(), &targets))
+            else {
+                continue;
+            };
+            base.root_mut()
+                .child_or_insert(OverlayKeys::SYMBOLS)
+                .map_err(Error::Edit)?
+                .set_property_str(label, &rewritten);
+        }
+    }
+
+    Ok(())
+}
@@ -5,12 +5,13 @@
 use core::{
     ffi::{CStr, FromBytesUntilNulError},
     mem,
-    num::NonZeroUsize,
     ptr::NonNull,
 };
 
 use alloc::{boxed::Box, vec::Vec};
 
+use crate::property::Address;
+
 /// A `U32ByteSlice` encapsulates a slice of `u32`s in big-endian format.
 /// This is the format used by the device tree header and struct portion of the device tree blob.
 #[derive(Debug, Clone, Copy)]
@@ -83,24 +84,37 @@ impl<'bytes> U32ByteSlice<'bytes> {
 
     /// Removes the first `cell_count` `u32`s and returns them as an integer
     ///
-    /// Currently the implementation only handles returning up to `u64`s, and will "silently" fail for larger cell counts (but will print out a message)
+    /// Currently the implementation only handles returning up to `u64`s.
+    /// For larger cell counts, the leading cells must all be zero; otherwise, the cells are still removed but `None` is returned
     pub fn consume_cells(&mut self, cell_count: u8) -> Option<u64> {
         match cell_count {
             0 => Some(0),
             1 => self.consume_u32().map(u64::from),
             2 => self.consume_u64(),
             count => {
-                let value = self.consume_u64();
+                let mut fits = true;
                 for _ in 2..count {
-                    if self.consume_u32() != Some(0) {
-                        panic!("WARNING: Cannot handle cell count {cell_count}");
-                    }
+                    fits &= self.consume_u32()? == 0;
                 }
-                value
+                self.consume_u64().filter(|_| fits)
             }
         }
     }
 
+    /// Removes the first `cell_count` `u32`s and returns them as an address, keeping every cell.
+    ///
+    /// Addresses of more than `Address::MAX_CELLS` cells are only supported if the extra leading cells are all zero; otherwise, the cells are still removed but `None` is returned
+    pub fn consume_address(&mut self, cell_count: u8) -> Option<Address> {
+        let mut value: u128 = 0;
+        let mut fits = true;
+        for _ in 0..cell_count {
+            let cell = self.consume_u32()?;
+            fits &= value.leading_zeros() >= u32::BITS;
+            value = value.wrapping_shl(u32::BITS) | u128::from(cell);
+        }
+        Address::new(value, cell_count.min(Address::MAX_CELLS)).filter(|_| fits)
+    }
+
     /// Converts this byte slice into a single cell integer, if exactly `cell_count` integers are in the slice
     ///
     /// This has the same limitations as `consume_cells` with respect to cell counts
@@ -108,43 +122,22 @@ impl<'bytes> U32ByteSlice<'bytes> {
         self.consume_cells(cell_count).filter(|_| self.is_empty())
     }
 
-    /// Converts this slice into a list of appropriate cell arrays, where the width of each element is determined by the corresponding size specified in `cell_counts`
+    /// Converts this slice into a list of (address, size) pairs, such as the entries of a `reg`, if it is a whole number of such pairs
     ///
-    /// This has the same limitations as `consume_cells` with respect to cell counts
-    #[expect(clippy::unwrap_in_result, reason = "Checks should never fail")]
-    pub fn into_cells_slice<const N: usize>(
-        mut self,
-        cell_counts: &[u8; N],
-    ) -> Option<Box<[[u64; N]]>> {
-        if self.padding != 0 {
+    /// This has the same limitations as `consume_address` and `consume_cells` with respect to cell counts.
+    /// Fails if the slice is nonempty but each pair has zero cells
+    pub fn into_regs(mut self, address_cells: u8, size_cells: u8) -> Option<Box<[(Address, u64)]>> {
+        if address_cells == 0 && size_cells == 0 && !self.is_empty() {
             return None;
         }
-        let total_length = cell_counts
-            .iter()
-            .copied()
-            .map(usize::from)
-            .try_reduce(usize::checked_add)
-            .expect("The total size of cells should not overflow a `usize`")
-            .expect("There should be a nonzero number of cells");
-        if let Some(length) = NonZeroUsize::new(total_length) {
-            if self.len_u32s() % length != 0 {
-                return None;
-            }
-            let num_groups = self.len_u32s() / length;
-            let mut cell_list = Vec::with_capacity(num_groups);
-            while !self.is_empty() {
-                let mut cell_group = [0; N];
-                for (&mut ref mut value, &size) in cell_group.iter_mut().zip(cell_counts.iter()) {
-                    *value = self
-                        .consume_cells(size)
-                        .expect("Length should have been properly checked already");
-                }
-                cell_list.push(cell_group);
-            }
-            Some(cell_list.into_boxed_slice())
-        } else {
-            self.is_empty().then(|| Vec::new().into_boxed_slice())
+        let mut regs = Vec::new();
+        while !self.is_empty() {
+            regs.push((
+                self.consume_address(address_cells)?,
+                self.consume_cells(size_cells)?,
+            ));
         }
+        Some(regs.into_boxed_slice())
     }
 
     /// Takes the first `count` *bytes* from the slice, if there are enough.
@@ -211,7 +204,7 @@ impl<'bytes> U32ByteSlice<'bytes> {
     }
 
     /// Returns the number of bytes in this slice, NOT the number of `u32`s
-    fn len_bytes(&self) -> usize {
+    pub(crate) fn len_bytes(&self) -> usize {
         self.len_u32s()
             .checked_mul(ELEMENT_WIDTH)
             .and_then(|bytes| bytes.checked_sub(self.padding.into()))
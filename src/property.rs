@@ -5,9 +5,14 @@ use alloc::string::String;
 use alloc::{boxed::Box, vec::Vec};
 use core::ffi::CStr;
 use core::fmt;
+use core::{
+    cmp,
+    hash::{Hash, Hasher},
+};
 use core::{ffi::FromBytesUntilNulError, fmt::Debug};
 
 /// The model property value is a `<string>` that specifies the manufacturer’s model number of the device.
+#[derive(Clone, Copy)]
 pub enum Model<'bytes> {
     /// The recommended format: "manufacturer,model", where manufacturer is a string describing the
     /// name of the manufacturer (such as a stock ticker symbol), and model specifies the model number.
@@ -37,6 +42,20 @@ impl fmt::Debug for Model<'_> {
     }
 }
 
+impl Model<'_> {
+    /// Returns whether or not this model is the given string, such as `b"arm,gic-400"`
+    #[must_use]
+    #[inline]
+    pub fn matches(&self, string: &[u8]) -> bool {
+        match *self {
+            Self::ManufacturerModel(manufacturer, model) => {
+                string.split_once(|&byte| byte == b',') == Some((manufacturer, model))
+            }
+            Self::Other(other) => other == string,
+        }
+    }
+}
+
 impl<'bytes> From<&'bytes CStr> for Model<'bytes> {
     fn from(value: &'bytes CStr) -> Self {
         let value = value.to_bytes();
@@ -86,24 +105,201 @@ impl<'bytes> TryFrom<U32ByteSlice<'bytes>> for Box<[Model<'bytes>]> {
     }
 }
 
+/// An address within the address space of some bus, made up of the bus' `#address-cells` cells.
+///
+/// Every cell of the address is kept, so that addresses wider than 64 bits, such as those of PCI buses, are represented exactly.
+/// Addresses are compared and hashed by value alone, so that the same address made up of different numbers of cells is considered equal
+#[derive(Clone, Copy)]
+pub struct Address {
+    /// The value of the address, with its cells concatenated from most to least significant
+    value: u128,
+    /// The number of cells that the address is made up of
+    cells: u8,
+}
+
+impl Address {
+    /// The greatest number of cells supported in an address
+    pub const MAX_CELLS: u8 = 4;
+
+    /// Creates an address of the given number of cells, if the value fits into that many cells
+    #[must_use]
+    #[inline]
+    pub fn new(value: u128, cells: u8) -> Option<Self> {
+        if cells > Self::MAX_CELLS {
+            return None;
+        }
+        match value.checked_shr(u32::BITS.saturating_mul(cells.into())) {
+            Some(0) | None => Some(Self { value, cells }),
+            Some(_) => None,
+        }
+    }
+
+    /// Returns the value of this address, with its cells concatenated from most to least significant
+    #[must_use]
+    #[inline]
+    pub const fn value(&self) -> u128 {
+        self.value
+    }
+
+    /// Returns the number of cells that this address is made up of
+    #[must_use]
+    #[inline]
+    pub const fn cells(&self) -> u8 {
+        self.cells
+    }
+
+    /// Returns the cell at the given index of this address, where index 0 is the most significant cell
+    #[must_use]
+    #[inline]
+    pub fn cell(&self, index: u8) -> Option<u32> {
+        let shift = u32::BITS.checked_mul(self.cells.checked_sub(index)?.checked_sub(1)?.into())?;
+        u32::try_from(self.value.checked_shr(shift)? & u128::from(u32::MAX)).ok()
+    }
+
+    /// Returns the value of this address as a `u64`, if it fits
+    #[must_use]
+    #[inline]
+    pub fn to_u64(self) -> Option<u64> {
+        u64::try_from(self.value).ok()
+    }
+
+    /// Returns the address the given offset past this one, if it still fits into the same number of cells
+    #[must_use]
+    #[inline]
+    pub fn checked_add(&self, offset: u128) -> Option<Self> {
+        Self::new(self.value.checked_add(offset)?, self.cells)
+    }
+
+    /// Returns the offset of this address past the given address, if this address is not below it
+    #[must_use]
+    #[inline]
+    pub const fn offset_from(&self, base: &Self) -> Option<u128> {
+        self.value.checked_sub(base.value)
+    }
+}
+
+impl From<u64> for Address {
+    #[inline]
+    fn from(value: u64) -> Self {
+        Self {
+            value: value.into(),
+            cells: 2,
+        }
+    }
+}
+
+impl PartialEq for Address {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for Address {}
+
+impl PartialOrd for Address {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Address {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl Hash for Address {
+    #[inline]
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.value.hash(state);
+    }
+}
+
+impl fmt::Debug for Address {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#X}", self.value)
+    }
+}
+
+impl fmt::UpperHex for Address {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.value, f)
+    }
+}
+
+impl fmt::LowerHex for Address {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.value, f)
+    }
+}
+
 /// The `ranges` property provides a means of defining a mapping or translation between the address space of the bus (the child address space) and the address space of the bus node’s parent (the parent address space).
 #[derive(Debug)]
 pub struct Range {
     /// The `child-bus-address` is a physical address within the child bus’ address space.
-    pub child_address: u64,
+    pub child_address: Address,
     /// `The parent-bus-address` is a physical address within the parent bus’ address space.
-    pub parent_address: u64,
+    pub parent_address: Address,
     /// The `length` specifies the size of the range in the child’s address space.
     pub length: u64,
 }
 
-impl From<[u64; 3]> for Range {
-    fn from([child_address, parent_address, size]: [u64; 3]) -> Self {
-        Self {
-            child_address,
-            parent_address,
-            length: size,
+impl Range {
+    /// Translates the region of the given size at the given child bus address into the parent bus' address space,
+    /// if the region lies entirely within this range
+    #[must_use]
+    #[inline]
+    pub fn translate(&self, address: &Address, size: u64) -> Option<Address> {
+        let offset = address.offset_from(&self.child_address)?;
+        (offset.checked_add(size.into())? <= self.length.into())
+            .then(|| self.parent_address.checked_add(offset))
+            .flatten()
+    }
+
+    /// Translates the region of the given size at the given parent bus address back into the child bus' address space,
+    /// if the region lies entirely within this range
+    #[must_use]
+    #[inline]
+    pub fn translate_from_parent(&self, address: &Address, size: u64) -> Option<Address> {
+        let offset = address.offset_from(&self.parent_address)?;
+        (offset.checked_add(size.into())? <= self.length.into())
+            .then(|| self.child_address.checked_add(offset))
+            .flatten()
+    }
+
+    /// Parses a `ranges` property of the given child address, parent address, and size cell counts into its entries.
+    /// Fails if the value is nonempty but each entry has zero cells
+    pub(crate) fn parse_list(
+        mut bytes: U32ByteSlice<'_>,
+        child_address_cells: u8,
+        parent_address_cells: u8,
+        size_cells: u8,
+    ) -> Option<Box<[Self]>> {
+        if child_address_cells == 0
+            && parent_address_cells == 0
+            && size_cells == 0
+            && !bytes.is_empty()
+        {
+            return None;
+        }
+        let mut ranges = Vec::new();
+        while !bytes.is_empty() {
+            ranges.push(Self {
+                child_address: bytes.consume_address(child_address_cells)?,
+                parent_address: bytes.consume_address(parent_address_cells)?,
+                length: bytes.consume_cells(size_cells)?,
+            });
         }
+        Some(ranges.into_boxed_slice())
     }
 }
 
@@ -245,6 +441,16 @@ pub enum ChassisError<'bytes> {
     Invalid,
 }
 
+impl fmt::Display for ChassisError<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::CStr(_) => f.write_str("not a string"),
+            Self::Invalid => f.write_str("not a known chassis type"),
+        }
+    }
+}
+
 impl<'bytes> TryFrom<U32ByteSlice<'bytes>> for ChassisType {
     type Error = ChassisError<'bytes>;
 
@@ -0,0 +1,23 @@
+//! The reference-counted pointers used throughout the node graph
+//!
+//! By default these are the single-threaded `Rc` and `Weak` from `alloc::rc`.
+//! With the `sync` feature enabled, they are instead the atomic `Arc` and `Weak` from `alloc::sync`,
+//! which makes a parsed device tree `Send` and `Sync` so that it can be shared between cores
+
+#[cfg(not(feature = "sync"))]
+use alloc::rc;
+#[cfg(feature = "sync")]
+use alloc::sync;
+
+/// A strong, single-threaded reference-counted pointer
+#[cfg(not(feature = "sync"))]
+pub type Rc<T> = rc::Rc<T>;
+/// A weak, single-threaded reference-counted pointer
+#[cfg(not(feature = "sync"))]
+pub type Weak<T> = rc::Weak<T>;
+/// A strong, atomically reference-counted pointer
+#[cfg(feature = "sync")]
+pub type Rc<T> = sync::Arc<T>;
+/// A weak, atomically reference-counted pointer
+#[cfg(feature = "sync")]
+pub type Weak<T> = sync::Weak<T>;
@@ -0,0 +1,343 @@
+//! Allocation-free walking of a device tree blob
+//!
+//! Unlike `DeviceTree::from_bytes`, which builds the whole tree on the heap, a `Walker` reads nodes and properties directly out of the blob as a stream of events.
+//! This allows early boot code to find e.g. `/memory` and `/chosen` before any heap is available
+
+use crate::dtb::{Blocks, DeviceTreeError, Token, TokenError};
+use crate::parse::U32ByteSlice;
+use crate::{is_aligned_for, transmute_slice_down};
+use core::ffi::CStr;
+use core::fmt::{self, Display, Write as _};
+use core::iter;
+
+/// The maximum depth of nodes, including the root, that a `Walker` can track the path of
+pub const MAX_DEPTH: usize = 32;
+
+/// Errors that can occur while walking the structure block
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error occured while trying to parse a token and associated data
+    Token(TokenError),
+    /// Nodes were nested more deeply than `MAX_DEPTH`
+    Depth,
+    /// A node was ended without having begun, or the structure block ended while nodes were still open
+    MismatchedNodes,
+}
+
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Token(ref error) => Display::fmt(error, f),
+            Self::Depth => write!(f, "nodes are nested more than {MAX_DEPTH} deep"),
+            Self::MismatchedNodes => f.write_str("mismatched `BeginNode` and `EndNode` tokens"),
+        }
+    }
+}
+
+/// A single piece of the structure block, as produced by a `Walker`
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Event<'dtb> {
+    /// A node begins, with the given raw unit name. The root node has an empty name
+    BeginNode(&'dtb [u8]),
+    /// The most recently begun node ends
+    EndNode,
+    /// A property of the most recently begun node
+    Property(Property<'dtb>),
+}
+
+/// A property, as read directly out of a device tree blob
+#[derive(Debug, Clone, Copy)]
+pub struct Property<'dtb> {
+    /// The name of the property
+    name: &'dtb CStr,
+    /// The value of the property
+    value: U32ByteSlice<'dtb>,
+}
+
+impl<'dtb> Property<'dtb> {
+    /// Returns the name of the property
+    #[must_use]
+    #[inline]
+    pub const fn name(&self) -> &'dtb CStr {
+        self.name
+    }
+
+    /// Returns the raw, big-endian value of the property
+    #[must_use]
+    #[inline]
+    pub fn value(&self) -> &'dtb [u8] {
+        self.value.into()
+    }
+
+    /// Returns the value of the property as a single `u32`, if it is exactly one cell
+    #[must_use]
+    #[inline]
+    pub fn as_u32(&self) -> Option<u32> {
+        u32::try_from(self.value).ok()
+    }
+
+    /// Returns the value of the property as a single `u64`, if it is exactly two cells
+    #[must_use]
+    #[inline]
+    pub fn as_u64(&self) -> Option<u64> {
+        u64::try_from(self.value).ok()
+    }
+
+    /// Returns the value of the property as a string, if it is nul-terminated
+    #[must_use]
+    #[inline]
+    pub fn as_c_str(&self) -> Option<&'dtb CStr> {
+        <&CStr>::try_from(self.value).ok()
+    }
+
+    /// Returns an iterator over the value of the property as groups of the given number of cells, such as the entries of a `reg`.
+    /// Each group is produced as a single `u64`, so only up to 2 cells per group are supported.
+    /// Returns `None` if the value is not a whole number of groups
+    #[must_use]
+    #[inline]
+    pub fn cells(&self, cell_count: u8) -> Option<impl Iterator<Item = u64> + 'dtb> {
+        let mut value = self.value;
+        <&[u32]>::try_from(value)
+            .ok()
+            .filter(|cells| {
+                (1..=2).contains(&cell_count)
+                    && cells.len().checked_rem(usize::from(cell_count)) == Some(0)
+            })
+            .map(|_| iter::from_fn(move || value.consume_cells(cell_count)))
+    }
+}
+
+/// A cursor over the structure block of a device tree blob, producing the nodes and properties in order without allocating.
+///
+/// The walker tracks the depth of, and path to, the current node as it goes
+#[derive(Debug, Clone)]
+pub struct Walker<'dtb> {
+    /// The remainder of the structure block
+    structure: U32ByteSlice<'dtb>,
+    /// The size in bytes of the whole structure block
+    size: usize,
+    /// The strings block
+    strings: &'dtb [u8],
+    /// The names of the currently open nodes, from the root downwards
+    path: [&'dtb [u8]; MAX_DEPTH],
+    /// The number of currently open nodes
+    depth: usize,
+    /// Whether or not the end of the structure block has been reached
+    done: bool,
+}
+
+impl<'dtb> Walker<'dtb> {
+    /// Locates the structure block of the given device tree blob, and creates a walker at its start
+    ///
+    /// # Errors
+    /// Returns an error if the header of the blob is invalid
+    #[inline]
+    pub fn new(dtb: &'dtb [u64]) -> Result<Self, DeviceTreeError<'dtb>> {
+        // SAFETY: It is safe to transmute a `u64` to `u8`s
+        Self::from_byte_slice(unsafe { transmute_slice_down(dtb) })
+    }
+
+    /// Locates the structure block of the given device tree blob, which must be aligned to a `u64` boundary, and creates a walker at its start
+    ///
+    /// # Errors
+    /// Returns an error if the blob is misaligned or its header is invalid
+    #[inline]
+    pub fn from_byte_slice(dtb: &'dtb [u8]) -> Result<Self, DeviceTreeError<'dtb>> {
+        if !is_aligned_for::<u64>(dtb.as_ptr()) {
+            return Err(DeviceTreeError::Alignment);
+        }
+        let blocks = Blocks::locate(dtb)?;
+        Ok(Self {
+            structure: blocks.structure,
+            size: blocks.header.structure_size(),
+            strings: blocks.strings,
+            path: [&[]; MAX_DEPTH],
+            depth: 0,
+            done: false,
+        })
+    }
+
+    /// Returns the number of currently open nodes, i.e. 1 while within the root node but not any of its children
+    #[must_use]
+    #[inline]
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the byte offset, from the start of the structure block, of the next token to be read
+    #[must_use]
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.size.wrapping_sub(self.structure.len_bytes())
+    }
+
+    /// Returns the path to the current node, which can be displayed or compared against without allocating
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> Path<'_, 'dtb> {
+        Path(self.path.get(..self.depth).unwrap_or_default())
+    }
+
+    /// Skips the remainder of the current node, including all of its children, such that the next event is the one after its `EndNode`
+    ///
+    /// # Errors
+    /// Returns an error if the structure block is malformed
+    #[inline]
+    pub fn skip_node(&mut self) -> Result<(), Error> {
+        let depth = self.depth;
+        while self.depth >= depth && depth > 0 {
+            if self.next().transpose()?.is_none() {
+                return Err(Error::MismatchedNodes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Walks forward to the node with the given path, such as `b"/chosen"`, returning whether it was found.
+    /// The walker is left just after the `BeginNode` of the found node, such that its properties are next
+    ///
+    /// # Errors
+    /// Returns an error if the structure block is malformed
+    #[inline]
+    pub fn find_node(&mut self, path: &[u8]) -> Result<bool, Error> {
+        while let Some(event) = self.next().transpose()? {
+            if matches!(event, Event::BeginNode(_)) {
+                if self.path() == path {
+                    return Ok(true);
+                }
+                if !self.path().is_prefix_of(path) {
+                    self.skip_node()?;
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl<'dtb> Iterator for Walker<'dtb> {
+    type Item = Result<Event<'dtb>, Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let token = match Token::consume_token(&mut self.structure, self.strings) {
+                Ok(token) => token,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(Error::Token(err)));
+                }
+            };
+            let event = match token {
+                Token::BeginNode(name) => {
+                    let Some(slot) = self.path.get_mut(self.depth) else {
+                        self.done = true;
+                        return Some(Err(Error::Depth));
+                    };
+                    *slot = name;
+                    self.depth = self.depth.wrapping_add(1);
+                    Event::BeginNode(name)
+                }
+                Token::EndNode => {
+                    let Some(depth) = self.depth.checked_sub(1) else {
+                        self.done = true;
+                        return Some(Err(Error::MismatchedNodes));
+                    };
+                    self.depth = depth;
+                    Event::EndNode
+                }
+                Token::Prop(name, value) => Event::Property(Property { name, value }),
+                Token::Nop => continue,
+                Token::End => {
+                    self.done = true;
+                    return (self.depth != 0).then_some(Err(Error::MismatchedNodes));
+                }
+            };
+            return Some(Ok(event));
+        }
+        None
+    }
+}
+
+/// The path to a node being walked, as the names of each node from the root downwards
+#[derive(Debug, Clone, Copy)]
+pub struct Path<'walker, 'dtb>(&'walker [&'dtb [u8]]);
+
+/// Splits an absolute path, such as `b"/soc/serial@7e201000"`, into the names of each node below the root.
+/// Returns `None` if the path is not absolute
+fn split_path(path: &[u8]) -> Option<impl Iterator<Item = &[u8]>> {
+    Some(
+        path.strip_prefix(b"/")?
+            .split(|&byte| byte == b'/')
+            .filter(|component| !component.is_empty()),
+    )
+}
+
+/// Returns whether or not the given node name matches the given path component.
+/// A component without a unit address matches any node with the same node name, as in `b"/memory"` matching `memory@0`
+fn component_matches(name: &[u8], component: &[u8]) -> bool {
+    name == component
+        || (!component.contains(&b'@')
+            && name.split(|&byte| byte == b'@').next() == Some(component))
+}
+
+impl<'walker, 'dtb> Path<'walker, 'dtb> {
+    /// Returns an iterator over the names of the nodes along the path, excluding the root
+    #[inline]
+    pub fn components(&self) -> impl Iterator<Item = &'dtb [u8]> + 'walker {
+        self.0.iter().skip(1).copied()
+    }
+
+    /// Returns whether or not this path is an ancestor of, or the same as, the given path
+    fn is_prefix_of(&self, path: &[u8]) -> bool {
+        split_path(path).is_some_and(|mut components| {
+            self.components().all(|name| {
+                components
+                    .next()
+                    .is_some_and(|component| component_matches(name, component))
+            })
+        })
+    }
+}
+
+impl PartialEq<[u8]> for Path<'_, '_> {
+    /// Compares this path against an absolute path, where components without unit addresses match any unit address
+    #[inline]
+    fn eq(&self, other: &[u8]) -> bool {
+        split_path(other).is_some_and(|mut components| {
+            self.components().all(|name| {
+                components
+                    .next()
+                    .is_some_and(|component| component_matches(name, component))
+            }) && components.next().is_none()
+        })
+    }
+}
+
+impl PartialEq<&[u8]> for Path<'_, '_> {
+    #[inline]
+    fn eq(&self, other: &&[u8]) -> bool {
+        *self == **other
+    }
+}
+
+impl Display for Path<'_, '_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.len() <= 1 {
+            return f.write_char('/');
+        }
+        for component in self.components() {
+            f.write_char('/')?;
+            for chunk in component.utf8_chunks() {
+                f.write_str(chunk.valid())?;
+                if !chunk.invalid().is_empty() {
+                    f.write_char(char::REPLACEMENT_CHARACTER)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+//! Serialization of a device tree back into the flattened device tree blob (DTB) format
+//!
+//! The `Writer` is fed the structure of a tree one node and property at a time, in the same order as the tokens of the structure block, and then emits a complete blob with a header, memory reservation block, structure block, and deduplicated strings block.
+
+use crate::map::Map;
+use alloc::{boxed::Box, vec::Vec};
+use core::{ffi::CStr, mem};
+
+/// Errors that can occur while serializing a device tree
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A node was ended without a corresponding beginning
+    TooManyEnds,
+    /// The tree was finished while some node was still open
+    UnclosedNode(usize),
+    /// The tree did not have exactly one root node
+    BadRoots(usize),
+    /// A property was written outside of any node
+    PropertyOutsideNode,
+    /// A node name contained a NUL byte
+    NodeName,
+    /// The size of some block or value does not fit into the 32-bit fields of the format
+    Size,
+}
+
+/// Incrementally builds the structure and strings blocks of a device tree blob
+#[derive(Debug, Default)]
+pub struct Writer {
+    /// The structure block emitted so far
+    structure: Vec<u8>,
+    /// The strings block emitted so far
+    strings: Vec<u8>,
+    /// Offsets of the strings already present in the strings block, so that property names are only stored once
+    string_offsets: Map<Box<[u8]>, u32>,
+    /// The number of nodes currently open
+    depth: usize,
+    /// The number of top-level nodes written
+    roots: usize,
+}
+
+/// The magic bytes located at the start of the device tree
+const FDT_HEADER_MAGIC: u32 = 0xD00D_FEED;
+/// The discriminant value for a `BeginNode` token
+const FDT_BEGIN_NODE: u32 = 0x1;
+/// The discriminant value for an `EndNode` token
+const FDT_END_NODE: u32 = 0x2;
+/// The discriminant value for a `Prop` token
+const FDT_PROP: u32 = 0x3;
+/// The discriminant value for an `End` token
+const FDT_END: u32 = 0x9;
+/// The version of the blob emitted
+const VERSION: u32 = 17;
+/// The last version that the emitted blob is backwards compatible with
+const LAST_COMPATIBLE_VERSION: u32 = 16;
+/// The size of the header, in bytes
+const HEADER_SIZE: usize = 10 * mem::size_of::<u32>();
+
+impl Writer {
+    /// Creates a new, empty writer
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a big-endian `u32` to the structure block
+    fn push_u32(&mut self, value: u32) {
+        self.structure.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Pads the structure block with zeroes up to the next 32-bit boundary
+    fn pad(&mut self) {
+        self.structure.resize(
+            self.structure.len().next_multiple_of(mem::size_of::<u32>()),
+            0,
+        );
+    }
+
+    /// Returns the offset of the given string in the strings block, adding it if not yet present
+    fn string_offset(&mut self, string: &CStr) -> Result<u32, Error> {
+        let bytes = string.to_bytes();
+        if let Some(&offset) = self.string_offsets.get(bytes) {
+            return Ok(offset);
+        }
+        let offset = u32::try_from(self.strings.len()).map_err(|_err| Error::Size)?;
+        self.strings.extend_from_slice(string.to_bytes_with_nul());
+        self.string_offsets.insert(Box::from(bytes), offset);
+        Ok(offset)
+    }
+
+    /// Begins a new node with the given unit name, as a child of the currently open node.
+    /// All properties of the node should be written before any of its children.
+    ///
+    /// # Errors
+    /// Returns an error if the name contains a NUL byte
+    #[inline]
+    pub fn begin_node(&mut self, name: &[u8]) -> Result<(), Error> {
+        if name.contains(&0) {
+            return Err(Error::NodeName);
+        }
+        if self.depth == 0 {
+            self.roots = self.roots.saturating_add(1);
+        }
+        self.depth = self.depth.saturating_add(1);
+        self.push_u32(FDT_BEGIN_NODE);
+        self.structure.extend_from_slice(name);
+        self.structure.push(0);
+        self.pad();
+        Ok(())
+    }
+
+    /// Writes a property of the currently open node
+    ///
+    /// # Errors
+    /// Returns an error if no node is open, or if the value is too large
+    #[inline]
+    pub fn property(&mut self, name: &CStr, value: &[u8]) -> Result<(), Error> {
+        if self.depth == 0 {
+            return Err(Error::PropertyOutsideNode);
+        }
+        let length = u32::try_from(value.len()).map_err(|_err| Error::Size)?;
+        let name_offset = self.string_offset(name)?;
+        self.push_u32(FDT_PROP);
+        self.push_u32(length);
+        self.push_u32(name_offset);
+        self.structure.extend_from_slice(value);
+        self.pad();
+        Ok(())
+    }
+
+    /// Ends the currently open node
+    ///
+    /// # Errors
+    /// Returns an error if there is no open node
+    #[inline]
+    pub fn end_node(&mut self) -> Result<(), Error> {
+        self.depth = self.depth.checked_sub(1).ok_or(Error::TooManyEnds)?;
+        self.push_u32(FDT_END_NODE);
+        Ok(())
+    }
+
+    /// Finishes the tree and emits the complete blob, including the given memory reservations and physical ID of the boot CPU
+    ///
+    /// # Errors
+    /// Returns an error if some node is still open, the tree does not have exactly one root, or the blob is too large
+    #[inline]
+    pub fn finish(
+        mut self,
+        memory_reservations: &[(u64, u64)],
+        boot_cpuid_phys: u32,
+    ) -> Result<Vec<u8>, Error> {
+        if self.depth != 0 {
+            return Err(Error::UnclosedNode(self.depth));
+        }
+        if self.roots != 1 {
+            return Err(Error::BadRoots(self.roots));
+        }
+        self.push_u32(FDT_END);
+
+        // The memory reservation block directly follows the header, which is already 8-byte aligned
+        let mem_rsvmap_offset = HEADER_SIZE;
+        let struct_offset = memory_reservations
+            .len()
+            .checked_add(1) // Terminating entry
+            .and_then(|entries| entries.checked_mul(mem::size_of::<[u64; 2]>()))
+            .and_then(|size| size.checked_add(mem_rsvmap_offset))
+            .ok_or(Error::Size)?;
+        let strings_offset = struct_offset
+            .checked_add(self.structure.len())
+            .ok_or(Error::Size)?;
+        let total_size = strings_offset
+            .checked_add(self.strings.len())
+            .ok_or(Error::Size)?;
+
+        let header = [
+            FDT_HEADER_MAGIC,
+            u32::try_from(total_size).map_err(|_err| Error::Size)?,
+            u32::try_from(struct_offset).map_err(|_err| Error::Size)?,
+            u32::try_from(strings_offset).map_err(|_err| Error::Size)?,
+            u32::try_from(mem_rsvmap_offset).map_err(|_err| Error::Size)?,
+            VERSION,
+            LAST_COMPATIBLE_VERSION,
+            boot_cpuid_phys,
+            u32::try_from(self.strings.len()).map_err(|_err| Error::Size)?,
+            u32::try_from(self.structure.len()).map_err(|_err| Error::Size)?,
+        ];
+
+        let mut blob = Vec::with_capacity(total_size);
+        for field in header {
+            blob.extend_from_slice(&field.to_be_bytes());
+        }
+        for &(address, size) in memory_reservations.iter().chain(&[(0, 0)]) {
+            blob.extend_from_slice(&address.to_be_bytes());
+            blob.extend_from_slice(&size.to_be_bytes());
+        }
+        blob.append(&mut self.structure);
+        blob.append(&mut self.strings);
+        Ok(blob)
+    }
+}
@@ -0,0 +1,201 @@
+//! Tests for decoding the interrupts of ARM Generic Interrupt Controllers and generic timers
+
+mod common;
+
+use device_tree::dtb::DeviceTree;
+use device_tree::node::interrupt::arm::{Error, GicSpecifier, Kind, Timer, Trigger};
+use device_tree::node::Node as _;
+
+/// Decodes a GIC specifier from the given cells
+fn decode(cells: &[u32]) -> Result<GicSpecifier, Error> {
+    GicSpecifier::try_from(cells)
+}
+
+#[test]
+fn spi_range() {
+    let first = decode(&[0, 0, 4]).expect("First SPI should decode");
+    assert_eq!(first.kind(), Kind::Spi);
+    assert_eq!(first.intid(), 32);
+    let last = decode(&[0, 987, 4]).expect("Last SPI should decode");
+    assert_eq!(last.number(), 987);
+    assert_eq!(last.intid(), 1019);
+    assert!(matches!(decode(&[0, 988, 4]), Err(Error::Number(988))));
+}
+
+#[test]
+fn ppi_range() {
+    let first = decode(&[1, 0, 4]).expect("First PPI should decode");
+    assert_eq!(first.kind(), Kind::Ppi);
+    assert_eq!(first.intid(), 16);
+    assert_eq!(
+        decode(&[1, 15, 4]).map(|specifier| specifier.intid()).ok(),
+        Some(31)
+    );
+    assert!(matches!(decode(&[1, 16, 4]), Err(Error::Number(16))));
+}
+
+#[test]
+fn extended_ranges() {
+    let spi = decode(&[2, 5, 4]).expect("Extended SPI should decode");
+    assert_eq!(spi.kind(), Kind::ExtendedSpi);
+    assert_eq!(spi.intid(), 4101);
+    assert!(matches!(decode(&[2, 1024, 4]), Err(Error::Number(1024))));
+
+    let ppi = decode(&[3, 63, 4]).expect("Extended PPI should decode");
+    assert_eq!(ppi.kind(), Kind::ExtendedPpi);
+    assert_eq!(ppi.intid(), 1119);
+    assert!(matches!(decode(&[3, 64, 4]), Err(Error::Number(64))));
+
+    assert!(matches!(decode(&[4, 0, 4]), Err(Error::Kind(4))));
+}
+
+#[test]
+fn trigger_decoding() {
+    let trigger = |flags| decode(&[0, 1, flags]).map(|specifier| specifier.trigger());
+    assert_eq!(trigger(0).ok(), Some(None));
+    assert_eq!(trigger(1).ok(), Some(Some(Trigger::EdgeRising)));
+    assert_eq!(trigger(2).ok(), Some(Some(Trigger::EdgeFalling)));
+    assert_eq!(trigger(4).ok(), Some(Some(Trigger::LevelHigh)));
+    assert_eq!(trigger(8).ok(), Some(Some(Trigger::LevelLow)));
+    assert!(matches!(trigger(3), Err(Error::Trigger(3))));
+}
+
+#[test]
+fn cpu_mask() {
+    let specifier = decode(&[1, 14, 0xF08]).expect("PPI should decode");
+    assert_eq!(specifier.cpu_mask(), 0x0F);
+    assert_eq!(specifier.trigger(), Some(Trigger::LevelLow));
+    assert_eq!(
+        decode(&[1, 14, 0xFF04])
+            .map(|specifier| specifier.cpu_mask())
+            .ok(),
+        Some(0xFF)
+    );
+    assert_eq!(
+        decode(&[0, 14, 4])
+            .map(|specifier| specifier.cpu_mask())
+            .ok(),
+        Some(0)
+    );
+}
+
+#[test]
+fn partition() {
+    let specifier = decode(&[1, 7, 4, 0x42]).expect("Partitioned PPI should decode");
+    assert_eq!(specifier.partition(), Some(0x42));
+    assert_eq!(specifier.intid(), 23);
+    assert_eq!(
+        decode(&[1, 7, 4, 0])
+            .map(|specifier| specifier.partition())
+            .ok(),
+        Some(None)
+    );
+    assert_eq!(
+        decode(&[1, 7, 4])
+            .map(|specifier| specifier.partition())
+            .ok(),
+        Some(None)
+    );
+    assert!(matches!(decode(&[1, 7]), Err(Error::Cells)));
+}
+
+/// Compiles a tree with a GIC and the given nodes
+fn compile(nodes: &str) -> Box<[u64]> {
+    common::compile(&common::source(
+        1,
+        "",
+        &format!(
+            r#"memory@0 {{ device_type = "memory"; reg = <0x0 0x10000000>; }};
+    gic: interrupt-controller@1000 {{
+        compatible = "arm,gic-400";
+        reg = <0x1000 0x100>;
+        interrupt-controller;
+        #interrupt-cells = <3>;
+    }};
+    {nodes}"#
+        ),
+    ))
+}
+
+/// Decodes the timer at `/timer` of the given tree
+fn timer<'dtb>(tree: &'dtb DeviceTree<'dtb>) -> Result<Timer, Error> {
+    let root = tree.root();
+    Timer::new(&root.find_str(b"/timer").expect("Timer should exist"), root)
+}
+
+#[test]
+fn timer_with_four_interrupts() {
+    let dtb = compile(
+        r#"timer {
+        compatible = "arm,armv8-timer";
+        interrupt-parent = <&gic>;
+        interrupts = <1 13 0xf08>, <1 14 0xf08>, <1 11 0xf08>, <1 10 0xf08>;
+    };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let timer = timer(&tree).expect("Timer should decode");
+    assert_eq!(timer.secure_physical().intid(), 29);
+    assert_eq!(timer.non_secure_physical().intid(), 30);
+    assert_eq!(timer.virt().intid(), 27);
+    assert_eq!(timer.hypervisor().intid(), 26);
+    assert_eq!(timer.hypervisor().cpu_mask(), 0x0F);
+    assert_eq!(timer.hypervisor_virtual(), None);
+}
+
+#[test]
+fn timer_with_five_interrupts() {
+    let dtb = compile(
+        r#"timer {
+        compatible = "arm,armv8-timer";
+        interrupt-parent = <&gic>;
+        interrupts = <1 13 4>, <1 14 4>, <1 11 4>, <1 10 4>, <1 12 4>;
+    };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let timer = timer(&tree).expect("Timer should decode");
+    assert_eq!(
+        timer
+            .hypervisor_virtual()
+            .map(|specifier| specifier.intid()),
+        Some(28)
+    );
+}
+
+#[test]
+fn timer_rejects_spi() {
+    let dtb = compile(
+        r#"timer {
+        compatible = "arm,armv8-timer";
+        interrupt-parent = <&gic>;
+        interrupts = <1 13 4>, <0 14 4>, <1 11 4>, <1 10 4>;
+    };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(matches!(timer(&tree), Err(Error::TimerInterrupts)));
+}
+
+#[test]
+fn timer_rejects_missing_interrupts() {
+    let dtb = compile(
+        r#"timer {
+        compatible = "arm,armv7-timer";
+        interrupt-parent = <&gic>;
+        interrupts = <1 13 4>, <1 14 4>, <1 11 4>;
+    };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(matches!(timer(&tree), Err(Error::TimerInterrupts)));
+}
+
+#[test]
+fn timer_must_be_compatible() {
+    let dtb = compile(
+        r#"timer {
+        compatible = "test,timer";
+        interrupt-parent = <&gic>;
+        interrupts = <1 13 4>, <1 14 4>, <1 11 4>, <1 10 4>;
+    };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(matches!(timer(&tree), Err(Error::Timer)));
+}
@@ -0,0 +1,107 @@
+//! Fixtures shared between the integration tests
+
+#![allow(dead_code, reason = "Each test crate uses only some of the fixtures")]
+
+use device_tree::dtb::copy_aligned;
+
+/// The indices of the fields of a blob's header, each of which is a big-endian `u32`
+pub mod field {
+    pub const MAGIC: usize = 0;
+    pub const TOTAL_SIZE: usize = 1;
+    pub const STRUCTURE_OFFSET: usize = 2;
+    pub const STRINGS_OFFSET: usize = 3;
+    pub const RESERVATIONS_OFFSET: usize = 4;
+    pub const VERSION: usize = 5;
+    pub const LAST_COMPATIBLE_VERSION: usize = 6;
+    pub const BOOT_CPUID_PHYS: usize = 7;
+    pub const STRINGS_SIZE: usize = 8;
+    pub const STRUCTURE_SIZE: usize = 9;
+}
+
+/// The tokens of the structure block, each of which is a big-endian `u32`
+pub mod token {
+    /// The `BeginNode` token
+    pub const BEGIN_NODE: u32 = 1;
+    /// The `EndNode` token
+    pub const END_NODE: u32 = 2;
+    /// The `End` token
+    pub const END: u32 = 9;
+}
+
+/// Builds a blob with no memory reservations or strings around the given structure block tokens
+pub fn blob(structure: &[u32]) -> Box<[u64]> {
+    const HEADER_SIZE: u32 = 40;
+    const RESERVATIONS_SIZE: u32 = 16;
+    let structure_size = u32::try_from(structure.len() * 4).expect("Structure should be small");
+    let header = [
+        0xD00D_FEED,
+        HEADER_SIZE + RESERVATIONS_SIZE + structure_size,
+        HEADER_SIZE + RESERVATIONS_SIZE,
+        HEADER_SIZE + RESERVATIONS_SIZE + structure_size,
+        HEADER_SIZE,
+        17,
+        16,
+        0,
+        0,
+        structure_size,
+    ];
+    let bytes: Vec<u8> = header
+        .into_iter()
+        .chain([0; 4])
+        .chain(structure.iter().copied())
+        .flat_map(u32::to_be_bytes)
+        .collect();
+    copy_aligned(&bytes)
+}
+
+/// Compiles the given source into an aligned blob
+pub fn compile(source: &str) -> Box<[u64]> {
+    copy_aligned(&compile_bytes(source))
+}
+
+/// Compiles the given source into the bytes of a blob, which may be modified before being aligned with `copy_aligned`
+pub fn compile_bytes(source: &str) -> Vec<u8> {
+    let tree = device_tree::dts::compiler::compile(source).expect("Source should compile");
+    tree.to_bytes().expect("Tree should serialize")
+}
+
+/// Returns the header field of the given blob at the given index
+pub fn header_field(blob: &[u8], index: usize) -> u32 {
+    let start = index * 4;
+    u32::from_be_bytes(
+        blob.get(start..start + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .expect("Blob should have a whole header"),
+    )
+}
+
+/// Overwrites the header field of the given blob at the given index
+pub fn set_header_field(blob: &mut [u8], index: usize, value: u32) {
+    let start = index * 4;
+    blob.get_mut(start..start + 4)
+        .expect("Blob should have a whole header")
+        .copy_from_slice(&value.to_be_bytes());
+}
+
+/// Builds the source of a tree with the given memory reservation block entries,
+/// whose root has the given number of address and size cells and the given contents,
+/// along with the `model`, `compatible`, and `/cpus` node required of every tree
+pub fn source(cells: u8, reservations: &str, contents: &str) -> String {
+    format!(
+        r#"/dts-v1/;
+{reservations}
+/ {{
+    #address-cells = <{cells}>;
+    #size-cells = <{cells}>;
+    model = "test";
+    compatible = "test";
+    cpus {{
+        #address-cells = <1>;
+        #size-cells = <0>;
+        cpu@0 {{ device_type = "cpu"; reg = <0>; }};
+    }};
+    {contents}
+}};
+"#
+    )
+}
@@ -0,0 +1,113 @@
+//! Tests for translating DMA addresses through the `dma-ranges` of ancestor buses
+
+mod common;
+
+use device_tree::dtb::DeviceTree;
+use device_tree::node::Node as _;
+
+/// A bus whose masters see the first 128 MiB of memory at 0x80000000 at address 0, with buses beneath it
+/// that translate through a further `dma-ranges`, through an empty `dma-ranges`, and without `dma-ranges`
+const SOURCE: &str = r#"memory@80000000 { device_type = "memory"; reg = <0x80000000 0x10000000>; };
+    soc {
+        #address-cells = <1>;
+        #size-cells = <1>;
+        ranges;
+        dma-ranges = <0x0 0x80000000 0x8000000>;
+
+        bus {
+            #address-cells = <1>;
+            #size-cells = <1>;
+            ranges;
+            dma-ranges = <0x10000000 0x0 0x10000000>;
+            dma@1000 { reg = <0x1000 0x100>; };
+        };
+        identity {
+            #address-cells = <1>;
+            #size-cells = <1>;
+            ranges;
+            dma-ranges;
+            dma@2000 { reg = <0x2000 0x100>; };
+        };
+        bare {
+            #address-cells = <1>;
+            #size-cells = <1>;
+            ranges;
+            dma@3000 { reg = <0x3000 0x100>; };
+        };
+    };
+    dma@4000 { reg = <0x4000 0x100>; };"#;
+
+#[test]
+fn multi_level_dma_ranges() {
+    let dtb = common::compile(&common::source(1, "", SOURCE));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let root = tree.root();
+    let master = root
+        .find_str(b"/soc/bus/dma@1000")
+        .expect("Master should exist");
+    assert_eq!(
+        master.dma_to_cpu(0x1000_1000_u64.into(), 0x1000),
+        Some(0x8000_1000_u64.into())
+    );
+    assert_eq!(
+        master.cpu_to_dma(0x8000_1000_u64.into(), 0x1000),
+        Some(0x1000_1000_u64.into())
+    );
+    // Below the range of the nearest bus
+    assert_eq!(master.dma_to_cpu(0x1000_u64.into(), 0x1000), None);
+    // Memory beyond the first 128 MiB is not visible to masters on the bus
+    assert_eq!(master.cpu_to_dma(0x8800_0000_u64.into(), 0x1000), None);
+}
+
+#[test]
+fn empty_and_missing_dma_ranges_are_identity() {
+    let dtb = common::compile(&common::source(1, "", SOURCE));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let root = tree.root();
+    for path in ["/soc/identity/dma@2000", "/soc/bare/dma@3000"] {
+        let master = root.find_str(path.as_bytes()).expect("Master should exist");
+        assert_eq!(
+            master.dma_to_cpu(0x1000_u64.into(), 0x1000),
+            Some(0x8000_1000_u64.into())
+        );
+        assert_eq!(
+            master.cpu_to_dma(0x8000_1000_u64.into(), 0x1000),
+            Some(0x1000_u64.into())
+        );
+    }
+
+    // No bus between the master and the root node translates its accesses
+    let master = root.find_str(b"/dma@4000").expect("Master should exist");
+    assert_eq!(
+        master.dma_to_cpu(0x9000_0000_u64.into(), 0x1000),
+        Some(0x9000_0000_u64.into())
+    );
+    assert_eq!(
+        master.cpu_to_dma(0x9000_0000_u64.into(), 0x1000),
+        Some(0x9000_0000_u64.into())
+    );
+}
+
+#[test]
+fn region_straddling_end_of_range() {
+    let dtb = common::compile(&common::source(1, "", SOURCE));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let root = tree.root();
+    let master = root
+        .find_str(b"/soc/bus/dma@1000")
+        .expect("Master should exist");
+    // Ends exactly at the end of the range of the outer bus
+    assert_eq!(
+        master.dma_to_cpu(0x17FF_F000_u64.into(), 0x1000),
+        Some(0x87FF_F000_u64.into())
+    );
+    assert_eq!(
+        master.cpu_to_dma(0x87FF_F000_u64.into(), 0x1000),
+        Some(0x17FF_F000_u64.into())
+    );
+    // Lies within the range of the nearest bus, but straddles the end of the range of the outer bus
+    assert_eq!(master.dma_to_cpu(0x17FF_F000_u64.into(), 0x2000), None);
+    assert_eq!(master.cpu_to_dma(0x87FF_F000_u64.into(), 0x2000), None);
+    // Straddles the end of the range of the nearest bus
+    assert_eq!(master.dma_to_cpu(0x1FFF_F000_u64.into(), 0x2000), None);
+}
@@ -0,0 +1,79 @@
+//! Tests for the entry points that parse a blob from raw bytes or a raw pointer
+
+mod common;
+
+use common::{field, header_field};
+use device_tree::dtb::{copy_aligned, DeviceTree, DeviceTreeError};
+use std::slice;
+
+/// Returns the bytes of the given aligned buffer
+fn bytes(buffer: &[u64]) -> &[u8] {
+    // SAFETY: Any `u64` is also valid as bytes, which have no alignment requirement
+    unsafe { slice::from_raw_parts(buffer.as_ptr().cast(), size_of_val(buffer)) }
+}
+
+/// Copies the given blob into aligned memory, after the given number of padding bytes and followed by the given number of trailing bytes
+fn padded(blob: &[u8], padding: usize, trailing: usize) -> Box<[u64]> {
+    let bytes: Vec<u8> = [0xAA]
+        .repeat(padding)
+        .into_iter()
+        .chain(blob.iter().copied())
+        .chain([0xAA].repeat(trailing))
+        .collect();
+    copy_aligned(&bytes)
+}
+
+#[test]
+fn aligned_byte_slice() {
+    let blob = common::compile_bytes(&common::source(1, "", ""));
+    let buffer = padded(&blob, 0, 0);
+    let tree = DeviceTree::from_byte_slice(bytes(&buffer)).expect("Aligned blob should parse");
+    assert_eq!(tree.header().total_size(), blob.len());
+}
+
+#[test]
+fn unaligned_byte_slice_is_rejected() {
+    let blob = common::compile_bytes(&common::source(1, "", ""));
+    for padding in [1, 4] {
+        let buffer = padded(&blob, padding, 0);
+        let unaligned = bytes(&buffer)
+            .get(padding..)
+            .expect("Buffer should hold the blob");
+        assert_eq!(unaligned.get(..blob.len()), Some(blob.as_slice()));
+        let err = DeviceTree::from_byte_slice(unaligned)
+            .expect_err("Unaligned blob should not be copied into alignment");
+        assert!(matches!(err.error(), DeviceTreeError::Alignment));
+    }
+}
+
+#[test]
+fn pointer_reads_size_from_header() {
+    let blob = common::compile_bytes(&common::source(1, "", ""));
+    // The bytes after the blob are not part of it, so do not need to parse
+    let buffer = padded(&blob, 0, 64);
+    // SAFETY: The buffer holds a whole blob and outlives the tree
+    let tree = unsafe { DeviceTree::from_ptr(buffer.as_ptr().cast()) }
+        .expect("Blob should parse from a pointer");
+    assert_eq!(tree.header().total_size(), blob.len());
+    assert_eq!(
+        tree.header().total_size(),
+        header_field(&blob, field::TOTAL_SIZE) as usize
+    );
+    assert_eq!(tree.root().cpus().iter().count(), 1);
+}
+
+#[test]
+fn pointer_checks_alignment_and_magic() {
+    let blob = common::compile_bytes(&common::source(1, "", ""));
+    let buffer = padded(&blob, 4, 4);
+    // SAFETY: The buffer holds a whole blob, after 4 bytes of padding
+    let err = unsafe { DeviceTree::from_ptr(bytes(&buffer).as_ptr().add(4)) }
+        .expect_err("Unaligned blob should not parse");
+    assert!(matches!(err.error(), DeviceTreeError::Alignment));
+
+    let buffer = padded(&[0; 8], 0, 0);
+    // SAFETY: The buffer holds 8 readable bytes
+    let err = unsafe { DeviceTree::from_ptr(buffer.as_ptr().cast()) }
+        .expect_err("Blob without magic should not parse");
+    assert!(matches!(err.error(), DeviceTreeError::Magic));
+}
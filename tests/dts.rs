@@ -0,0 +1,94 @@
+//! Tests for compiling device tree source into blobs that parse
+
+use device_tree::dtb::{copy_aligned, DeviceTree};
+use device_tree::node::Node as _;
+
+const SOURCE: &str = r#"/dts-v1/;
+
+/ {
+    #address-cells = <1>;
+    #size-cells = <1>;
+    model = "test";
+    compatible = "test";
+
+    cpus {
+        #address-cells = <1>;
+        #size-cells = <0>;
+        cpu@0 { device_type = "cpu"; reg = <0>; };
+    };
+
+    memory@0 { device_type = "memory"; reg = <0x0 0x10000000>; };
+
+    intc: interrupt-controller@1000 {
+        compatible = "test,intc";
+        reg = <0x1000 0x100>;
+        interrupt-controller;
+        #interrupt-cells = <1>;
+    };
+
+    uart@2000 {
+        compatible = "test,uart";
+        reg = <0x2000 0x100>;
+        interrupt-parent = <&intc>;
+        interrupts = <5>;
+        status = "disabled";
+    };
+
+    unused: unused@3000 { reg = <0x3000 0x100>; };
+    scratch@4000 { reg = <0x4000 0x100>; };
+};
+
+&intc { status = "okay"; };
+&{/uart@2000} { status = "okay"; };
+/delete-node/ &unused;
+/ { /delete-node/ scratch@4000; };
+"#;
+
+#[test]
+fn compiled_tree_parses() {
+    let tree = device_tree::dts::compiler::compile(SOURCE).expect("Source should compile");
+    let dtb = copy_aligned(&tree.to_bytes().expect("Tree should serialize"));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Compiled tree should parse strictly");
+    let root = tree.root();
+
+    let intc = root
+        .find_str(b"/interrupt-controller@1000")
+        .expect("Labelled node should exist");
+    assert_eq!(format!("{:?}", intc.status()), "Ok");
+    assert!(root
+        .phandles()
+        .iter()
+        .any(|(_, node)| node.path() == intc.path()));
+
+    let uart = root.find_str(b"/uart@2000").expect("Node should exist");
+    assert_eq!(format!("{:?}", uart.status()), "Ok");
+    let interrupts = uart
+        .resolve_interrupts(root)
+        .expect("Interrupts should resolve through the phandle reference");
+    assert_eq!(interrupts.len(), 1);
+    assert_eq!(
+        interrupts
+            .first()
+            .map(|interrupt| interrupt.controller().path()),
+        Some(intc.path())
+    );
+    assert_eq!(
+        interrupts.first().map(|interrupt| interrupt.specifier()),
+        Some([5].as_slice())
+    );
+
+    assert!(root.find_str(b"/unused@3000").is_none());
+    assert!(root.find_str(b"/scratch@4000").is_none());
+}
+
+#[test]
+fn emitted_source_recompiles() {
+    let tree = device_tree::dts::compiler::compile(SOURCE).expect("Source should compile");
+    let emitted = tree.dts().to_string();
+    let recompiled =
+        device_tree::dts::compiler::compile(&emitted).expect("Emitted source should compile");
+    assert_eq!(
+        recompiled.to_bytes().expect("Tree should serialize"),
+        tree.to_bytes().expect("Tree should serialize")
+    );
+}
@@ -0,0 +1,58 @@
+//! Tests for the editable tree
+
+mod common;
+
+use common::blob;
+use common::token::{BEGIN_NODE, END, END_NODE};
+use device_tree::dtb::{copy_aligned, DeviceTreeError};
+use device_tree::edit::{Node, Tree};
+
+#[test]
+fn empty_tree_round_trips() {
+    let tree = Tree::new(0);
+    let dtb = copy_aligned(&tree.to_bytes().expect("Tree should serialize"));
+    let parsed = Tree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(parsed.root().children().is_empty());
+}
+
+#[test]
+fn multiple_roots_are_rejected() {
+    let dtb = blob(&[BEGIN_NODE, 0, END_NODE, BEGIN_NODE, 0, END_NODE, END]);
+    assert!(matches!(
+        Tree::from_bytes(&dtb),
+        Err(DeviceTreeError::BadRoots(roots)) if roots.len() == 2
+    ));
+}
+
+#[test]
+fn named_root_is_rejected() {
+    let dtb = blob(&[BEGIN_NODE, u32::from_be_bytes(*b"a\0\0\0"), END_NODE, END]);
+    assert!(matches!(
+        Tree::from_bytes(&dtb),
+        Err(DeviceTreeError::BadRootName(_))
+    ));
+}
+
+#[test]
+fn child_by_node_name() {
+    let mut tree = Tree::new(0);
+    let root = tree.root_mut();
+    root.add_child(Node::new(b"memory@0").expect("Name should be valid"))
+        .expect("Child should be new");
+    root.add_child(Node::new(b"cpus").expect("Name should be valid"))
+        .expect("Child should be new");
+    assert_eq!(
+        root.child(b"memory").map(Node::name),
+        Some(b"memory@0".as_slice())
+    );
+
+    root.add_child(Node::new(b"memory@80000000").expect("Name should be valid"))
+        .expect("Child should be new");
+    // The node name alone is now ambiguous
+    assert!(root.child(b"memory").is_none());
+    assert!(root.remove_child(b"memory").is_none());
+    assert_eq!(
+        root.child(b"memory@80000000").map(Node::name),
+        Some(b"memory@80000000".as_slice())
+    );
+}
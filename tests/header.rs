@@ -0,0 +1,168 @@
+//! Tests for parsing and validating the header of a blob on its own
+
+mod common;
+
+use common::{field, header_field, set_header_field};
+use device_tree::dtb::DeviceTree;
+use device_tree::header::{Error, Header};
+
+/// Compiles a minimal tree into the bytes of a blob
+fn blob() -> Vec<u8> {
+    common::compile_bytes(&common::source(1, "", ""))
+}
+
+/// Parses the header of the given blob after overwriting the field at the given index
+fn parse_with(index: usize, value: u32) -> Result<Header, Error> {
+    let mut blob = blob();
+    set_header_field(&mut blob, index, value);
+    Header::parse(&blob)
+}
+
+#[test]
+fn valid_header() {
+    let mut blob = blob();
+    set_header_field(&mut blob, field::BOOT_CPUID_PHYS, 3);
+    let header = Header::parse(&blob).expect("Header should parse");
+    assert_eq!(header.total_size(), blob.len());
+    assert_eq!(
+        header.structure_offset(),
+        header_field(&blob, field::STRUCTURE_OFFSET) as usize
+    );
+    assert_eq!(
+        header.structure_size(),
+        header_field(&blob, field::STRUCTURE_SIZE) as usize
+    );
+    assert_eq!(
+        header.strings_offset(),
+        header_field(&blob, field::STRINGS_OFFSET) as usize
+    );
+    assert_eq!(
+        header.strings_size(),
+        header_field(&blob, field::STRINGS_SIZE) as usize
+    );
+    assert_eq!(
+        header.memory_reservations_offset(),
+        header_field(&blob, field::RESERVATIONS_OFFSET) as usize
+    );
+    assert_eq!(header.version(), DeviceTree::VERSION_PARSED);
+    assert_eq!(header.boot_cpuid_phys(), 3);
+}
+
+#[test]
+fn short_header() {
+    let blob = blob();
+    assert!(matches!(
+        Header::parse(
+            blob.get(..Header::SIZE - 1)
+                .expect("Blob should have a whole header")
+        ),
+        Err(Error::EoF)
+    ));
+}
+
+#[test]
+fn wrong_magic() {
+    assert!(matches!(
+        parse_with(field::MAGIC, 0xEDFE_0DD0),
+        Err(Error::Magic)
+    ));
+}
+
+#[test]
+fn newer_version() {
+    // A newer version is accepted as long as it remains compatible with the parsed version
+    let header = parse_with(field::VERSION, DeviceTree::VERSION_PARSED + 1)
+        .expect("Compatible newer version should parse");
+    assert_eq!(header.version(), DeviceTree::VERSION_PARSED + 1);
+
+    let mut blob = blob();
+    set_header_field(&mut blob, field::VERSION, DeviceTree::VERSION_PARSED + 2);
+    set_header_field(
+        &mut blob,
+        field::LAST_COMPATIBLE_VERSION,
+        DeviceTree::VERSION_PARSED + 1,
+    );
+    assert!(matches!(
+        Header::parse(&blob),
+        Err(Error::NewerVersion((version, last_compatible_version)))
+            if version == DeviceTree::VERSION_PARSED + 2
+                && last_compatible_version == DeviceTree::VERSION_PARSED + 1
+    ));
+}
+
+#[test]
+fn misaligned_blocks() {
+    let blob = blob();
+    let reservations = header_field(&blob, field::RESERVATIONS_OFFSET);
+    assert!(matches!(
+        parse_with(field::RESERVATIONS_OFFSET, reservations + 4),
+        Err(Error::Alignment)
+    ));
+    let structure = header_field(&blob, field::STRUCTURE_OFFSET);
+    assert!(matches!(
+        parse_with(field::STRUCTURE_OFFSET, structure + 2),
+        Err(Error::Alignment)
+    ));
+    let structure_size = header_field(&blob, field::STRUCTURE_SIZE);
+    assert!(matches!(
+        parse_with(field::STRUCTURE_SIZE, structure_size - 1),
+        Err(Error::Alignment)
+    ));
+}
+
+#[test]
+fn out_of_bounds_blocks() {
+    let blob = blob();
+    let total_size = header_field(&blob, field::TOTAL_SIZE);
+
+    let reservations = total_size.next_multiple_of(8);
+    assert!(matches!(
+        parse_with(field::RESERVATIONS_OFFSET, reservations),
+        Err(Error::ReservationsIndex(offset)) if offset == reservations as usize
+    ));
+
+    let structure = header_field(&blob, field::STRUCTURE_OFFSET);
+    let structure_size = total_size - structure + 4;
+    assert!(matches!(
+        parse_with(field::STRUCTURE_SIZE, structure_size),
+        Err(Error::StructIndex((offset, size)))
+            if offset == structure as usize && size == structure_size as usize
+    ));
+
+    let strings = header_field(&blob, field::STRINGS_OFFSET);
+    assert!(matches!(
+        parse_with(field::STRINGS_OFFSET, total_size),
+        Err(Error::StringsIndex((offset, _))) if offset == total_size as usize
+    ));
+    assert!(matches!(
+        parse_with(field::STRINGS_SIZE, total_size - strings + 1),
+        Err(Error::StringsIndex(_))
+    ));
+
+    // Shrinking the blob leaves the strings block out of bounds
+    assert!(matches!(
+        parse_with(field::TOTAL_SIZE, strings),
+        Err(Error::StringsIndex(_))
+    ));
+}
+
+#[test]
+fn overlapping_blocks() {
+    let blob = blob();
+    // The strings block begins within the structure block
+    let structure = header_field(&blob, field::STRUCTURE_OFFSET);
+    assert!(matches!(
+        parse_with(field::STRINGS_OFFSET, structure + 4),
+        Err(Error::Overlap)
+    ));
+    // The structure block begins within the header
+    let mut overlapping = blob.clone();
+    set_header_field(&mut overlapping, field::STRUCTURE_OFFSET, 8);
+    set_header_field(&mut overlapping, field::STRUCTURE_SIZE, 8);
+    assert!(matches!(Header::parse(&overlapping), Err(Error::Overlap)));
+    // The memory reservation block begins within the structure block
+    assert!(matches!(
+        parse_with(field::RESERVATIONS_OFFSET, structure.next_multiple_of(8)),
+        Err(Error::Overlap)
+    ));
+}
@@ -0,0 +1,311 @@
+//! Tests for resolving interrupts through the interrupt tree
+
+mod common;
+
+use device_tree::dtb::DeviceTree;
+use device_tree::node::interrupt::Error;
+use device_tree::node::Node as _;
+
+/// Controllers shared by the trees under test: a three-cell `gic` and a one-cell `intc`
+const CONTROLLERS: &str = r#"memory@0 { device_type = "memory"; reg = <0x0 0x10000000>; };
+    gic: interrupt-controller@1000 {
+        compatible = "arm,gic-400";
+        reg = <0x1000 0x100>;
+        interrupt-controller;
+        #interrupt-cells = <3>;
+    };
+    intc: interrupt-controller@2000 {
+        reg = <0x2000 0x100>;
+        interrupt-controller;
+        #interrupt-cells = <1>;
+    };"#;
+
+/// Compiles a tree with one-cell addresses and sizes, the shared controllers, and the given nodes
+fn compile(nodes: &str) -> Box<[u64]> {
+    common::compile(&common::source(
+        1,
+        "",
+        &format!("{CONTROLLERS}\n    {nodes}"),
+    ))
+}
+
+/// Resolves the interrupts of the node at the given path, as the path of each controller along with the specifier
+fn resolve<'dtb>(
+    tree: &'dtb DeviceTree<'dtb>,
+    path: &'dtb str,
+) -> Result<Vec<(String, Vec<u32>)>, Error> {
+    let root = tree.root();
+    let node = root.find_str(path.as_bytes()).expect("Node should exist");
+    Ok(node
+        .resolve_interrupts(root)?
+        .iter()
+        .map(|interrupt| {
+            (
+                interrupt.controller().path(),
+                interrupt.specifier().to_vec(),
+            )
+        })
+        .collect())
+}
+
+/// A nexus translating interrupts by the unit address and pin of the device, ignoring the low bits of the address
+const MASKED_NEXUS: &str = r"bus@10000 {
+        #address-cells = <1>;
+        #size-cells = <1>;
+        ranges;
+        #interrupt-cells = <1>;
+        interrupt-map-mask = <0xf000 0x7>;
+        interrupt-map = <0x1000 1 &gic 0 10 4>, <0x1000 2 &gic 0 11 4>, <0x2000 1 &intc 7>;
+
+        device@11000 { reg = <0x11000 0x100>; interrupts = <1>, <2>; };
+        device@12004 { reg = <0x12004 0x100>; interrupts = <9>; };
+        device@13000 { reg = <0x13000 0x100>; interrupts = <1>; };
+    };";
+
+#[test]
+fn nexus_translates_through_map() {
+    let dtb = compile(MASKED_NEXUS);
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        resolve(&tree, "/bus@10000/device@11000").expect("Interrupts should resolve"),
+        [
+            ("/interrupt-controller@1000".into(), vec![0, 10, 4]),
+            ("/interrupt-controller@1000".into(), vec![0, 11, 4]),
+        ]
+    );
+}
+
+#[test]
+fn nexus_masks_address_and_specifier() {
+    let dtb = compile(MASKED_NEXUS);
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    // The low bits of both the address and the pin are masked off
+    assert_eq!(
+        resolve(&tree, "/bus@10000/device@12004").expect("Interrupts should resolve"),
+        [("/interrupt-controller@2000".into(), vec![7])]
+    );
+
+    let root = tree.root();
+    let nexus = root.find_str(b"/bus@10000").expect("Nexus should exist");
+    assert_eq!(
+        nexus
+            .interrupts()
+            .interrupt_map_mask()
+            .expect("Mask should be valid")
+            .as_deref(),
+        Some([0xF000, 0x7].as_slice())
+    );
+    let map = nexus
+        .interrupts()
+        .interrupt_map(root)
+        .expect("Map should be valid")
+        .expect("Map should be present");
+    assert_eq!(map.len(), 3);
+    let entry = map.last().expect("Map should have entries");
+    assert_eq!(entry.child_address(), [0x2000]);
+    assert_eq!(entry.child_specifier(), [1]);
+    assert_eq!(entry.parent().path(), "/interrupt-controller@2000");
+    assert!(entry.parent_address().is_empty());
+    assert_eq!(entry.parent_specifier(), [7]);
+}
+
+#[test]
+fn unmatched_interrupt_is_unmapped() {
+    let dtb = compile(MASKED_NEXUS);
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(matches!(
+        resolve(&tree, "/bus@10000/device@13000"),
+        Err(Error::Unmapped)
+    ));
+}
+
+#[test]
+fn nexus_inherits_address_cells() {
+    // The nexus has no `#address-cells` of its own, so it uses the single cell of its parent rather than the default of two
+    let dtb = compile(
+        r"soc {
+        #address-cells = <1>;
+        #size-cells = <1>;
+        ranges;
+        nexus: nexus {
+            #interrupt-cells = <1>;
+            interrupt-map = <0x3000 1 &intc 21>;
+        };
+    };
+    leaf@3000 { reg = <0x3000 0x10>; interrupt-parent = <&nexus>; interrupts = <1>; };",
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        resolve(&tree, "/leaf@3000").expect("Interrupts should resolve"),
+        [("/interrupt-controller@2000".into(), vec![21])]
+    );
+
+    let root = tree.root();
+    let map = root
+        .find_str(b"/soc/nexus")
+        .expect("Nexus should exist")
+        .interrupts()
+        .interrupt_map(root)
+        .expect("Map should be valid")
+        .expect("Map should be present");
+    assert_eq!(
+        map.first().map(|entry| entry.child_address().to_vec()),
+        Some(vec![0x3000])
+    );
+}
+
+#[test]
+fn chained_nexuses() {
+    // The outer nexus forwards to the inner nexus, which has no address cells and forwards to the controller
+    let dtb = compile(
+        r"inner: inner {
+        #address-cells = <0>;
+        #interrupt-cells = <1>;
+        interrupt-map = <5 &gic 0 40 4>, <6 &intc 3>;
+    };
+    outer: outer {
+        #address-cells = <0>;
+        #interrupt-cells = <1>;
+        interrupt-map = <1 &inner 5>, <2 &inner 6>;
+    };
+    leaf { interrupt-parent = <&outer>; interrupts = <1>, <2>; };",
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        resolve(&tree, "/leaf").expect("Interrupts should resolve"),
+        [
+            ("/interrupt-controller@1000".into(), vec![0, 40, 4]),
+            ("/interrupt-controller@2000".into(), vec![3]),
+        ]
+    );
+}
+
+#[test]
+fn cyclic_nexuses_are_too_deep() {
+    let dtb = compile(
+        r"first: first {
+        #address-cells = <0>;
+        #interrupt-cells = <1>;
+        interrupt-map = <1 &second 1>;
+    };
+    second: second {
+        #address-cells = <0>;
+        #interrupt-cells = <1>;
+        interrupt-map = <1 &first 1>;
+    };
+    leaf { interrupt-parent = <&first>; interrupts = <1>; };",
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(matches!(resolve(&tree, "/leaf"), Err(Error::Depth)));
+}
+
+#[test]
+fn missing_interrupt_parent_is_bad_phandle() {
+    let dtb = compile("leaf { interrupt-parent = <0x1234>; interrupts = <1>; };");
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(matches!(
+        resolve(&tree, "/leaf"),
+        Err(Error::BadPHandle(0x1234))
+    ));
+}
+
+#[test]
+fn missing_map_parent_is_bad_phandle() {
+    let dtb = compile(
+        r"nexus: nexus {
+        #address-cells = <0>;
+        #interrupt-cells = <1>;
+        interrupt-map = <1 0x1234 1>;
+    };
+    leaf { interrupt-parent = <&nexus>; interrupts = <1>; };",
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(matches!(
+        resolve(&tree, "/leaf"),
+        Err(Error::BadPHandle(0x1234))
+    ));
+}
+
+/// A device with interrupts on both controllers, which are named, along with an `interrupts` property that is overridden
+const EXTENDED: &str = r#"device {
+        interrupt-parent = <&intc>;
+        interrupts = <99>;
+        interrupts-extended = <&gic 0 5 4>, <&intc 3>, <&gic 1 9 8>;
+        interrupt-names = "tx", "rx", "timer";
+    };"#;
+
+#[test]
+fn extended_interrupts_have_mixed_parents() {
+    let dtb = compile(EXTENDED);
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    // `interrupts-extended` takes precedence over `interrupts`
+    assert_eq!(
+        resolve(&tree, "/device").expect("Interrupts should resolve"),
+        [
+            ("/interrupt-controller@1000".into(), vec![0, 5, 4]),
+            ("/interrupt-controller@2000".into(), vec![3]),
+            ("/interrupt-controller@1000".into(), vec![1, 9, 8]),
+        ]
+    );
+
+    let root = tree.root();
+    let names: Vec<_> = root
+        .find_str(b"/device")
+        .expect("Device should exist")
+        .resolve_interrupts(root)
+        .expect("Interrupts should resolve")
+        .iter()
+        .map(|interrupt| interrupt.name().map(|name| name.to_bytes().to_vec()))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            Some(b"tx".to_vec()),
+            Some(b"rx".to_vec()),
+            Some(b"timer".to_vec())
+        ]
+    );
+}
+
+#[test]
+fn interrupts_are_found_by_name() {
+    let dtb = compile(EXTENDED);
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let root = tree.root();
+    let device = root.find_str(b"/device").expect("Device should exist");
+
+    let rx = device
+        .interrupt_by_name(root, b"rx")
+        .expect("Interrupt should resolve")
+        .expect("Interrupt should be named");
+    assert_eq!(rx.controller().path(), "/interrupt-controller@2000");
+    assert_eq!(rx.specifier(), [3]);
+    assert_eq!(rx.name(), Some(c"rx"));
+
+    let timer = device
+        .interrupt_by_name(root, b"timer")
+        .expect("Interrupt should resolve")
+        .expect("Interrupt should be named");
+    assert_eq!(timer.specifier(), [1, 9, 8]);
+
+    assert!(device
+        .interrupt_by_name(root, b"missing")
+        .expect("Lookup should succeed")
+        .is_none());
+}
+
+#[test]
+fn unnamed_interrupts_are_not_found_by_name() {
+    let dtb = compile("device { interrupt-parent = <&intc>; interrupts = <4>; };");
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let root = tree.root();
+    let device = root.find_str(b"/device").expect("Device should exist");
+    assert_eq!(
+        resolve(&tree, "/device").expect("Interrupts should resolve"),
+        [("/interrupt-controller@2000".into(), vec![4])]
+    );
+    assert!(device
+        .interrupt_by_name(root, b"tx")
+        .expect("Lookup should succeed")
+        .is_none());
+}
@@ -0,0 +1,236 @@
+//! Tests for computing the usable physical memory map
+
+mod common;
+
+use device_tree::dtb::DeviceTree;
+use device_tree::memory_map::{AllocationError, Reason};
+
+/// Compiles a tree with the given memory reservation block entries and root children into an aligned blob,
+/// where addresses and sizes are two cells each
+fn compile(reservations: &str, nodes: &str) -> Box<[u64]> {
+    common::compile(&common::source(2, reservations, nodes))
+}
+
+/// Returns the ranges excluded from the memory map of the given blob, as (address, size) pairs
+fn excluded(tree: &DeviceTree<'_>) -> Vec<(u64, u64)> {
+    tree.memory_map()
+        .excluded()
+        .iter()
+        .map(|exclusion| (exclusion.address(), exclusion.size()))
+        .collect()
+}
+
+#[test]
+fn adjacent_and_overlapping_memory_is_merged() {
+    let dtb = compile(
+        "",
+        r#"memory {
+        device_type = "memory";
+        reg = <0x0 0x0 0x0 0x1000>, <0x0 0x1000 0x0 0x1000>, <0x0 0x1800 0x0 0x1000>, <0x0 0x8000 0x0 0x1000>;
+    };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(tree.memory_map().free(), [(0x0, 0x2800), (0x8000, 0x1000)]);
+}
+
+#[test]
+fn reservation_covering_start() {
+    let dtb = compile(
+        "/memreserve/ 0x0 0x1000;",
+        r#"memory@0 { device_type = "memory"; reg = <0x0 0x0 0x0 0x10000>; };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let memory_map = tree.memory_map();
+    assert_eq!(memory_map.free(), [(0x1000, 0xF000)]);
+    assert_eq!(excluded(&tree), [(0x0, 0x1000)]);
+    assert!(matches!(
+        memory_map
+            .excluded()
+            .first()
+            .map(|exclusion| exclusion.reason()),
+        Some(Reason::Reservation)
+    ));
+    assert!(!memory_map.is_free(0xFFF));
+    assert!(memory_map.is_free(0x1000));
+}
+
+#[test]
+fn reservation_covering_end() {
+    let dtb = compile(
+        "",
+        r#"memory@0 { device_type = "memory"; reg = <0x0 0x0 0x0 0x10000>; };
+    reserved-memory {
+        #address-cells = <2>;
+        #size-cells = <2>;
+        ranges;
+        firmware@f000 { reg = <0x0 0xf000 0x0 0x1000>; no-map; };
+    };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let memory_map = tree.memory_map();
+    assert_eq!(memory_map.free(), [(0x0, 0xF000)]);
+    assert_eq!(excluded(&tree), [(0xF000, 0x1000)]);
+    assert!(matches!(
+        memory_map.excluded().first().map(|exclusion| exclusion.reason()),
+        Some(Reason::ReservedMemory(node)) if node.path() == "/reserved-memory/firmware@f000"
+    ));
+}
+
+#[test]
+fn reservation_splitting_memory() {
+    let dtb = compile(
+        "/memreserve/ 0x4000 0x1000;",
+        r#"memory@0 { device_type = "memory"; reg = <0x0 0x0 0x0 0x10000>; };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(tree.memory_map().free(), [(0x0, 0x4000), (0x5000, 0xB000)]);
+    assert_eq!(excluded(&tree), [(0x4000, 0x1000)]);
+}
+
+#[test]
+fn reservation_outside_memory_is_ignored() {
+    let dtb = compile(
+        "/memreserve/ 0x20000 0x1000;",
+        r#"memory@0 { device_type = "memory"; reg = <0x0 0x0 0x0 0x10000>; };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(tree.memory_map().free(), [(0x0, 0x10000)]);
+    assert!(excluded(&tree).is_empty());
+}
+
+#[test]
+fn memory_reaching_top_of_address_space() {
+    let dtb = compile(
+        "",
+        r#"memory@ffffffff00000000 { device_type = "memory"; reg = <0xffffffff 0x0 0x1 0x0>; };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let memory_map = tree.memory_map();
+    assert_eq!(memory_map.free(), [(0xFFFF_FFFF_0000_0000, 0x1_0000_0000)]);
+    assert!(memory_map.is_free(u64::MAX));
+}
+
+#[test]
+fn reservation_reaching_top_of_address_space() {
+    let dtb = compile(
+        "/memreserve/ 0xfffffffffffff000 0x1000;",
+        r#"memory@ffffffff00000000 { device_type = "memory"; reg = <0xffffffff 0x0 0x1 0x0>; };"#,
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let memory_map = tree.memory_map();
+    assert_eq!(memory_map.free(), [(0xFFFF_FFFF_0000_0000, 0xFFFF_F000)]);
+    assert_eq!(excluded(&tree), [(0xFFFF_FFFF_FFFF_F000, 0x1000)]);
+    assert!(!memory_map.is_free(u64::MAX));
+}
+
+/// Compiles a tree with a single megabyte of memory and the given children of `/reserved-memory`
+fn compile_reserved(regions: &str) -> Box<[u64]> {
+    compile(
+        "",
+        &format!(
+            r#"memory@0 {{ device_type = "memory"; reg = <0x0 0x0 0x0 0x100000>; }};
+    reserved-memory {{
+        #address-cells = <2>;
+        #size-cells = <2>;
+        ranges;
+        {regions}
+    }};"#
+        ),
+    )
+}
+
+/// Returns the path of each dynamic reservation of the given tree, in the order they were placed, along with where each was placed
+fn placements(tree: &DeviceTree<'_>) -> Vec<(String, Result<u64, AllocationError>)> {
+    tree.allocate_reserved_memory()
+        .placements()
+        .iter()
+        .map(|placement| (placement.node().path(), placement.address()))
+        .collect()
+}
+
+#[test]
+fn allocation_is_aligned() {
+    let dtb = compile_reserved("pool { size = <0x0 0x3000>; alignment = <0x0 0x10000>; };");
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        placements(&tree),
+        [("/reserved-memory/pool".into(), Ok(0xF0000))]
+    );
+    let allocation = tree.allocate_reserved_memory();
+    assert_eq!(
+        allocation.memory_map().free(),
+        [(0x0, 0xF0000), (0xF3000, 0xD000)]
+    );
+}
+
+#[test]
+fn allocation_within_alloc_ranges() {
+    // The first range is too small, so the second is used
+    let dtb = compile_reserved(
+        "pool { size = <0x0 0x1000>; alloc-ranges = <0x0 0x0 0x0 0x800>, <0x0 0x40000 0x0 0x10000>; };",
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        placements(&tree),
+        [("/reserved-memory/pool".into(), Ok(0x4F000))]
+    );
+}
+
+#[test]
+fn allocation_avoids_static_reservations() {
+    let dtb = compile_reserved(
+        "firmware@f0000 { reg = <0x0 0xf0000 0x0 0x10000>; no-map; };
+        pool { size = <0x0 0x1000>; };",
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        placements(&tree),
+        [("/reserved-memory/pool".into(), Ok(0xEF000))]
+    );
+}
+
+#[test]
+fn allocations_are_placed_in_name_order() {
+    let dtb = compile_reserved(
+        "second { size = <0x0 0x1000>; };
+        first { size = <0x0 0x1000>; };",
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        placements(&tree),
+        [
+            ("/reserved-memory/first".into(), Ok(0xFF000)),
+            ("/reserved-memory/second".into(), Ok(0xFE000)),
+        ]
+    );
+}
+
+#[test]
+fn allocation_failures() {
+    let dtb = compile_reserved(
+        "huge { size = <0x0 0x200000>; };
+        narrow { size = <0x0 0x2000>; alloc-ranges = <0x0 0x10000 0x0 0x1000>; };
+        odd { size = <0x0 0x1000>; alignment = <0x0 0x3000>; };",
+    );
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        placements(&tree),
+        [
+            (
+                "/reserved-memory/huge".into(),
+                Err(AllocationError::NoSpace)
+            ),
+            (
+                "/reserved-memory/narrow".into(),
+                Err(AllocationError::NoSpace)
+            ),
+            (
+                "/reserved-memory/odd".into(),
+                Err(AllocationError::Alignment(0x3000))
+            ),
+        ]
+    );
+    let allocation = tree.allocate_reserved_memory();
+    assert_eq!(allocation.unsatisfied().count(), 3);
+    assert_eq!(allocation.memory_map().free(), [(0x0, 0x100000)]);
+}
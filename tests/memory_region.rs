@@ -0,0 +1,119 @@
+//! Tests for parsing memory nodes
+
+mod common;
+
+use device_tree::dtb::{DeviceTree, ParseOptions};
+
+/// Compiles a tree with a single gigabyte of memory at address 0 and the given `initial-mapped-area`,
+/// where addresses and sizes are two cells each
+fn compile(initial_mapped_area: &str) -> Box<[u64]> {
+    common::compile(&common::source(
+        2,
+        "",
+        &format!(
+            r#"memory@0 {{
+        device_type = "memory";
+        reg = <0x0 0x0 0x0 0x40000000>;
+        initial-mapped-area = <{initial_mapped_area}>;
+    }};"#
+        ),
+    ))
+}
+
+/// Returns the `initial-mapped-area` entries of the single memory node of the given tree,
+/// as (effective address, physical address, size) triples
+fn areas<'dtb>(tree: &'dtb DeviceTree<'dtb>) -> Vec<(u64, u64, u32)> {
+    let [ref memory] = *tree.root().memory() else {
+        panic!("Tree should have a single memory node");
+    };
+    memory
+        .initial_mapped_areas()
+        .iter()
+        .map(|area| {
+            (
+                area.effective_address,
+                area.physical_address,
+                area.size.get(),
+            )
+        })
+        .collect()
+}
+
+/// Asserts that strict parsing fails with the given error of the memory node
+fn assert_rejected(dtb: &[u64], error: &str) {
+    let err = DeviceTree::from_bytes(dtb).expect_err("Tree should not parse strictly");
+    let err = format!("{:?}", err.error());
+    assert!(
+        err.starts_with("Node(Memory(") && err.ends_with(&format!(", {error}))")),
+        "Unexpected error {err}"
+    );
+}
+
+#[test]
+fn single_area() {
+    let dtb = compile("0xffffffff 0x0 0x0 0x100000 0x1000");
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(areas(&tree), [(0xFFFF_FFFF_0000_0000, 0x10_0000, 0x1000)]);
+}
+
+#[test]
+fn multiple_areas() {
+    let dtb =
+        compile("0xffffffff 0x0 0x0 0x100000 0x1000 0xffffffff 0x10000000 0x0 0x3ffff000 0x1000");
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        areas(&tree),
+        [
+            (0xFFFF_FFFF_0000_0000, 0x10_0000, 0x1000),
+            (0xFFFF_FFFF_1000_0000, 0x3FFF_F000, 0x1000),
+        ]
+    );
+}
+
+#[test]
+#[expect(
+    deprecated,
+    reason = "The single-area accessor is kept for compatibility"
+)]
+fn first_area() {
+    let dtb = compile("0x0 0x1000 0x0 0x1000 0x1000 0x0 0x2000 0x0 0x2000 0x1000");
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let memory = tree
+        .root()
+        .memory()
+        .first()
+        .expect("Memory should be present");
+    assert_eq!(
+        memory
+            .initial_mapped_area()
+            .map(|area| area.physical_address),
+        Some(0x1000)
+    );
+}
+
+#[test]
+fn partial_area_is_rejected() {
+    assert_rejected(&compile("0x0 0x0 0x0 0x0"), "InitialMappedArea");
+    assert_rejected(
+        &compile("0x0 0x0 0x0 0x0 0x1000 0x0 0x1000"),
+        "InitialMappedArea",
+    );
+}
+
+#[test]
+fn empty_area_is_rejected() {
+    assert_rejected(&compile("0x0 0x0 0x0 0x0 0x0"), "InitialMappedArea");
+}
+
+#[test]
+fn unmapped_area_is_rejected() {
+    // Straddles the end of memory
+    assert_rejected(&compile("0x0 0x0 0x0 0x3ffff000 0x2000"), "UnmappedArea");
+    // Lies entirely past the end of memory
+    let dtb = compile("0x0 0x0 0x0 0x40000000 0x1000");
+    assert_rejected(&dtb, "UnmappedArea");
+    let tree = DeviceTree::from_bytes_with(&dtb, ParseOptions::lenient())
+        .expect("Invalid memory should be tolerated when lenient");
+    assert_eq!(tree.warnings().len(), 1);
+    assert!(tree.root().memory().is_empty());
+}
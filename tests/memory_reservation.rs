@@ -0,0 +1,113 @@
+//! Tests for parsing the memory reservation block
+
+mod common;
+
+use common::{compile, compile_bytes, field, header_field, set_header_field};
+use device_tree::dtb::{copy_aligned, DeviceTree, DeviceTreeError, ParseOptions};
+use device_tree::header;
+use device_tree::memory_reservation::Error;
+
+/// Builds the source of a minimal tree with the given memory reservation block entries
+fn with_reservations(reservations: &str) -> String {
+    common::source(2, reservations, "")
+}
+
+/// Returns the error from parsing the given blob, both strictly and leniently, checking that both fail the same way
+fn parse_error(dtb: &[u64]) -> String {
+    let strict = DeviceTree::from_bytes(dtb).expect_err("Blob should not parse strictly");
+    let lenient = DeviceTree::from_bytes_with(dtb, ParseOptions::lenient())
+        .expect_err("Blob should not parse leniently");
+    assert_eq!(format!("{strict:?}"), format!("{lenient:?}"));
+    format!("{:?}", strict.into_error())
+}
+
+#[test]
+fn reservations_are_sorted() {
+    let dtb = compile(&with_reservations(
+        "/memreserve/ 0x2000 0x1000;\n/memreserve/ 0x0 0x1000;",
+    ));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let reservations = tree.memory_reservations();
+    assert_eq!(reservations.entries(), [(0x0, 0x1000), (0x2000, 0x1000)]);
+    assert!(reservations.contains(0xFFF));
+    assert!(!reservations.contains(0x1000));
+    assert!(reservations.contains(0x2FFF));
+    assert!(!reservations.contains(0x3000));
+}
+
+#[test]
+fn no_reservations() {
+    let dtb = compile(&with_reservations(""));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert!(tree.memory_reservations().entries().is_empty());
+}
+
+#[test]
+fn reservation_ending_at_top_of_address_space() {
+    let dtb = compile(&with_reservations(
+        "/memreserve/ 0xfffffffffffff000 0x1000;",
+    ));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    assert_eq!(
+        tree.memory_reservations().entries(),
+        [(0xFFFF_FFFF_FFFF_F000, 0x1000)]
+    );
+    assert!(tree.memory_reservations().contains(u64::MAX));
+}
+
+#[test]
+fn reservation_past_top_of_address_space() {
+    let dtb = compile(&with_reservations(
+        "/memreserve/ 0xfffffffffffff000 0x1001;",
+    ));
+    assert!(matches!(
+        DeviceTree::from_bytes(&dtb).map_err(|err| err.into_error()),
+        Err(DeviceTreeError::MemoryReservations(Error::Overflow((
+            0xFFFF_FFFF_FFFF_F000,
+            0x1001
+        ))))
+    ));
+}
+
+#[test]
+fn overlapping_reservations() {
+    let dtb = compile(&with_reservations(
+        "/memreserve/ 0x2000 0x1000;\n/memreserve/ 0x1000 0x1001;",
+    ));
+    assert!(matches!(
+        DeviceTree::from_bytes(&dtb).map_err(|err| err.into_error()),
+        Err(DeviceTreeError::MemoryReservations(Error::Overlap(
+            (0x1000, 0x1001),
+            (0x2000, 0x1000)
+        )))
+    ));
+}
+
+#[test]
+fn unterminated_reservations() {
+    let mut bytes = compile_bytes(&with_reservations("/memreserve/ 0x1000 0x1000;"));
+    // Overwrite the terminating entry, so that the block runs into the structure block
+    let terminator = usize::try_from(header_field(&bytes, field::RESERVATIONS_OFFSET))
+        .expect("Offset should fit")
+        + 16;
+    bytes
+        .get_mut(terminator..terminator + 16)
+        .expect("Terminator should be within the blob")
+        .copy_from_slice(&[0, 0, 0, 0, 0, 0, 0x30, 0, 0, 0, 0, 0, 0, 0, 0x10, 0]);
+    assert_eq!(
+        parse_error(&copy_aligned(&bytes)),
+        "MemoryReservations(Unterminated)"
+    );
+}
+
+#[test]
+fn reservations_out_of_bounds() {
+    let mut bytes = compile_bytes(&with_reservations(""));
+    // The offset must remain aligned, so that the block is found to be out of bounds rather than misaligned
+    let offset = header_field(&bytes, field::TOTAL_SIZE).next_multiple_of(8);
+    set_header_field(&mut bytes, field::RESERVATIONS_OFFSET, offset);
+    assert!(matches!(
+        DeviceTree::from_bytes(&copy_aligned(&bytes)).map_err(|err| err.into_error()),
+        Err(DeviceTreeError::Header(header::Error::ReservationsIndex(index))) if index == usize::try_from(offset).expect("Offset should fit")
+    ));
+}
@@ -0,0 +1,292 @@
+//! Tests for applying overlays
+
+use device_tree::edit::{Node, Tree};
+use device_tree::overlay::{self, Error};
+use std::ffi::CStr;
+
+/// Builds an overlay with one fragment targeting the root, whose device refers to a phandle within the overlay,
+/// and whose `__local_fixups__` lists that reference under the given node name
+fn overlay(fixup_node: &[u8]) -> Tree {
+    let mut tree = Tree::new(0);
+    let root = tree.root_mut();
+
+    let fragment = root
+        .add_child(Node::new(b"fragment@0").expect("Name should be valid"))
+        .expect("Child should be new");
+    fragment.set_property_str(c"target-path", c"/");
+    let contents = fragment
+        .add_child(Node::new(b"__overlay__").expect("Name should be valid"))
+        .expect("Child should be new");
+    contents
+        .child_or_insert(b"controller")
+        .expect("Name should be valid")
+        .set_property_u32(c"phandle", 1);
+    contents
+        .child_or_insert(b"device")
+        .expect("Name should be valid")
+        .set_property_u32(c"interrupt-parent", 1);
+
+    root.child_or_insert(b"__local_fixups__")
+        .and_then(|fixups| fixups.child_or_insert(b"fragment@0"))
+        .and_then(|fixups| fixups.child_or_insert(b"__overlay__"))
+        .and_then(|fixups| fixups.child_or_insert(fixup_node))
+        .expect("Names should be valid")
+        .set_property_u32(c"interrupt-parent", 0);
+    tree
+}
+
+/// Builds a base tree that already uses the given phandle
+fn base(phandle: u32) -> Tree {
+    let mut tree = Tree::new(0);
+    tree.root_mut()
+        .child_or_insert(b"clock")
+        .expect("Name should be valid")
+        .set_property_u32(c"phandle", phandle);
+    tree
+}
+
+#[test]
+fn local_references_are_renumbered() {
+    let mut tree = base(4);
+    overlay::apply(&mut tree, &overlay(b"device")).expect("Overlay should apply");
+    assert_eq!(
+        tree.find(b"/controller")
+            .and_then(|node| node.property_u32(c"phandle")),
+        Some(5)
+    );
+    assert_eq!(
+        tree.find(b"/device")
+            .and_then(|node| node.property_u32(c"interrupt-parent")),
+        Some(5)
+    );
+}
+
+#[test]
+fn missing_local_fixup_node_is_named() {
+    let mut tree = base(4);
+    assert!(matches!(
+        overlay::apply(&mut tree, &overlay(b"missing")),
+        Err(Error::LocalFixup(name)) if name.as_bytes() == b"missing"
+    ));
+}
+
+/// Compiles the given source into a tree
+fn compile(source: &str) -> Tree {
+    device_tree::dts::compiler::compile(source).expect("Source should compile")
+}
+
+/// Returns the given property of the node at the given path as a string
+fn property_str<'tree>(tree: &'tree Tree, path: &[u8], property: &CStr) -> Option<&'tree [u8]> {
+    tree.find(path)
+        .and_then(|node| node.property(property))
+        .and_then(|value| CStr::from_bytes_until_nul(value).ok())
+        .map(CStr::to_bytes)
+}
+
+/// Source of an overlay whose device refers to the base tree's `clk` label
+const CLOCK_CONSUMER: &str = r#"/dts-v1/;
+/ {
+    fragment@0 {
+        target-path = "/";
+        __overlay__ { device { clocks = <0xffffffff>; }; };
+    };
+    __fixups__ { clk = "/fragment@0/__overlay__/device:clocks:0"; };
+};
+"#;
+
+#[test]
+fn fixups_resolve_labels_of_base() {
+    let mut tree = compile(
+        r#"/dts-v1/;
+/ {
+    clock { phandle = <7>; };
+    __symbols__ { clk = "/clock"; };
+};
+"#,
+    );
+    overlay::apply(&mut tree, &compile(CLOCK_CONSUMER)).expect("Overlay should apply");
+    assert_eq!(
+        tree.find(b"/device")
+            .and_then(|node| node.property_u32(c"clocks")),
+        Some(7)
+    );
+}
+
+#[test]
+fn fixups_reuse_legacy_phandles() {
+    let mut tree = compile(
+        r#"/dts-v1/;
+/ {
+    clock { linux,phandle = <7>; };
+    __symbols__ { clk = "/clock"; };
+};
+"#,
+    );
+    overlay::apply(&mut tree, &compile(CLOCK_CONSUMER)).expect("Overlay should apply");
+    let clock = tree.find(b"/clock").expect("Clock should exist");
+    assert_eq!(clock.property_u32(c"phandle"), None);
+    assert_eq!(
+        tree.find(b"/device")
+            .and_then(|node| node.property_u32(c"clocks")),
+        Some(7)
+    );
+}
+
+#[test]
+fn fixups_assign_missing_phandles() {
+    let mut tree = compile(
+        r#"/dts-v1/;
+/ {
+    timer { phandle = <3>; };
+    clock { };
+    __symbols__ { clk = "/clock"; };
+};
+"#,
+    );
+    overlay::apply(&mut tree, &compile(CLOCK_CONSUMER)).expect("Overlay should apply");
+    assert_eq!(
+        tree.find(b"/clock")
+            .and_then(|node| node.property_u32(c"phandle")),
+        Some(4)
+    );
+    assert_eq!(
+        tree.find(b"/device")
+            .and_then(|node| node.property_u32(c"clocks")),
+        Some(4)
+    );
+}
+
+#[test]
+fn unresolved_fixup_label_is_rejected() {
+    let mut tree = compile(
+        r#"/dts-v1/;
+/ {
+    clock { phandle = <7>; };
+    __symbols__ { other = "/clock"; };
+};
+"#,
+    );
+    assert!(matches!(
+        overlay::apply(&mut tree, &compile(CLOCK_CONSUMER)),
+        Err(Error::UnresolvedLabel(label)) if label.as_bytes() == b"clk"
+    ));
+}
+
+#[test]
+fn fragment_targets_phandle() {
+    let mut tree = compile(
+        r#"/dts-v1/;
+/ {
+    soc { bus { phandle = <2>; }; };
+};
+"#,
+    );
+    let overlay = compile(
+        r#"/dts-v1/;
+/ {
+    fragment@0 {
+        target = <2>;
+        __overlay__ { status = "okay"; device { }; };
+    };
+};
+"#,
+    );
+    overlay::apply(&mut tree, &overlay).expect("Overlay should apply");
+    assert_eq!(
+        property_str(&tree, b"/soc/bus", c"status"),
+        Some(b"okay".as_slice())
+    );
+    assert!(tree.find(b"/soc/bus/device").is_some());
+}
+
+#[test]
+fn fragment_targets_alias_relative_path() {
+    let mut tree = compile(
+        r#"/dts-v1/;
+/ {
+    aliases { soc = "/soc"; };
+    soc { bus { }; };
+};
+"#,
+    );
+    let overlay = compile(
+        r#"/dts-v1/;
+/ {
+    fragment@0 {
+        target-path = "soc/bus";
+        __overlay__ { device { }; };
+    };
+    fragment@1 {
+        target-path = "soc";
+        __overlay__ { status = "okay"; };
+    };
+};
+"#,
+    );
+    overlay::apply(&mut tree, &overlay).expect("Overlay should apply");
+    assert!(tree.find(b"/soc/bus/device").is_some());
+    assert_eq!(
+        property_str(&tree, b"/soc", c"status"),
+        Some(b"okay".as_slice())
+    );
+}
+
+#[test]
+fn missing_target_is_rejected() {
+    let mut tree = compile("/dts-v1/;\n/ { };\n");
+    let overlay = compile(
+        r#"/dts-v1/;
+/ {
+    fragment@0 {
+        target-path = "missing/bus";
+        __overlay__ { status = "okay"; };
+    };
+};
+"#,
+    );
+    assert!(matches!(
+        overlay::apply(&mut tree, &overlay),
+        Err(Error::Target(name)) if *name == *b"fragment@0"
+    ));
+}
+
+#[test]
+fn symbols_are_rewritten_into_base() {
+    let mut tree = compile(
+        r#"/dts-v1/;
+/ {
+    soc { };
+    __symbols__ { soc = "/soc"; };
+};
+"#,
+    );
+    let overlay = compile(
+        r#"/dts-v1/;
+/ {
+    fragment@0 {
+        target-path = "/soc";
+        __overlay__ { device { sensor { }; }; };
+    };
+    fragment@1 {
+        target-path = "/";
+        __overlay__ { led { }; };
+    };
+    __symbols__ {
+        sensor = "/fragment@0/__overlay__/device/sensor";
+        soc_contents = "/fragment@0/__overlay__";
+        led = "/fragment@1/__overlay__/led";
+        root = "/fragment@1/__overlay__";
+        elsewhere = "/other";
+    };
+};
+"#,
+    );
+    overlay::apply(&mut tree, &overlay).expect("Overlay should apply");
+    let symbol = |label: &CStr| property_str(&tree, b"/__symbols__", label);
+    assert_eq!(symbol(c"soc"), Some(b"/soc".as_slice()));
+    assert_eq!(symbol(c"sensor"), Some(b"/soc/device/sensor".as_slice()));
+    assert_eq!(symbol(c"soc_contents"), Some(b"/soc".as_slice()));
+    assert_eq!(symbol(c"led"), Some(b"/led".as_slice()));
+    assert_eq!(symbol(c"root"), Some(b"/".as_slice()));
+    assert_eq!(symbol(c"elsewhere"), None);
+}
@@ -0,0 +1,184 @@
+//! Tests for parsing whole device tree blobs
+
+use device_tree::dtb::{copy_aligned, DeviceTree, OwnedDeviceTree, ParseOptions};
+use device_tree::node::reserved_memory::Range;
+
+mod common;
+
+use common::compile;
+
+/// Wraps the given nodes in a root node with one-cell addresses and sizes and a single memory node
+fn with_root(nodes: &str) -> String {
+    common::source(
+        1,
+        "",
+        &format!(
+            r#"memory@0 {{ device_type = "memory"; reg = <0x0 0x10000000>; }};
+    {nodes}"#
+        ),
+    )
+}
+
+#[test]
+fn raspberry_pi_4() {
+    let dtb = copy_aligned(include_bytes!("../bcm2711-rpi-4-b.dtb"));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Bundled blob should parse strictly");
+    assert!(tree.warnings().is_empty());
+    assert_eq!(tree.root().cpus().iter().count(), 4);
+}
+
+#[test]
+fn minimal_tree() {
+    let dtb = compile(&with_root(""));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Minimal tree should parse");
+    assert_eq!(tree.root().memory().len(), 1);
+}
+
+#[test]
+fn zero_cell_reg_is_rejected() {
+    let dtb = compile(&with_root(
+        "bus { #address-cells = <0>; #size-cells = <0>; device { reg = <1>; }; };",
+    ));
+    assert!(DeviceTree::from_bytes_with(&dtb, ParseOptions::strict()).is_err());
+}
+
+#[test]
+fn zero_cell_empty_reg_is_accepted() {
+    let dtb = compile(&with_root(
+        "bus { #address-cells = <0>; #size-cells = <0>; device { reg; }; };",
+    ));
+    assert!(DeviceTree::from_bytes_with(&dtb, ParseOptions::strict()).is_ok());
+}
+
+#[test]
+fn zero_cell_ranges_is_rejected() {
+    let dtb = compile(&with_root(
+        "bus { #address-cells = <0>; #size-cells = <0>; bridge { #address-cells = <0>; #size-cells = <0>; ranges = <1>; }; };",
+    ));
+    assert!(DeviceTree::from_bytes_with(&dtb, ParseOptions::strict()).is_err());
+}
+
+#[test]
+fn alias_to_reserved_memory_is_not_dangling() {
+    let dtb = compile(&with_root(
+        r#"aliases { region = "/reserved-memory/region@1000000"; cpu = "/cpus/cpu@0"; };
+    reserved-memory {
+        #address-cells = <1>;
+        #size-cells = <1>;
+        ranges;
+        region@1000000 { reg = <0x1000000 0x1000>; no-map; };
+    };"#,
+    ));
+    let tree =
+        DeviceTree::from_bytes(&dtb).expect("Aliases to non-device nodes should be tolerated");
+    assert_eq!(
+        tree.root()
+            .reserved_memory()
+            .map(|regions| regions.iter().count()),
+        Some(1)
+    );
+}
+
+#[test]
+fn dangling_alias_is_rejected() {
+    let dtb = compile(&with_root(r#"aliases { missing = "/missing"; };"#));
+    assert!(DeviceTree::from_bytes(&dtb).is_err());
+    let tree = DeviceTree::from_bytes_with(&dtb, ParseOptions::lenient())
+        .expect("Dangling aliases should be tolerated when lenient");
+    assert_eq!(tree.warnings().len(), 1);
+}
+
+#[test]
+fn owned_tree_with_options() {
+    let dtb = compile(&with_root(r#"aliases { missing = "/missing"; };"#));
+    let bytes: Vec<u8> = dtb.iter().flat_map(|word| word.to_ne_bytes()).collect();
+    assert!(OwnedDeviceTree::copy_from(&bytes).is_err());
+    let tree = OwnedDeviceTree::copy_from_with(&bytes, ParseOptions::lenient())
+        .expect("Dangling aliases should be tolerated when lenient");
+    assert_eq!(tree.tree().warnings().len(), 1);
+}
+
+#[test]
+fn addresses_compare_by_value() {
+    let dtb = compile(&with_root(
+        r"reserved-memory {
+        #address-cells = <1>;
+        #size-cells = <1>;
+        ranges;
+        region@1000000 { reg = <0x1000000 0x1000>; no-map; };
+    };",
+    ));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let regions = tree
+        .root()
+        .reserved_memory()
+        .expect("Reserved memory should be present");
+    let (_, region) = regions.iter().next().expect("Region should be present");
+    let Range::Static(ref regs) = *region.memory() else {
+        panic!("Region should be static");
+    };
+    let &[(address, size)] = regs.as_ref() else {
+        panic!("Region should have a single entry");
+    };
+    // The region's address is a single cell, while converted addresses are two cells
+    assert_eq!(address.cells(), 1);
+    assert_eq!(address, 0x100_0000_u64.into());
+    assert!(address < 0x100_0001_u64.into());
+    assert_eq!(size, 0x1000);
+}
+
+/// Asserts that the given blob fails to parse strictly with an error mentioning the given variant,
+/// but parses leniently with a single warning
+fn assert_lenient<'dtb>(dtb: &'dtb [u64], error: &str) -> DeviceTree<'dtb> {
+    let err = DeviceTree::from_bytes_with(dtb, ParseOptions::strict())
+        .expect_err("Tree should not parse strictly");
+    let err = format!("{:?}", err.error());
+    assert!(err.contains(error), "Unexpected error {err}");
+    let tree = DeviceTree::from_bytes_with(dtb, ParseOptions::lenient())
+        .expect("Tree should parse leniently");
+    assert_eq!(tree.warnings().len(), 1);
+    tree
+}
+
+#[test]
+fn missing_model_is_tolerated() {
+    let dtb = compile(&with_root("/delete-property/ model;"));
+    let tree = assert_lenient(&dtb, "Model");
+    assert_eq!(tree.root().cpus().iter().count(), 1);
+}
+
+#[test]
+fn cpu_without_device_type_is_tolerated() {
+    let dtb = compile(&with_root(
+        "cpus { cpu@0 { /delete-property/ device_type; }; };",
+    ));
+    let tree = assert_lenient(&dtb, "DeviceType");
+    assert_eq!(tree.root().cpus().iter().count(), 1);
+}
+
+#[test]
+fn three_cell_cpu_addresses_are_accepted() {
+    let dtb = compile(&with_root(
+        "cpus { #address-cells = <3>; cpu@0 { reg = <0x0 0x0 0x0>; }; };",
+    ));
+    let tree = DeviceTree::from_bytes_with(&dtb, ParseOptions::strict())
+        .expect("Three-cell CPU addresses should parse strictly");
+    assert_eq!(tree.root().cpus().iter().count(), 1);
+    let tree = DeviceTree::from_bytes_with(&dtb, ParseOptions::lenient())
+        .expect("Three-cell CPU addresses should parse leniently");
+    assert!(tree.warnings().is_empty());
+}
+
+#[test]
+fn reserved_memory_with_translating_ranges_is_tolerated() {
+    let dtb = compile(&with_root(
+        "reserved-memory {
+        #address-cells = <1>;
+        #size-cells = <1>;
+        ranges = <0x0 0x1000000 0x1000000>;
+        region@0 { reg = <0x0 0x1000>; no-map; };
+    };",
+    ));
+    let tree = assert_lenient(&dtb, "CellsMismatch");
+    assert!(tree.root().reserved_memory().is_none());
+}
@@ -0,0 +1,223 @@
+//! Tests for decoding PCI addresses and host bridges
+
+mod common;
+
+use device_tree::dtb::{copy_aligned, DeviceTree};
+use device_tree::node::pci::{Address, Error, HostBridge, Pin, Space};
+use device_tree::node::Node as _;
+
+#[test]
+fn address_decoding() {
+    // Non-relocatable, prefetchable, 64-bit memory at bus 1, device 5, function 2, register 0x10
+    let address = Address::try_from([0xC301_2A10, 0x1, 0x2000].as_slice())
+        .expect("Three cells should decode");
+    assert_eq!(address.space(), Space::Memory64);
+    assert!(address.prefetchable());
+    assert!(!address.relocatable());
+    assert!(!address.aliased());
+    assert_eq!(address.bus(), 1);
+    assert_eq!(address.device(), 5);
+    assert_eq!(address.function(), 2);
+    assert_eq!(address.register(), 0x10);
+    assert_eq!(address.address(), 0x1_0000_2000);
+    assert_eq!(address.to_cells(), [0xC301_2A10, 0x1, 0x2000]);
+
+    let io = Address::new(0x2100_0000, 0x1000);
+    assert_eq!(io.space(), Space::Io);
+    assert!(!io.prefetchable());
+    assert!(io.relocatable());
+    assert!(io.aliased());
+    assert_eq!(Address::new(0, 0).space(), Space::Configuration);
+    assert_eq!(Address::new(0x0200_0000, 0).space(), Space::Memory32);
+
+    assert!(matches!(
+        Address::try_from([0, 0].as_slice()),
+        Err(Error::Cells(2))
+    ));
+}
+
+#[test]
+fn pins() {
+    assert_eq!(Pin::try_from(1).ok(), Some(Pin::IntA));
+    assert_eq!(Pin::try_from(4).ok(), Some(Pin::IntD));
+    assert_eq!(Pin::IntC.number(), 3);
+    assert!(matches!(Pin::try_from(0), Err(Error::Pin(0))));
+    assert!(matches!(Pin::try_from(5), Err(Error::Pin(5))));
+}
+
+#[test]
+fn raspberry_pi_4_host_bridge() {
+    let dtb = copy_aligned(include_bytes!("../bcm2711-rpi-4-b.dtb"));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Bundled blob should parse");
+    let root = tree.root();
+    let node = root
+        .find_str(b"/scb/pcie@7d500000")
+        .expect("PCIe node should exist");
+    let bridge = HostBridge::new(&node, root).expect("Host bridge should decode");
+
+    // Relocatable, non-prefetchable 32-bit memory at 0xC0000000 appears at 0x600000000 to the CPU
+    let [range] = bridge.ranges() else {
+        panic!("Host bridge should have a single range");
+    };
+    assert_eq!(range.pci_address(), Address::new(0x0200_0000, 0xC000_0000));
+    assert_eq!(range.pci_address().space(), Space::Memory32);
+    assert!(range.pci_address().relocatable());
+    assert!(!range.pci_address().prefetchable());
+    assert_eq!(range.parent_address(), 0x6_0000_0000_u64.into());
+    assert_eq!(range.length(), 0x4000_0000);
+
+    // The first 3 GiB of memory is visible to bus masters at the same addresses
+    let [dma_range] = bridge.dma_ranges() else {
+        panic!("Host bridge should have a single DMA range");
+    };
+    assert_eq!(dma_range.pci_address(), Address::new(0x0200_0000, 0));
+    assert_eq!(dma_range.parent_address(), 0_u64.into());
+    assert_eq!(dma_range.length(), 0xC000_0000);
+
+    // Each pin is routed to its own SPI of the GIC
+    let map: Vec<_> = bridge
+        .interrupt_map()
+        .iter()
+        .map(|entry| {
+            assert_eq!(entry.address(), Address::new(0, 0));
+            assert_eq!(entry.parent().path(), "/soc/interrupt-controller@40041000");
+            (entry.pin(), entry.parent_specifier().to_vec())
+        })
+        .collect();
+    assert_eq!(
+        map,
+        [
+            (Pin::IntA, vec![0, 143, 4]),
+            (Pin::IntB, vec![0, 144, 4]),
+            (Pin::IntC, vec![0, 145, 4]),
+            (Pin::IntD, vec![0, 146, 4]),
+        ]
+    );
+}
+
+#[test]
+fn raspberry_pi_4_translation() {
+    let dtb = copy_aligned(include_bytes!("../bcm2711-rpi-4-b.dtb"));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Bundled blob should parse");
+    let root = tree.root();
+    let node = root
+        .find_str(b"/scb/pcie@7d500000")
+        .expect("PCIe node should exist");
+    let bridge = HostBridge::new(&node, root).expect("Host bridge should decode");
+    let [range] = bridge.ranges() else {
+        panic!("Host bridge should have a single range");
+    };
+
+    // The space code of the address is significant, but not its other flags
+    let bar = Address::new(0x8201_0010, 0xC000_1000);
+    assert_eq!(
+        range.translate(&bar, 0x1000),
+        Some(0x6_0000_1000_u64.into())
+    );
+    assert_eq!(
+        bridge.translate(&bar, 0x1000),
+        Some(0x6_0000_1000_u64.into())
+    );
+    let end = Address::new(0x0200_0000, 0xFFFF_F000);
+    assert_eq!(
+        range.translate(&end, 0x1000),
+        Some(0x6_3FFF_F000_u64.into())
+    );
+    // Regions straddling either end of the range, or in a different space, are not translated
+    assert_eq!(range.translate(&end, 0x2000), None);
+    assert_eq!(
+        range.translate(&Address::new(0x0200_0000, 0xBFFF_F000), 0x2000),
+        None
+    );
+    assert_eq!(
+        bridge.translate(&Address::new(0x0300_0000, 0xC000_1000), 0x1000),
+        None
+    );
+    assert_eq!(
+        bridge.translate(&Address::new(0x0100_0000, 0xC000_1000), 0x1000),
+        None
+    );
+}
+
+#[test]
+fn raspberry_pi_4_interrupt_mapping() {
+    let dtb = copy_aligned(include_bytes!("../bcm2711-rpi-4-b.dtb"));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Bundled blob should parse");
+    let root = tree.root();
+    let node = root
+        .find_str(b"/scb/pcie@7d500000")
+        .expect("PCIe node should exist");
+    let bridge = HostBridge::new(&node, root).expect("Host bridge should decode");
+
+    // The mask ignores the address entirely, so any function's pin maps to the same interrupt
+    let function = Address::new(0x0001_2800, 0);
+    for (pin, spi) in [
+        (Pin::IntA, 143),
+        (Pin::IntB, 144),
+        (Pin::IntC, 145),
+        (Pin::IntD, 146),
+    ] {
+        let entry = bridge
+            .map_interrupt(&function, pin)
+            .expect("Every pin should be mapped");
+        assert_eq!(entry.pin(), pin);
+        assert_eq!(entry.parent_specifier(), [0, spi, 4]);
+    }
+}
+
+#[test]
+fn non_pci_node_is_not_a_host_bridge() {
+    let dtb = copy_aligned(include_bytes!("../bcm2711-rpi-4-b.dtb"));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Bundled blob should parse");
+    let root = tree.root();
+    let node = root.find_str(b"/scb").expect("Bus should exist");
+    assert!(matches!(HostBridge::new(&node, root), Err(Error::Bridge)));
+}
+
+#[test]
+fn interrupt_map_mask_selects_device_and_pin() {
+    let dtb = common::compile(&common::source(
+        1,
+        "",
+        r#"memory@0 { device_type = "memory"; reg = <0x0 0x10000000>; };
+    intc: interrupt-controller@1000 {
+        reg = <0x1000 0x100>;
+        interrupt-controller;
+        #interrupt-cells = <1>;
+    };
+    pcie@40000000 {
+        device_type = "pci";
+        reg = <0x40000000 0x1000>;
+        #address-cells = <3>;
+        #size-cells = <2>;
+        #interrupt-cells = <1>;
+        ranges = <0x42000000 0x0 0x0 0x50000000 0x0 0x10000000>;
+        interrupt-map-mask = <0x1800 0x0 0x0 0x7>;
+        interrupt-map = <0x0000 0x0 0x0 1 &intc 10>, <0x0800 0x0 0x0 1 &intc 11>, <0x0800 0x0 0x0 2 &intc 12>;
+    };"#,
+    ));
+    let tree = DeviceTree::from_bytes(&dtb).expect("Tree should parse");
+    let root = tree.root();
+    let node = root
+        .find_str(b"/pcie@40000000")
+        .expect("PCIe node should exist");
+    let bridge = HostBridge::new(&node, root).expect("Host bridge should decode");
+    assert!(bridge
+        .ranges()
+        .first()
+        .is_some_and(|range| range.pci_address().prefetchable()));
+
+    let spi = |hi, pin| {
+        bridge
+            .map_interrupt(&Address::new(hi, 0), pin)
+            .map(|entry| entry.parent_specifier().to_vec())
+    };
+    // Device 1, whose function, bus, and register numbers are masked off
+    assert_eq!(spi(0x0800, Pin::IntA), Some(vec![11]));
+    assert_eq!(spi(0x0003_0A10, Pin::IntA), Some(vec![11]));
+    assert_eq!(spi(0x0800, Pin::IntB), Some(vec![12]));
+    assert_eq!(spi(0x0000, Pin::IntA), Some(vec![10]));
+    // Device 2 has no entries, and device 0 has no entry for `INTB`
+    assert_eq!(spi(0x1000, Pin::IntA), None);
+    assert_eq!(spi(0x0000, Pin::IntB), None);
+}
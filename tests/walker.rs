@@ -0,0 +1,166 @@
+//! Tests for walking a blob without allocating
+
+mod common;
+
+use common::blob;
+use common::token::{BEGIN_NODE, END, END_NODE};
+use device_tree::walker::{Error, Event, Walker, MAX_DEPTH};
+
+const SOURCE: &str = r#"/dts-v1/;
+/ {
+    #address-cells = <1>;
+    soc {
+        serial@1000 { reg = <0x1000 0x100>; };
+    };
+    memory@0 { device_type = "memory"; reg = <0x0 0x1000>; };
+    chosen { bootargs = "console=ttyS0"; };
+};
+"#;
+
+/// A simplified event, along with the depth and path of the walker after producing it
+#[derive(Debug, PartialEq)]
+enum Step {
+    Begin(String, usize, String),
+    End(usize, String),
+    Property(String, usize, String),
+}
+
+/// Walks the whole of the given blob, recording each event
+fn steps(dtb: &[u64]) -> Result<Vec<Step>, Error> {
+    let mut walker = Walker::new(dtb).expect("Header should be valid");
+    let mut steps = Vec::new();
+    while let Some(event) = walker.next().transpose()? {
+        let (depth, path) = (walker.depth(), walker.path().to_string());
+        steps.push(match event {
+            Event::BeginNode(name) => {
+                Step::Begin(String::from_utf8_lossy(name).into(), depth, path)
+            }
+            Event::EndNode => Step::End(depth, path),
+            Event::Property(property) => {
+                Step::Property(property.name().to_string_lossy().into(), depth, path)
+            }
+            _ => panic!("Unexpected event {event:?}"),
+        });
+    }
+    Ok(steps)
+}
+
+#[test]
+fn event_sequence() {
+    let dtb = common::compile(SOURCE);
+    let begin = |name: &str, depth, path: &str| Step::Begin(name.into(), depth, path.into());
+    let end = |depth, path: &str| Step::End(depth, path.into());
+    let property = |name: &str, depth, path: &str| Step::Property(name.into(), depth, path.into());
+    assert_eq!(
+        steps(&dtb).expect("Walk should succeed"),
+        [
+            begin("", 1, "/"),
+            property("#address-cells", 1, "/"),
+            begin("soc", 2, "/soc"),
+            begin("serial@1000", 3, "/soc/serial@1000"),
+            property("reg", 3, "/soc/serial@1000"),
+            end(2, "/soc"),
+            end(1, "/"),
+            begin("memory@0", 2, "/memory@0"),
+            property("device_type", 2, "/memory@0"),
+            property("reg", 2, "/memory@0"),
+            end(1, "/"),
+            begin("chosen", 2, "/chosen"),
+            property("bootargs", 2, "/chosen"),
+            end(1, "/"),
+            end(0, "/"),
+        ]
+    );
+}
+
+#[test]
+fn path_comparison() {
+    let dtb = common::compile(SOURCE);
+    let mut walker = Walker::new(&dtb).expect("Header should be valid");
+    assert!(walker
+        .find_node(b"/soc/serial@1000")
+        .expect("Walk should succeed"));
+    assert!(walker.path() == b"/soc/serial@1000".as_slice());
+    // Components without a unit address match any unit address
+    assert!(walker.path() == b"/soc/serial".as_slice());
+    assert!(walker.path() != b"/soc".as_slice());
+    assert!(walker.path() != b"/soc/serial@1000/extra".as_slice());
+    assert!(walker.path() != b"soc/serial@1000".as_slice());
+    assert_eq!(
+        walker.path().components().collect::<Vec<_>>(),
+        [b"soc".as_slice(), b"serial@1000"]
+    );
+}
+
+#[test]
+fn find_node() {
+    let dtb = common::compile(SOURCE);
+    let mut walker = Walker::new(&dtb).expect("Header should be valid");
+    assert!(walker.find_node(b"/memory").expect("Walk should succeed"));
+    assert_eq!(walker.depth(), 2);
+    assert_eq!(walker.path().to_string(), "/memory@0");
+    // The properties of the found node are next
+    let Some(Ok(Event::Property(property))) = walker.next() else {
+        panic!("A property should be next");
+    };
+    assert_eq!(property.name(), c"device_type");
+    assert_eq!(property.as_c_str(), Some(c"memory"));
+    let Some(Ok(Event::Property(reg))) = walker.next() else {
+        panic!("A property should be next");
+    };
+    assert_eq!(
+        reg.cells(1).map(Iterator::collect::<Vec<_>>),
+        Some(vec![0, 0x1000])
+    );
+
+    // Searching continues from the current position
+    assert!(walker.find_node(b"/chosen").expect("Walk should succeed"));
+    assert!(!walker.find_node(b"/soc").expect("Walk should succeed"));
+    assert!(walker.next().is_none());
+
+    let mut walker = Walker::new(&dtb).expect("Header should be valid");
+    assert!(!walker.find_node(b"/missing").expect("Walk should succeed"));
+}
+
+/// Builds a blob whose root has the given number of nested descendants, each named `a`
+fn nested(descendants: usize) -> Box<[u64]> {
+    let name = u32::from_be_bytes(*b"a\0\0\0");
+    let structure: Vec<u32> = [BEGIN_NODE, 0]
+        .into_iter()
+        .chain([BEGIN_NODE, name].repeat(descendants))
+        .chain([END_NODE].repeat(descendants + 1))
+        .chain([END])
+        .collect();
+    blob(&structure)
+}
+
+#[test]
+fn max_depth() {
+    let dtb = nested(MAX_DEPTH - 1);
+    let deepest = steps(&dtb).expect("Nodes at the maximum depth should be walked");
+    assert!(deepest.contains(&Step::Begin(
+        "a".into(),
+        MAX_DEPTH,
+        "/a".repeat(MAX_DEPTH - 1)
+    )));
+
+    let dtb = nested(MAX_DEPTH);
+    assert!(matches!(steps(&dtb), Err(Error::Depth)));
+    let mut walker = Walker::new(&dtb).expect("Header should be valid");
+    assert!(matches!(walker.find_node(b"/missing"), Err(Error::Depth)));
+    // The walker stops after an error
+    assert!(walker.next().is_none());
+}
+
+#[test]
+fn mismatched_nodes() {
+    let extra_end = blob(&[BEGIN_NODE, 0, END_NODE, END_NODE, END]);
+    assert!(matches!(steps(&extra_end), Err(Error::MismatchedNodes)));
+
+    let unclosed = blob(&[BEGIN_NODE, 0, BEGIN_NODE, 0, END_NODE, END]);
+    assert!(matches!(steps(&unclosed), Err(Error::MismatchedNodes)));
+
+    let mut walker = Walker::new(&unclosed).expect("Header should be valid");
+    assert!(matches!(walker.next(), Some(Ok(Event::BeginNode(_)))));
+    assert!(matches!(walker.skip_node(), Err(Error::MismatchedNodes)));
+}